@@ -38,6 +38,6 @@ fn bench_render_mandelbrot_set_default_view_720p_1x_ssaa(b: &mut Bencher) {
     let coloring_function = TrueColor::new_from_bernstein_polynomials;
     //Benchmark
     b.iter(|| {
-        rendering::render_complex_plane_into_buffer(&mut p, &c, &m, supersampling_amount, coloring_function);
+        rendering::render_complex_plane_into_buffer(&mut p, &c, &m, supersampling_amount, coloring_function).unwrap();
     })
 }
\ No newline at end of file
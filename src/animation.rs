@@ -0,0 +1,208 @@
+use std::{fs::{self, File}, io::{BufReader, BufWriter}, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{checkpoint::Checkpoint, complex_plane::{ComplexPlane, View}, config::Config, error::Error, coloring::{ColoringAlgorithm, ColorChannelMapping}, mandelbrot_set::MandelbrotSet,
+    metadata::Metadata, pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane}, rendering};
+
+pub mod keyframes;
+
+///Easing functions, mapping the normalized animation time t ∈ [0, 1] to the normalized animation progress ∈ [0, 1]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    ///Starts slow, ends fast
+    EaseIn,
+    ///Starts fast, ends slow
+    EaseOut,
+    ///Starts slow and ends slow (smoothstep)
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+fn default_frame_rate() -> u16 {
+    30
+}
+
+///A zoom animation from a `start` `View` to a `target` `View`, read from a JSON animation file, e.g.:
+/// ```ignore
+/// {"start": {"x": -0.5, "y": 0.0, "scale": 1.0}, "target": {"x": -0.7498, "y": -0.0203, "scale": 0.000064}, "frames": 300, "easing": "EaseInOut", "animated_png": true}
+/// ```
+/// `easing` defaults to `Linear`, `animated_png` to false and `frame_rate` to 30
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoomAnimation {
+    pub start: View,
+    pub target: View,
+    pub frames: u32,
+    #[serde(default)]
+    pub easing: Easing,
+    ///Whether the rendered frames should also be assembled into an animated png
+    #[serde(default)]
+    pub animated_png: bool,
+    ///Frames per second of the animated png
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: u16,
+}
+
+impl ZoomAnimation {
+    ///Reads a `ZoomAnimation` from the JSON animation file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be read, or does not contain a valid `ZoomAnimation`
+    pub fn read_from_file(path: &str) -> Result<ZoomAnimation, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("{}: {}", err, path))?;
+        let animation: ZoomAnimation = serde_json::from_str(&json).map_err(|err| format!("Invalid animation file: {}: {}", err, path))?;
+        if animation.frames == 0 {
+            return Err(format!("An animation should have at least one frame: {}", path));
+        }
+        Ok(animation)
+    }
+
+    ///Returns the `View` of frame `frame` ∈ [0, `frames`)
+    pub fn view_at_frame(&self, frame: u32) -> View {
+        let t = if self.frames > 1 {f64::from(frame) / f64::from(self.frames - 1)} else {1.0};
+        interpolate_views(&self.start, &self.target, self.easing.apply(t))
+    }
+}
+
+///Interpolates from `start` to `target`, where t ∈ [0, 1]
+///
+///The scale is interpolated exponentially, so that every frame zooms in by the same factor. The center is interpolated proportionally to
+///the scale, so that the target keeps a fixed position on the screen while zooming, instead of sliding out of view at high zoom levels.
+///The rotation is interpolated linearly
+pub fn interpolate_views(start: &View, target: &View, t: f64) -> View {
+    let scale = start.scale * (target.scale / start.scale).powf(t);
+    let scale_difference = start.scale - target.scale;
+    //Fraction of the translation from start to target that is left, 1.0 at the start and 0.0 at the target
+    let remaining = if scale_difference.abs() > f64::EPSILON * start.scale {(scale - target.scale) / scale_difference} else {1.0 - t};
+    let x = target.x + (start.x - target.x) * remaining;
+    let y = target.y + (start.y - target.y) * remaining;
+    let rotation = start.rotation + (target.rotation - start.rotation) * t;
+    View::new_with_rotation(x, y, scale, rotation)
+}
+
+///Returns the path of the png image of frame `frame` in `output_directory`
+pub fn frame_path(output_directory: &Path, frame: u32) -> PathBuf {
+    output_directory.join(format!("frame_{:05}.png", frame))
+}
+
+///Returns the output directory of the animation in the animation file at `animation_path`: `{output_directory}/{animation file name without extension}`
+pub fn output_directory(output_directory: &Path, animation_path: &str) -> PathBuf {
+    let name = Path::new(animation_path).file_stem().map_or_else(|| String::from("animation"), |stem| stem.to_string_lossy().to_string());
+    output_directory.join(name)
+}
+
+///Renders every frame, described by its `Metadata`, as a numbered png image into `output_directory`.
+///
+///Resumable: frames that have already been rendered are skipped, after verifying that they were rendered with the same parameters.
+///Frames are first written to a temporary file and then renamed, so an interrupted render never leaves a partial frame behind.
+///If `checkpoint` is true, every frame is rendered using a `Checkpoint`, so an interrupted frame is continued as well
+/// # Errors
+/// Returns an Error if the `output_directory` cannot be created, if it contains frames or checkpoints of a different animation, or if a frame cannot be written
+pub fn render_frames(output_directory: &Path, width: usize, height: usize, frames: &[Metadata], checkpoint: bool) -> Result<(), Error> {
+    fs::create_dir_all(output_directory).map_err(|err| Error::io(output_directory, err))?;
+    let mut p = PixelBuffer::new(PixelPlane::new(width, height));
+    let mut c = ComplexPlane::new(width, height);
+    for (frame, metadata) in (0_u32..).zip(frames) {
+        let path = frame_path(output_directory, frame);
+        if path.exists() {
+            verify_frame(&path, width, height, metadata)?;
+            println!("Frame {}/{} has already been rendered, skipping: {}", frame + 1, frames.len(), path.display());
+            continue;
+        }
+        println!("Rendering frame {}/{}: {:?}", frame + 1, frames.len(), metadata.view);
+        let partial_path = path.with_extension("png.part");
+        if checkpoint {
+            let checkpoint = Checkpoint::new(output_directory, width, height, metadata.clone());
+            checkpoint.render()?.save_as_png_at(&partial_path, &metadata.view, &metadata.mandelbrot_set, metadata.supersampling_amount, metadata.coloring_algorithm)?;
+            fs::rename(&partial_path, &path).map_err(|err| Error::io(&path, err))?;
+            checkpoint.remove()?;
+            continue;
+        }
+        c.set_view(&metadata.view);
+        p.color_channel_mapping = metadata.color_channel_mapping;
+        p.palette_offset = metadata.palette_offset;
+        rendering::render_complex_plane_into_buffer(&mut p, &c, &metadata.mandelbrot_set, metadata.supersampling_amount, metadata.coloring_algorithm.coloring_function())?;
+        p.save_as_png_at(&partial_path, &metadata.view, &metadata.mandelbrot_set, metadata.supersampling_amount, metadata.coloring_algorithm)?;
+        fs::rename(&partial_path, &path).map_err(|err| Error::io(&path, err))?;
+    }
+    Ok(())
+}
+
+///Verifies that the already rendered frame at `path` has the given dimensions and `Metadata`
+fn verify_frame(path: &Path, width: usize, height: usize, metadata: &Metadata) -> Result<(), String> {
+    let path_text = path.to_string_lossy();
+    let rendered = Metadata::read_from_png(&path_text)?;
+    let (rendered_width, rendered_height) = png_dimensions(path)?;
+    if rendered.to_json() != metadata.to_json() || rendered_width != width || rendered_height != height {
+        return Err(format!("{} was rendered with different parameters, remove the output directory or rename the animation file to start over", path_text));
+    }
+    Ok(())
+}
+
+///Returns the width and height of the png image at `path`
+fn png_dimensions(path: &Path) -> Result<(usize, usize), String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", err, path.display()))?;
+    let reader = png::Decoder::new(BufReader::new(file)).read_info().map_err(|err| format!("{}: {}", err, path.display()))?;
+    let info = reader.info();
+    Ok((info.width as usize, info.height as usize))
+}
+
+///Assembles the `frame_count` numbered png images in `output_directory` into the animated png `output_directory/animation.png`,
+///playing at `frame_rate` frames per second and looping forever
+/// # Errors
+/// Returns an Error if a frame cannot be read, or if the animated png cannot be written
+pub fn assemble_animated_png(output_directory: &Path, frame_count: u32, frame_rate: u16, width: usize, height: usize) -> Result<PathBuf, String> {
+    let path = output_directory.join("animation.png");
+    let to_error = |err: &dyn std::fmt::Display| format!("{}: {}", err, path.display());
+    let file = File::create(&path).map_err(|err| to_error(&err))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frame_count, 0).map_err(|err| to_error(&err))?;
+    encoder.set_frame_delay(1, frame_rate.max(1)).map_err(|err| to_error(&err))?;
+    let mut writer = encoder.write_header().map_err(|err| to_error(&err))?;
+    for frame in 0..frame_count {
+        let frame_path = frame_path(output_directory, frame);
+        let file = File::open(&frame_path).map_err(|err| format!("{}: {}", err, frame_path.display()))?;
+        let mut reader = png::Decoder::new(BufReader::new(file)).read_info().map_err(|err| format!("{}: {}", err, frame_path.display()))?;
+        let mut data = vec![0; reader.output_buffer_size()];
+        reader.next_frame(&mut data).map_err(|err| format!("{}: {}", err, frame_path.display()))?;
+        writer.write_image_data(&data).map_err(|err| to_error(&err))?;
+    }
+    writer.finish().map_err(|err| to_error(&err))?;
+    Ok(path)
+}
+
+///Renders the `ZoomAnimation` in the animation file at `animation_path` into `{--output directory}/{animation file name without extension}`.
+///
+///Renders at the image resolution, `max_iterations` and SSAA multiplier given in the `config`. Optionally assembles the frames into an animated png
+/// # Errors
+/// Returns an Error if the animation file cannot be read, or if the animation cannot be rendered
+pub fn render_zoom_animation(animation_path: &str, config: &Config, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping) -> Result<(), Error> {
+    let animation = ZoomAnimation::read_from_file(animation_path)?;
+    let output_directory = output_directory(&config.output.directory, animation_path);
+    let m = MandelbrotSet::new(config.max_iterations, config.orbit_radius);
+    let frames: Vec<Metadata> = (0..animation.frames)
+        .map(|frame| Metadata::new(animation.view_at_frame(frame), m.clone(), coloring_algorithm, color_channel_mapping, 0, config.supersampling_amount))
+        .collect();
+    println!("Rendering a {} frame zoom animation from {:?} to {:?} into {}", animation.frames, animation.start, animation.target, output_directory.display());
+    render_frames(&output_directory, config.image_width, config.image_height, &frames, config.checkpoint)?;
+    if animation.animated_png {
+        let path = assemble_animated_png(&output_directory, animation.frames, animation.frame_rate, config.image_width, config.image_height)?;
+        println!("Saved the animated png: {}", path.display());
+    }
+    println!("Rendered the zoom animation into {}", output_directory.display());
+    Ok(())
+}
@@ -0,0 +1,391 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{complex::Complex, complex_plane::{ComplexPlane, View}, config::Config, error::Error, coloring::{ColoringAlgorithm, ColorChannelMapping},
+    mandelbrot_set::MandelbrotSet, metadata::Metadata};
+
+use super::{assemble_animated_png, default_frame_rate, output_directory, render_frames};
+
+///The keyframe file in the output directory the viewer appends the current view to
+pub static KEYFRAMES_FILE_NAME: &str = "keyframes.json";
+///The amount of frames between a keyframe appended by the viewer and the previous keyframe
+pub static KEYFRAME_SPACING: u32 = 60;
+
+///How the values of a `Track` are interpolated between its keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Interpolation {
+    Linear,
+    ///Cubic Hermite spline through all keys (Catmull-Rom), smooth at the keys
+    CubicSpline,
+    ///Linear interpolation of the logarithm of the values, e.g. zooming in by the same factor every frame; values should be positive
+    Logarithmic,
+}
+
+///A keyframe: the values of the parameter tracks at frame `frame`. A parameter that is None does not have a key at this keyframe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub frame: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center: Option<Complex>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    ///Rotation in degrees, counterclockwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_iterations: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub palette_offset: Option<u32>,
+    ///If any keyframe has a `julia_c`, the Julia set of the interpolated `julia_c` is rendered instead of the Mandelbrot set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub julia_c: Option<Complex>,
+}
+
+impl Keyframe {
+    ///Creates a `Keyframe` with a key for every parameter track, from the given `View`, `MandelbrotSet` and `palette_offset`
+    pub fn new(frame: u32, view: &View, m: &MandelbrotSet, palette_offset: u32) -> Keyframe {
+        Keyframe {
+            frame,
+            center: Some(Complex::new(view.x, view.y)),
+            scale: Some(view.scale),
+            rotation: Some(view.rotation),
+            max_iterations: Some(m.max_iterations),
+            palette_offset: Some(palette_offset),
+            julia_c: m.julia_c,
+        }
+    }
+}
+
+///The `Interpolation` of every parameter track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackInterpolations {
+    pub center: Interpolation,
+    pub scale: Interpolation,
+    pub rotation: Interpolation,
+    pub max_iterations: Interpolation,
+    pub palette_offset: Interpolation,
+    pub julia_c: Interpolation,
+}
+
+impl Default for TrackInterpolations {
+    fn default() -> Self {
+        TrackInterpolations {
+            center: Interpolation::CubicSpline,
+            scale: Interpolation::Logarithmic,
+            rotation: Interpolation::CubicSpline,
+            max_iterations: Interpolation::Linear,
+            palette_offset: Interpolation::Linear,
+            julia_c: Interpolation::Linear,
+        }
+    }
+}
+
+///A parameter track: the keys (frame, value) of a single parameter, sorted by frame, and their `Interpolation`
+pub struct Track {
+    keys: Vec<(f64, f64)>,
+    interpolation: Interpolation,
+}
+
+impl Track {
+    ///Creates a `Track` from the keys (frame, value), the keys should have distinct frames
+    pub fn new(mut keys: Vec<(f64, f64)>, interpolation: Interpolation) -> Track {
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Track { keys, interpolation }
+    }
+
+    ///Returns the interpolated value at `frame`, or None if the `Track` has no keys. Before the first key and after the last key, the value is constant
+    pub fn evaluate(&self, frame: f64) -> Option<f64> {
+        let (first, last) = (self.keys.first()?, self.keys.last()?);
+        if frame <= first.0 {
+            return Some(first.1);
+        }
+        if frame >= last.0 {
+            return Some(last.1);
+        }
+        let i = self.keys.windows(2).position(|keys| frame <= keys[1].0)?;
+        let ((t0, v0), (t1, v1)) = (self.keys[i], self.keys[i + 1]);
+        let s = (frame - t0) / (t1 - t0);
+        let value = match self.interpolation {
+            Interpolation::Logarithmic if v0 > 0.0 && v1 > 0.0 => v0 * (v1 / v0).powf(s),
+            Interpolation::Linear | Interpolation::Logarithmic => v0 + (v1 - v0) * s,
+            Interpolation::CubicSpline => {
+                //Cubic Hermite basis functions
+                let (s2, s3) = (s * s, s * s * s);
+                let h00 = 2.0 * s3 - 3.0 * s2 + 1.0;
+                let h10 = s3 - 2.0 * s2 + s;
+                let h01 = -2.0 * s3 + 3.0 * s2;
+                let h11 = s3 - s2;
+                let length = t1 - t0;
+                h00 * v0 + h10 * length * self.tangent(i) + h01 * v1 + h11 * length * self.tangent(i + 1)
+            }
+        };
+        Some(value)
+    }
+
+    ///Returns the Catmull-Rom tangent (change in value per frame) at key `i`, using one-sided differences at the first and last key
+    fn tangent(&self, i: usize) -> f64 {
+        let previous = self.keys[i.saturating_sub(1)];
+        let next = self.keys[(i + 1).min(self.keys.len() - 1)];
+        if next.0 > previous.0 {(next.1 - previous.1) / (next.0 - previous.0)} else {0.0}
+    }
+}
+
+///A keyframe animation, read from a JSON keyframe file, e.g.:
+/// ```ignore
+/// {
+///     "keyframes": [
+///         {"frame": 0, "center": {"x": -0.75, "y": 0.0}, "scale": 1.0, "max_iterations": 500},
+///         {"frame": 120, "center": {"x": -0.7498, "y": -0.0203}, "scale": 0.001, "rotation": 90.0, "palette_offset": 200},
+///         {"frame": 240, "scale": 0.000064, "max_iterations": 3000}
+///     ],
+///     "interpolation": {"center": "CubicSpline", "scale": "Logarithmic"},
+///     "animated_png": false
+/// }
+/// ```
+/// Every parameter track is interpolated separately between the keyframes that have a key for it. The animation ends at the last keyframe.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeyframeAnimation {
+    pub keyframes: Vec<Keyframe>,
+    #[serde(default)]
+    pub interpolation: TrackInterpolations,
+    ///Whether the rendered frames should also be assembled into an animated png
+    #[serde(default)]
+    pub animated_png: bool,
+    ///Frames per second of the animated png
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: u16,
+}
+
+impl KeyframeAnimation {
+    ///Reads a `KeyframeAnimation` from the JSON keyframe file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be read, does not contain a valid `KeyframeAnimation`, or contains two keyframes with the same frame
+    pub fn read_from_file(path: &str) -> Result<KeyframeAnimation, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("{}: {}", err, path))?;
+        KeyframeAnimation::from_json(&json).map_err(|err| format!("{}: {}", err, path))
+    }
+
+    ///Parses a `KeyframeAnimation` from the contents of a JSON keyframe file, sorting its keyframes by frame
+    /// # Errors
+    /// Returns an Error if `json` is not a valid `KeyframeAnimation`, or contains two keyframes with the same frame
+    pub fn from_json(json: &str) -> Result<KeyframeAnimation, String> {
+        let mut animation: KeyframeAnimation = serde_json::from_str(json).map_err(|err| format!("Invalid keyframe file: {}", err))?;
+        animation.keyframes.sort_by_key(|keyframe| keyframe.frame);
+        if animation.keyframes.windows(2).any(|keyframes| keyframes[0].frame == keyframes[1].frame) {
+            return Err(String::from("Every keyframe should have a distinct frame"));
+        }
+        Ok(animation)
+    }
+
+    ///Writes the `KeyframeAnimation` as JSON to the keyframe file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be written
+    pub fn write_to_file(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        fs::write(path, json).map_err(|err| format!("{}: {}", err, path))
+    }
+
+    ///Returns the amount of frames in the animation, the animation ends at the last keyframe
+    pub fn frame_count(&self) -> u32 {
+        self.keyframes.last().map_or(0, |keyframe| keyframe.frame + 1)
+    }
+
+    ///Returns the `Track` of the keys selected by `key` from all keyframes
+    fn track(&self, interpolation: Interpolation, key: impl Fn(&Keyframe) -> Option<f64>) -> Track {
+        let keys = self.keyframes.iter().filter_map(|keyframe| key(keyframe).map(|value| (f64::from(keyframe.frame), value))).collect();
+        Track::new(keys, interpolation)
+    }
+
+    ///Evaluates every parameter track, returns the `Metadata` of every frame. Parameters without any keys get the value in `defaults`
+    pub fn frames(&self, defaults: &Metadata) -> Vec<Metadata> {
+        let interpolation = &self.interpolation;
+        let center_x = self.track(interpolation.center, |keyframe| keyframe.center.map(|center| center.x));
+        let center_y = self.track(interpolation.center, |keyframe| keyframe.center.map(|center| center.y));
+        let scale = self.track(interpolation.scale, |keyframe| keyframe.scale);
+        let rotation = self.track(interpolation.rotation, |keyframe| keyframe.rotation);
+        let max_iterations = self.track(interpolation.max_iterations, |keyframe| keyframe.max_iterations.map(f64::from));
+        let palette_offset = self.track(interpolation.palette_offset, |keyframe| keyframe.palette_offset.map(f64::from));
+        let julia_c_x = self.track(interpolation.julia_c, |keyframe| keyframe.julia_c.map(|julia_c| julia_c.x));
+        let julia_c_y = self.track(interpolation.julia_c, |keyframe| keyframe.julia_c.map(|julia_c| julia_c.y));
+
+        (0..self.frame_count()).map(|frame| {
+            let frame = f64::from(frame);
+            let view = View::new_with_rotation(
+                center_x.evaluate(frame).unwrap_or(defaults.view.x),
+                center_y.evaluate(frame).unwrap_or(defaults.view.y),
+                scale.evaluate(frame).unwrap_or(defaults.view.scale),
+                rotation.evaluate(frame).unwrap_or(defaults.view.rotation),
+            );
+            let mut m = defaults.mandelbrot_set.clone();
+            m.max_iterations = max_iterations.evaluate(frame).map_or(m.max_iterations, |value| value.round().max(1.0) as u32);
+            if let (Some(x), Some(y)) = (julia_c_x.evaluate(frame), julia_c_y.evaluate(frame)) {
+                m.julia_c = Some(Complex::new(x, y));
+            }
+            let palette_offset = palette_offset.evaluate(frame).map_or(defaults.palette_offset, |value| value.round().max(0.0) as u32);
+            Metadata::new(view, m, defaults.coloring_algorithm, defaults.color_channel_mapping, palette_offset, defaults.supersampling_amount)
+        }).collect()
+    }
+}
+
+///Appends a `Keyframe` of the given view and parameters to the keyframe file at `path`, `KEYFRAME_SPACING` frames after the last keyframe.
+///
+///Creates the keyframe file if it does not exist. Returns the frame of the appended `Keyframe`
+/// # Errors
+/// Returns an Error if the existing keyframe file is invalid, or if the keyframe file cannot be written
+pub fn append_keyframe(path: &str, view: &View, m: &MandelbrotSet, palette_offset: u32) -> Result<u32, String> {
+    let mut animation = if std::path::Path::new(path).exists() {
+        KeyframeAnimation::read_from_file(path)?
+    } else {
+        KeyframeAnimation { frame_rate: default_frame_rate(), ..KeyframeAnimation::default() }
+    };
+    let frame = animation.keyframes.last().map_or(0, |keyframe| keyframe.frame + KEYFRAME_SPACING);
+    animation.keyframes.push(Keyframe::new(frame, view, m, palette_offset));
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|err| format!("{}: {}", err, parent.display()))?;
+    }
+    animation.write_to_file(path)?;
+    Ok(frame)
+}
+
+///Renders the `KeyframeAnimation` in the keyframe file at `keyframes_path` into `{--output directory}/{keyframe file name without extension}`.
+///
+///Renders at the image resolution and SSAA multiplier given in the `config`. The `config` also gives the `max_iterations` and the default view is
+///used for tracks without keys. Optionally assembles the frames into an animated png
+/// # Errors
+/// Returns an Error if the keyframe file cannot be read, or if the animation cannot be rendered
+pub fn render_keyframe_animation(keyframes_path: &str, config: &Config, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping) -> Result<(), Error> {
+    let animation = KeyframeAnimation::read_from_file(keyframes_path)?;
+    if animation.keyframes.is_empty() {
+        return Err(Error::Config(format!("A keyframe animation should have at least one keyframe: {}", keyframes_path)));
+    }
+    let output_directory = output_directory(&config.output.directory, keyframes_path);
+    let default_view = ComplexPlane::new(config.image_width, config.image_height).get_view();
+    let defaults = Metadata::new(default_view, MandelbrotSet::new(config.max_iterations, config.orbit_radius), coloring_algorithm, color_channel_mapping, 0, config.supersampling_amount);
+    let frames = animation.frames(&defaults);
+    println!("Rendering a {} frame keyframe animation with {} keyframes into {}", frames.len(), animation.keyframes.len(), output_directory.display());
+    render_frames(&output_directory, config.image_width, config.image_height, &frames, config.checkpoint)?;
+    if animation.animated_png {
+        let path = assemble_animated_png(&output_directory, animation.frame_count(), animation.frame_rate, config.image_width, config.image_height)?;
+        println!("Saved the animated png: {}", path.display());
+    }
+    println!("Rendered the keyframe animation into {}", output_directory.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() <= 1e-12 * expected.abs().max(1.0), "{} != {}", actual, expected);
+    }
+
+    ///Asserts that `track` has the value of every key at its frame, and is constant before the first and after the last key
+    fn assert_passes_through_keys(track: &Track, keys: &[(f64, f64)]) {
+        for &(frame, value) in keys {
+            assert_close(track.evaluate(frame).unwrap(), value);
+        }
+        let (first, last) = (keys.first().unwrap(), keys.last().unwrap());
+        assert_close(track.evaluate(first.0 - 10.0).unwrap(), first.1);
+        assert_close(track.evaluate(last.0 + 10.0).unwrap(), last.1);
+    }
+
+    #[test]
+    fn a_track_without_keys_has_no_value() {
+        assert_eq!(Track::new(Vec::new(), Interpolation::Linear).evaluate(0.0), None);
+    }
+
+    #[test]
+    fn linear_interpolation() {
+        let keys = [(0.0, 1.0), (10.0, 3.0), (30.0, -1.0)];
+        let track = Track::new(keys.to_vec(), Interpolation::Linear);
+        assert_passes_through_keys(&track, &keys);
+        assert_close(track.evaluate(5.0).unwrap(), 2.0);
+        assert_close(track.evaluate(20.0).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn logarithmic_interpolation() {
+        let keys = [(0.0, 1.0), (10.0, 0.01), (20.0, 0.0001)];
+        let track = Track::new(keys.to_vec(), Interpolation::Logarithmic);
+        assert_passes_through_keys(&track, &keys);
+        //Zooms in by the same factor every frame
+        assert_close(track.evaluate(5.0).unwrap(), 0.1);
+        assert_close(track.evaluate(15.0).unwrap(), 0.001);
+    }
+
+    #[test]
+    fn logarithmic_interpolation_of_non_positive_values_is_linear() {
+        let track = Track::new(vec![(0.0, 0.0), (10.0, 2.0)], Interpolation::Logarithmic);
+        assert_close(track.evaluate(0.0).unwrap(), 0.0);
+        assert_close(track.evaluate(5.0).unwrap(), 1.0);
+        assert_close(track.evaluate(10.0).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn cubic_spline_interpolation() {
+        let keys = [(0.0, 0.0), (10.0, 5.0), (20.0, -5.0), (40.0, 10.0)];
+        let track = Track::new(keys.to_vec(), Interpolation::CubicSpline);
+        assert_passes_through_keys(&track, &keys);
+        //The Catmull-Rom tangent at the second key is (-5 - 0) / 20, so the spline is smooth there
+        let slope = (track.evaluate(10.0 + 1e-6).unwrap() - track.evaluate(10.0 - 1e-6).unwrap()) / 2e-6;
+        assert!((slope - -0.25).abs() < 1e-6, "slope: {}", slope);
+    }
+
+    #[test]
+    fn cubic_spline_interpolation_of_collinear_keys_is_linear() {
+        let track = Track::new(vec![(20.0, 4.0), (0.0, 0.0), (10.0, 2.0)], Interpolation::CubicSpline);
+        for frame in [0.0, 2.5, 5.0, 10.0, 17.5, 20.0] {
+            assert_close(track.evaluate(frame).unwrap(), frame / 5.0);
+        }
+    }
+
+    #[test]
+    fn parses_keyframe_files() {
+        let animation = KeyframeAnimation::from_json(r#"{
+            "keyframes": [
+                {"frame": 10, "scale": 0.5, "max_iterations": 1000},
+                {"frame": 0, "center": {"x": -0.75, "y": 0.1}, "scale": 2.0}
+            ],
+            "interpolation": {"scale": "Linear"}
+        }"#).unwrap();
+        assert_eq!(animation.keyframes.iter().map(|keyframe| keyframe.frame).collect::<Vec<_>>(), [0, 10]);
+        assert_eq!(animation.frame_count(), 11);
+        assert_eq!(animation.interpolation.scale, Interpolation::Linear);
+        assert_eq!(animation.interpolation.center, TrackInterpolations::default().center);
+        assert_eq!(animation.frame_rate, default_frame_rate());
+        assert!(!animation.animated_png);
+    }
+
+    #[test]
+    fn rejects_invalid_keyframe_files() {
+        assert!(KeyframeAnimation::from_json("").is_err());
+        assert!(KeyframeAnimation::from_json(r#"{"keyframes": [{"scale": 1.0}]}"#).is_err());
+        assert!(KeyframeAnimation::from_json(r#"{"keyframes": [], "interpolation": {"scale": "Quadratic"}}"#).is_err());
+        let duplicate = KeyframeAnimation::from_json(r#"{"keyframes": [{"frame": 3}, {"frame": 3}]}"#);
+        assert_eq!(duplicate.unwrap_err(), "Every keyframe should have a distinct frame");
+    }
+
+    #[test]
+    fn frames_evaluate_every_track_and_use_the_defaults_for_tracks_without_keys() {
+        let animation = KeyframeAnimation::from_json(r#"{
+            "keyframes": [
+                {"frame": 0, "scale": 1.0, "max_iterations": 100},
+                {"frame": 4, "scale": 0.0625, "max_iterations": 500}
+            ]
+        }"#).unwrap();
+        let defaults = Metadata::new(View::new(-0.5, 0.25, 3.0), MandelbrotSet::new(200, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 7, 2);
+        let frames = animation.frames(&defaults);
+        assert_eq!(frames.len(), 5);
+        for (frame, metadata) in (0_u32..).zip(&frames) {
+            assert_close(metadata.view.x, -0.5);
+            assert_close(metadata.view.y, 0.25);
+            assert_close(metadata.view.scale, 0.5_f64.powf(f64::from(frame)));
+            assert_eq!(metadata.mandelbrot_set.max_iterations, 100 + 100 * frame);
+            assert_eq!(metadata.palette_offset, 7);
+            assert_eq!(metadata.supersampling_amount, 2);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::{fs, path::{Path, PathBuf}, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coloring::ColorChannelMapping, complex_plane::ComplexPlane, metadata::Metadata, pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane},
+    rendering::{self, RenderBox}};
+
+///Width and height in pixels of the tiles a checkpointed render is saved in
+pub static CHECKPOINT_TILE_SIZE: usize = 256;
+///File name of the parameters of a checkpointed render in its checkpoint directory
+static PARAMETERS_FILE_NAME: &str = "checkpoint.json";
+
+///The parameters of a checkpointed render, a later render can only continue the checkpoint if they are equal
+#[derive(Serialize, Deserialize)]
+struct CheckpointParameters {
+    width: usize,
+    height: usize,
+    tile_size: usize,
+    metadata: Metadata,
+}
+
+impl CheckpointParameters {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    ///Returns the 64-bit FNV-1a hash of the JSON of the parameters, which unlike `std::hash` is the same for every build, so a later build can continue the checkpoint
+    fn hash(&self) -> u64 {
+        self.to_json().bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3))
+    }
+}
+
+///A render of an image of `width` x `height` pixels in tiles, every completed tile is saved into the checkpoint `directory` as raw RGB bytes.
+///
+///An interrupted render is continued by rendering an image with the same parameters again: the checkpoint `directory` is named after the hash of the parameters,
+///so it is found again even if the image is saved under another name, e.g. one containing the date. Only the missing tiles are rendered,
+///after verifying that the checkpoint was made with the same parameters
+pub struct Checkpoint {
+    directory: PathBuf,
+    parameters: CheckpointParameters,
+}
+
+impl Checkpoint {
+    ///Creates a `Checkpoint` of the `width` x `height` image rendered from `metadata`, in the checkpoint directory `{output_directory}/{hash of the parameters}.checkpoint`
+    pub fn new(output_directory: &Path, width: usize, height: usize, metadata: Metadata) -> Checkpoint {
+        let parameters = CheckpointParameters { width, height, tile_size: CHECKPOINT_TILE_SIZE, metadata };
+        let directory = output_directory.join(format!("{:016x}.checkpoint", parameters.hash()));
+        Checkpoint { directory, parameters }
+    }
+
+    ///Returns the `RenderBox` of every tile and the path of its file in the checkpoint directory
+    fn tiles(&self) -> Vec<(RenderBox, PathBuf)> {
+        let CheckpointParameters { width, height, tile_size, .. } = self.parameters;
+        RenderBox::split(width, height, tile_size).into_iter()
+            .map(|render_box| (render_box, self.directory.join(format!("{}_{}.rgb", render_box.min_x / tile_size, render_box.min_y / tile_size))))
+            .collect()
+    }
+
+    ///Creates the checkpoint directory and saves the parameters into it, or verifies the parameters saved by an earlier render
+    fn open(&self) -> Result<(), String> {
+        let path = self.directory.join(PARAMETERS_FILE_NAME);
+        let json = self.parameters.to_json();
+        if path.exists() {
+            let saved = fs::read_to_string(&path).map_err(|err| format!("{}: {}", err, path.display()))?;
+            if saved != json {
+                return Err(format!("{} was made with different parameters, remove it to start over", self.directory.display()));
+            }
+            return Ok(());
+        }
+        fs::create_dir_all(&self.directory).map_err(|err| format!("{}: {}", err, self.directory.display()))?;
+        fs::write(&path, json).map_err(|err| format!("{}: {}", err, path.display()))
+    }
+
+    ///Renders the image into a new `PixelBuffer`, saving every completed tile to the checkpoint directory and reading the tiles completed by an earlier render from it.
+    ///
+    ///Tiles are first written to a temporary file and then renamed, so an interrupted render never leaves a partial tile behind
+    /// # Errors
+    /// Returns an Error if the checkpoint directory was made with different parameters, or a tile cannot be read or written
+    pub fn render(&self) -> Result<PixelBuffer, String> {
+        self.open()?;
+        let CheckpointParameters { width, height, ref metadata, .. } = self.parameters;
+        let mut p = PixelBuffer::new(PixelPlane::new(width, height));
+        p.color_channel_mapping = metadata.color_channel_mapping;
+        p.palette_offset = metadata.palette_offset;
+        let mut c = ComplexPlane::new(width, height);
+        c.set_view(&metadata.view);
+        let tiles = self.tiles();
+        let (completed, missing): (Vec<_>, Vec<_>) = tiles.into_iter().partition(|(render_box, path)| fs::metadata(path).is_ok_and(|file| file.len() == 3 * render_box.compute_pixel_count() as u64));
+        println!("Checkpoint {}: {} tiles completed, rendering {} tiles", self.directory.display(), completed.len(), missing.len());
+        for (render_box, path) in &completed {
+            let rgb = fs::read(path).map_err(|err| format!("{}: {}", err, path.display()))?;
+            rendering::copy_rgb_into_buffer(&mut p, *render_box, &rgb);
+        }
+        let time = Instant::now();
+        for (i, (render_box, path)) in missing.iter().enumerate() {
+            let mut rgb = vec![0; 3 * render_box.compute_pixel_count()];
+            //The tiles are mapped to the color channels when the image is saved
+            rendering::render_box_into_rgb(&mut rgb, &c, &metadata.mandelbrot_set, *render_box, metadata.supersampling_amount, metadata.palette_offset, ColorChannelMapping::RGB, metadata.coloring_algorithm.coloring_function());
+            let partial_path = path.with_extension("rgb.part");
+            fs::write(&partial_path, &rgb).map_err(|err| format!("{}: {}", err, partial_path.display()))?;
+            fs::rename(&partial_path, path).map_err(|err| format!("{}: {}", err, path.display()))?;
+            rendering::copy_rgb_into_buffer(&mut p, *render_box, &rgb);
+            rendering::print_progress_with_eta(i + 1, missing.len(), "tiles", time.elapsed());
+        }
+        if !missing.is_empty() {
+            println!();
+        }
+        p.update_pixels();
+        p.render_time = Some(time.elapsed());
+        Ok(p)
+    }
+
+    ///Removes the checkpoint directory, after the image has been saved
+    /// # Errors
+    /// Returns an Error if the checkpoint directory cannot be removed
+    pub fn remove(&self) -> Result<(), String> {
+        fs::remove_dir_all(&self.directory).map_err(|err| format!("{}: {}", err, self.directory.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coloring::ColoringAlgorithm, complex_plane::View, mandelbrot_set::MandelbrotSet, test_utils::temporary_directory};
+
+    use super::*;
+
+    fn metadata(max_iterations: u32) -> Metadata {
+        Metadata::new(View::new(-0.75, 0.1, 0.5), MandelbrotSet::new(max_iterations, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1)
+    }
+
+    fn color(p: &PixelBuffer, x: usize, y: usize) -> (u8, u8, u8) {
+        let color = p.colors[p.point_to_index(x, y)];
+        (color.red, color.green, color.blue)
+    }
+
+    #[test]
+    fn checkpoints_are_found_again_by_their_parameters() {
+        let directory = temporary_directory("checkpoint_directory");
+        let checkpoint = Checkpoint::new(&directory, 300, 200, metadata(50));
+        assert_eq!(checkpoint.directory, Checkpoint::new(&directory, 300, 200, metadata(50)).directory);
+        assert_eq!(checkpoint.directory.parent(), Some(directory.as_path()));
+        assert_ne!(checkpoint.directory, Checkpoint::new(&directory, 300, 200, metadata(51)).directory);
+        assert_ne!(checkpoint.directory, Checkpoint::new(&directory, 300, 201, metadata(50)).directory);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn an_interrupted_render_reuses_the_completed_tiles() {
+        let directory = temporary_directory("checkpoint_resume");
+        let (width, height) = (300, 260);
+        let checkpoint = Checkpoint::new(&directory, width, height, metadata(50));
+        checkpoint.render().unwrap();
+        let tile_path = |name: &str| checkpoint.directory.join(name);
+        for name in ["0_0.rgb", "1_0.rgb", "0_1.rgb", "1_1.rgb", PARAMETERS_FILE_NAME] {
+            assert!(tile_path(name).exists(), "{}", name);
+        }
+
+        //Interrupt the render: the last tile was not completed, and another tile was left partial. Mark a completed tile to see whether it is reused
+        fs::remove_file(tile_path("1_1.rgb")).unwrap();
+        fs::write(tile_path("1_1.rgb.part"), [1, 2, 3]).unwrap();
+        fs::write(tile_path("0_1.rgb"), [1, 2, 3]).unwrap();
+        fs::write(tile_path("0_0.rgb"), vec![7; 3 * 256 * 256]).unwrap();
+
+        //Running the render again, e.g. saving under another name, continues the checkpoint
+        let rerun = Checkpoint::new(&directory, width, height, metadata(50));
+        let p = rerun.render().unwrap();
+        assert_eq!(color(&p, 0, 0), (7, 7, 7));
+        assert_eq!(color(&p, 255, 255), (7, 7, 7));
+        assert_ne!(color(&p, 0, 256), (7, 7, 7));
+        assert_eq!(fs::metadata(tile_path("0_1.rgb")).unwrap().len(), 3 * 256 * 4);
+        assert_eq!(fs::metadata(tile_path("1_1.rgb")).unwrap().len(), 3 * 44 * 4);
+        assert_eq!(fs::read(tile_path("0_0.rgb")).unwrap(), vec![7; 3 * 256 * 256]);
+
+        rerun.remove().unwrap();
+        assert!(!rerun.directory.exists());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn a_checkpoint_with_other_parameters_is_not_continued() {
+        let directory = temporary_directory("checkpoint_parameters");
+        let checkpoint = Checkpoint::new(&directory, 20, 10, metadata(50));
+        checkpoint.render().unwrap();
+        fs::write(checkpoint.directory.join(PARAMETERS_FILE_NAME), Checkpoint::new(&directory, 20, 10, metadata(60)).parameters.to_json()).unwrap();
+        assert!(checkpoint.render().err().unwrap().ends_with("was made with different parameters, remove it to start over"));
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
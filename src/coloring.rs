@@ -3,11 +3,73 @@ use std::{fmt, str::FromStr};
 use angular_units::Deg;
 use num::traits::Pow;
 use prisma::{Hsv, Rgb, FromColor};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug,Clone,Copy)]
-///A mapping from ColorChannelMapping -> RGB, the first character denotes the new red channel, the second character the new green channel,
+///A function mapping the amount of iterations of a point to a `TrueColor`, e.g. `TrueColor::new_from_hsv_colors`
+pub type ColoringFunction = fn(iterations: u32, max_iterations: u32) -> TrueColor;
+
+///A function mapping the amount of iterations of a point to a full precision `FloatColor`, e.g. `FloatColor::new_from_hsv_colors`
+pub type FloatColoringFunction = fn(iterations: u32, max_iterations: u32) -> FloatColor;
+
+///The algorithms that can be used to color the Mandelbrot set, each algorithm maps to a `ColoringFunction`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColoringAlgorithm {
+    HSV,
+    BernsteinPolynomials,
+}
+
+impl ColoringAlgorithm {
+    ///Returns the `ColoringFunction` implementing this `ColoringAlgorithm`
+    pub fn coloring_function(self) -> ColoringFunction {
+        match self {
+            ColoringAlgorithm::HSV => TrueColor::new_from_hsv_colors,
+            ColoringAlgorithm::BernsteinPolynomials => TrueColor::new_from_bernstein_polynomials,
+        }
+    }
+
+    ///Returns the `FloatColoringFunction` implementing this `ColoringAlgorithm` at full precision
+    pub fn float_coloring_function(self) -> FloatColoringFunction {
+        match self {
+            ColoringAlgorithm::HSV => FloatColor::new_from_hsv_colors,
+            ColoringAlgorithm::BernsteinPolynomials => FloatColor::new_from_bernstein_polynomials,
+        }
+    }
+
+    ///Returns the human readable name of this `ColoringAlgorithm`
+    pub fn name(self) -> &'static str {
+        match self {
+            ColoringAlgorithm::HSV => "HSV",
+            ColoringAlgorithm::BernsteinPolynomials => "Bernstein polynomials",
+        }
+    }
+}
+
+impl FromStr for ColoringAlgorithm {
+    type Err = String;
+
+    ///Parses the name of a `ColoringAlgorithm` variant, case-insensitive, e.g. `hsv` or `BernsteinPolynomials`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [ColoringAlgorithm::HSV, ColoringAlgorithm::BernsteinPolynomials].into_iter().find(|algorithm| format!("{:?}", algorithm).eq_ignore_ascii_case(s))
+            .ok_or_else(|| format!("Unknown coloring algorithm, should be HSV or BernsteinPolynomials: {}", s))
+    }
+}
+
+///Cyclically shifts the palette of a `ColoringFunction` by `palette_offset` iterations.
+///
+///Points inside the Mandelbrot set (`iterations` == `max_iterations`) keep their color
+pub fn apply_palette_offset(iterations: u32, max_iterations: u32, palette_offset: u32) -> u32 {
+    if iterations >= max_iterations || max_iterations == 0 {
+        return iterations;
+    }
+    ((u64::from(iterations) + u64::from(palette_offset)) % u64::from(max_iterations)) as u32
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+///A mapping from `ColorChannelMapping` -> RGB.
+///
+///The first character denotes the new red channel, the second character the new green channel,
 /// the third character the new blue channel. </br>
-/// E.g: Ok(ColorChannelMapping::BGR) means that red will get the value of blue, green the value of green, and blue the value of red:
+/// E.g: `Ok(ColorChannelMapping::BGR)` means that red will get the value of blue, green the value of green, and blue the value of red:
 /// B => R, G => G, R => B.
 pub enum ColorChannelMapping {
     BBB,
@@ -40,6 +102,8 @@ pub enum ColorChannelMapping {
 }
 
 impl ColorChannelMapping {
+    /// # Errors
+    /// Returns an Error if `r_g_b` is not a string of length three with characters 'R', 'G' or 'B'
     pub fn new(r_g_b: &str) -> Result<ColorChannelMapping,String> {
         match &r_g_b.to_uppercase()[..] {
             "BBB" => Ok(ColorChannelMapping::BBB),
@@ -114,6 +178,8 @@ impl TrueColor {
         (r << 16) | (g << 8) | b
     }
 
+    /// # Panics
+    /// If `color` is not equal to 'R', 'G', or 'B'
     pub fn get_color(&self, color: char) -> u8 {
         assert!(color == 'R' || color == 'G' || color == 'B', "Error: color should be equal to R, G, or B, color = {}",color);
         match color {
@@ -129,12 +195,8 @@ impl TrueColor {
     /// ![img](https://solarianprogrammer.com/images/2013/02/28/rgb_smooth.png)</br>
     /// Source: [Bernstein polynomial coloring](https://solarianprogrammer.com/2013/02/28/mandelbrot-set-cpp-11/)
     fn new_from_bernstein_polynomials_normalized(t: f64) -> TrueColor {
-        let t = t.abs().min(0.999);
-        let one_minus_t = 1.0-t;
-        let red: f64 = 9.0 * one_minus_t * t.pow(3) * 255.0;
-        let green: f64 = 15.0 * one_minus_t * t.pow(2) * 255.0;
-        let blue: f64 = 8.5 * one_minus_t * t * 255.0;
-        TrueColor { red: red as u8, green: green as u8, blue: blue as u8 }
+        let (red, green, blue) = bernstein_polynomials(t);
+        TrueColor { red: (red * 255.0) as u8, green: (green * 255.0) as u8, blue: (blue * 255.0) as u8 }
     }
 
     ///A `coloring_function`
@@ -149,12 +211,7 @@ impl TrueColor {
 
     ///A `coloring_function`
     pub fn new_from_hsv_colors(iterations: u32, max_iterations: u32) -> TrueColor {
-        let hue = 0.3 * f64::from(iterations);
-        let saturation = 1.0;//0.8;
-        let value: f64 = if iterations < max_iterations {1.0} else {0.0};
-        let hue_degree = Deg(hue % 359.999);
-        let hsv = Hsv::new(hue_degree,saturation,value);
-        let rgb = Rgb::from_color(&hsv);
+        let rgb = hsv_colors(iterations, max_iterations);
         let red = normalized_to_byte(rgb.red());
         let green = normalized_to_byte(rgb.green());
         let blue = normalized_to_byte(rgb.blue());
@@ -163,7 +220,7 @@ impl TrueColor {
 
     ///Computes the average color of the given colors
     ///Can handle at most 2^24 colors
-    pub fn average(colors: &Vec<TrueColor>) -> TrueColor {
+    pub fn average(colors: &[TrueColor]) -> TrueColor {
         let mut red: u32 = 0;
         let mut green: u32 = 0;
         let mut blue: u32 = 0;
@@ -184,9 +241,127 @@ impl TrueColor {
 
 }
 
+///A 96-bit color with floating point channels ∈ [0.0, 1.0].
+///
+///Keeps the full precision of a `FloatColoringFunction` through SSAA averaging, so it can be exported without banding, e.g. as a 16-bit png or `OpenEXR` image
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FloatColor {
+    pub red: f32,
+    pub green: f32,
+    pub blue: f32
+}
+
+impl FloatColor {
+    pub fn new(red: f32, green: f32, blue: f32) -> FloatColor {
+        FloatColor { red, green, blue }
+    }
+
+    /// # Panics
+    /// If `color` is not equal to 'R', 'G', or 'B'
+    pub fn get_color(&self, color: char) -> f32 {
+        assert!(color == 'R' || color == 'G' || color == 'B', "Error: color should be equal to R, G, or B, color = {}",color);
+        match color {
+            'R' => self.red,
+            'G' => self.green,
+            'B' => self.blue,
+            _ => 0.0,
+        }
+    }
+
+    ///A `float_coloring_function`, the full precision version of `TrueColor::new_from_bernstein_polynomials`.
+    ///
+    ///The channels are clamped to [0.0, 1.0] like the bytes of the `TrueColor`, so SSAA averages the same colors
+    pub fn new_from_bernstein_polynomials(iterations: u32, max_iterations: u32) -> FloatColor {
+        let (red, green, blue) = bernstein_polynomials(f64::from(iterations) / f64::from(max_iterations));
+        FloatColor { red: red.clamp(0.0, 1.0) as f32, green: green.clamp(0.0, 1.0) as f32, blue: blue.clamp(0.0, 1.0) as f32 }
+    }
+
+    ///A `float_coloring_function`, the full precision version of `TrueColor::new_from_hsv_colors`
+    pub fn new_from_hsv_colors(iterations: u32, max_iterations: u32) -> FloatColor {
+        let rgb = hsv_colors(iterations, max_iterations);
+        FloatColor { red: rgb.red().clamp(0.0, 1.0) as f32, green: rgb.green().clamp(0.0, 1.0) as f32, blue: rgb.blue().clamp(0.0, 1.0) as f32 }
+    }
+
+    ///Computes the average color of the given colors, without rounding
+    pub fn average(colors: &[FloatColor]) -> FloatColor {
+        let divisor = f32::from(colors.len().clamp(1, usize::from(u16::MAX)) as u16);
+        let (red, green, blue) = colors.iter().fold((0.0, 0.0, 0.0), |(red, green, blue), color| (red + color.red, green + color.green, blue + color.blue));
+        FloatColor { red: red / divisor, green: green / divisor, blue: blue / divisor }
+    }
+}
+
+///Returns the (red, green, blue) channels of the Bernstein polynomial coloring of a normalized t ∈ [0, 1), see `TrueColor::new_from_bernstein_polynomials`.
+///
+///The channels are not clamped: green peaks at about 2.22 and blue at about 2.13, so they should be clamped to [0.0, 1.0]
+fn bernstein_polynomials(t: f64) -> (f64, f64, f64) {
+    let t = t.abs().min(0.999);
+    let one_minus_t = 1.0-t;
+    let red: f64 = 9.0 * one_minus_t * t.pow(3);
+    let green: f64 = 15.0 * one_minus_t * t.pow(2);
+    let blue: f64 = 8.5 * one_minus_t * t;
+    (red, green, blue)
+}
+
+///Returns the RGB color of the HSV coloring of `iterations`, see `TrueColor::new_from_hsv_colors`
+fn hsv_colors(iterations: u32, max_iterations: u32) -> Rgb<f64> {
+    let hue = 0.3 * f64::from(iterations);
+    let saturation = 1.0;//0.8;
+    let value: f64 = if iterations < max_iterations {1.0} else {0.0};
+    let hue_degree = Deg(hue % 359.999);
+    let hsv = Hsv::new(hue_degree,saturation,value);
+    Rgb::from_color(&hsv)
+}
+
 ///Maps a number t ∈ [0.0, 1.0] to a byte b ∈ [0, 255]
 fn normalized_to_byte(t: f64) -> u8 {
     let t = t.abs().min(1.0);
     let byte = (t * 255.0) as i16;
     byte.unsigned_abs() as u8 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static COLORING_ALGORITHMS: [ColoringAlgorithm; 2] = [ColoringAlgorithm::HSV, ColoringAlgorithm::BernsteinPolynomials];
+
+    ///Quantizes the channels of a `FloatColor` to bytes the way `TrueColor` does
+    fn quantize(color: FloatColor) -> [u8; 3] {
+        [color.red, color.green, color.blue].map(|channel| (f64::from(channel) * 255.0) as u8)
+    }
+
+    fn assert_same_color(float_color: FloatColor, true_color: TrueColor, context: &str) {
+        let quantized = quantize(float_color);
+        for (channel, byte) in quantized.into_iter().zip([true_color.red, true_color.green, true_color.blue]) {
+            assert!(channel.abs_diff(byte) <= 1, "{}: {:?} quantized to {:?} is not ({}, {}, {})", context, float_color, quantized, true_color.red, true_color.green, true_color.blue);
+        }
+    }
+
+    #[test]
+    fn float_colors_are_the_true_colors_at_full_precision() {
+        let max_iterations = 200;
+        for coloring_algorithm in COLORING_ALGORITHMS {
+            for iterations in 0..=max_iterations {
+                let float_color = coloring_algorithm.float_coloring_function()(iterations, max_iterations);
+                for channel in [float_color.red, float_color.green, float_color.blue] {
+                    assert!((0.0..=1.0).contains(&channel), "{:?} at {} iterations: {:?}", coloring_algorithm, iterations, float_color);
+                }
+                assert_same_color(float_color, coloring_algorithm.coloring_function()(iterations, max_iterations), &format!("{:?} at {} iterations", coloring_algorithm, iterations));
+            }
+        }
+    }
+
+    #[test]
+    fn averaged_float_colors_are_the_averaged_true_colors() {
+        //Samples on both sides of the saturated green and blue peaks of the Bernstein polynomials
+        let samples = [[10, 30, 60, 95], [40, 50, 55, 65], [1, 99, 5, 70]];
+        let max_iterations = 100;
+        for coloring_algorithm in COLORING_ALGORITHMS {
+            for iterations in samples {
+                let float_colors = iterations.map(|iterations| coloring_algorithm.float_coloring_function()(iterations, max_iterations));
+                let true_colors = iterations.map(|iterations| coloring_algorithm.coloring_function()(iterations, max_iterations));
+                assert_same_color(FloatColor::average(&float_colors), TrueColor::average(&true_colors), &format!("{:?} of {:?} iterations", coloring_algorithm, iterations));
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::{fs, io::{self, BufRead}, path::Path, str::FromStr};
+
+use crate::{checkpoint::Checkpoint, coloring::{ColorChannelMapping, ColoringAlgorithm}, complex::Complex, complex_plane::{ComplexPlane, View}, config::Config, distributed, error::Error, escape_data::{EscapeData, EscapeDataFormat},
+    float_image::{FloatImage, FloatImageFormat}, image_format::ImageFormat, mandelbrot_set::MandelbrotSet, metadata::Metadata, output::Output, pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane}, poster::Poster, pyramid::{Pyramid, PyramidFormat}, rendering};
+
+///A command of the command language, e.g. `view -0.74 0.1 1e-5`, mapping onto an operation of the `ComplexPlane`, `MandelbrotSet` or `PixelBuffer`
+#[derive(Debug, Clone)]
+pub enum Command {
+    ///`view <x> <y> <scale> [rotation]`: sets the view
+    View(View),
+    ///`center <x> <y>`: centers the view on x + yi, keeping the scale and rotation
+    Center(Complex),
+    ///`zoom <factor>`: zooms in by factor, zooms out if factor < 1
+    Zoom(f64),
+    ///`rotate <degrees>`: rotates the view counterclockwise
+    Rotate(f64),
+    ///`reset`: resets the view to the starting view
+    Reset,
+    ///`iter <max_iterations>`
+    Iterations(u32),
+    ///`julia <x> <y>` renders the Julia set of x + yi, `julia off` renders the Mandelbrot set
+    Julia(Option<Complex>),
+    ///`ssaa <n>`: sets the SSAA multiplier of the window and saved images, clamped from 1x to 64x
+    Supersampling(u8),
+    ///`image_ssaa <n>`: sets the SSAA multiplier of saved images, clamped from 1x to 64x
+    ImageSupersampling(u8),
+    ///`coloring <algorithm>`, e.g. `coloring hsv`
+    Coloring(ColoringAlgorithm),
+    ///`mapping <xyz>`: sets the color channel mapping, e.g. `mapping bgr`
+    Mapping(ColorChannelMapping),
+    ///`offset <iterations>`: shifts the palette by an amount of iterations
+    PaletteOffset(u32),
+    ///`render`: renders the view
+    Render,
+    ///`save [name]`: saves the view as `<output>/<name>`, the name is the `--name` template if it is not given, see `Output::path`.
+    ///The image format is chosen by the extension of the name, e.g. `name.jpg`, or else by `--format`
+    Save(Option<String>),
+    ///`poster <width> <height> [name]`: renders the view as a width x height png image streamed into `<output>/<name>.png`, for images too large to hold in memory
+    Poster(usize, usize, Option<String>),
+    ///`hdr <png16|exr|tiff> [name]`: renders the view at full precision and saves it as a 16-bit png, `OpenEXR` or float TIFF image `<output>/<name>.<png|exr|tif>`
+    Hdr(FloatImageFormat, Option<String>),
+    ///`escape <npy|bin|csv> [name]`: exports the raw escape data of every pixel of the view, e.g. its smooth iteration count, to `<output>/<name>.<npy|bin|csv>`
+    Escape(EscapeDataFormat, Option<String>),
+    ///`pyramid <dzi|iiif> <width> <height> <tile_size> <overlap> [name]`: renders the view as a width x height tile pyramid into `<output>/<name>`
+    Pyramid { format: PyramidFormat, width: usize, height: usize, tile_size: usize, overlap: usize, name: Option<String> },
+    ///`load <path>`: restores the view from the metadata of a saved png image
+    Load(String),
+    ///`print`: prints the view and the Mandelbrot set parameters
+    Print,
+}
+
+///Parses the argument at `index` of the command `name`
+fn argument<T: FromStr>(name: &str, arguments: &[&str], index: usize) -> Result<T, String>
+    where <T as FromStr>::Err: std::fmt::Display {
+    let argument = arguments.get(index).ok_or_else(|| format!("Missing argument {} of {}", index + 1, name))?;
+    argument.parse::<T>().map_err(|err| format!("{}: {} for argument {} of {}", err, argument, index + 1, name))
+}
+
+///Parses the argument at `index` of the command `name` as a finite number, rejecting NaN and infinity
+fn finite_argument(name: &str, arguments: &[&str], index: usize) -> Result<f64, String> {
+    let value: f64 = argument(name, arguments, index)?;
+    if !value.is_finite() {
+        return Err(format!("Argument {} of {} should be a finite number: {}", index + 1, name, arguments[index]));
+    }
+    Ok(value)
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    ///Parses a single command, its name followed by its whitespace separated arguments
+    fn from_str(s: &str) -> Result<Command, String> {
+        let mut words = s.split_whitespace();
+        let name = words.next().ok_or_else(|| String::from("Empty command"))?;
+        let arguments: Vec<&str> = words.collect();
+        let maximum_arguments = match name {
+            "view" => 4,
+            "poster" => 3,
+            "pyramid" => 6,
+            "center" | "julia" | "hdr" | "escape" => 2,
+            "reset" | "render" | "print" => 0,
+            "zoom" | "rotate" | "iter" | "ssaa" | "image_ssaa" | "coloring" | "mapping" | "offset" | "save" | "load" => 1,
+            _ => return Err(format!("Unknown command: {}", name)),
+        };
+        if arguments.len() > maximum_arguments {
+            return Err(format!("Too many arguments for {}: {}", name, s));
+        }
+        let command = match name {
+            "view" => {
+                let rotation = if arguments.len() > 3 {finite_argument(name, &arguments, 3)?} else {0.0};
+                Command::View(View::new_with_rotation(finite_argument(name, &arguments, 0)?, finite_argument(name, &arguments, 1)?, finite_argument(name, &arguments, 2)?, rotation))
+            }
+            "center" => Command::Center(Complex::new(finite_argument(name, &arguments, 0)?, finite_argument(name, &arguments, 1)?)),
+            "zoom" => Command::Zoom(finite_argument(name, &arguments, 0)?),
+            "rotate" => Command::Rotate(finite_argument(name, &arguments, 0)?),
+            "reset" => Command::Reset,
+            "iter" => Command::Iterations(argument(name, &arguments, 0)?),
+            "julia" if arguments.first() == Some(&"off") => Command::Julia(None),
+            "julia" => Command::Julia(Some(Complex::new(finite_argument(name, &arguments, 0)?, finite_argument(name, &arguments, 1)?))),
+            "ssaa" => Command::Supersampling(argument(name, &arguments, 0)?),
+            "image_ssaa" => Command::ImageSupersampling(argument(name, &arguments, 0)?),
+            "coloring" => Command::Coloring(argument(name, &arguments, 0)?),
+            "mapping" => Command::Mapping(argument(name, &arguments, 0)?),
+            "offset" => Command::PaletteOffset(argument(name, &arguments, 0)?),
+            "render" => Command::Render,
+            "save" => Command::Save(arguments.first().map(|name| String::from(*name))),
+            "poster" => Command::Poster(argument(name, &arguments, 0)?, argument(name, &arguments, 1)?, arguments.get(2).map(|name| String::from(*name))),
+            "hdr" => Command::Hdr(argument(name, &arguments, 0)?, arguments.get(1).map(|name| String::from(*name))),
+            "escape" => Command::Escape(argument(name, &arguments, 0)?, arguments.get(1).map(|name| String::from(*name))),
+            "pyramid" => Command::Pyramid {
+                format: argument(name, &arguments, 0)?,
+                width: argument(name, &arguments, 1)?,
+                height: argument(name, &arguments, 2)?,
+                tile_size: argument(name, &arguments, 3)?,
+                overlap: argument(name, &arguments, 4)?,
+                name: arguments.get(5).map(|name| String::from(*name)),
+            },
+            "load" => Command::Load(argument(name, &arguments, 0)?),
+            "print" => Command::Print,
+            _ => return Err(format!("Unknown command: {}", name)),
+        };
+        match command {
+            Command::Zoom(factor) if factor <= 0.0 => return Err(format!("The zoom factor should be positive: {}", s)),
+            Command::View(View { scale, .. }) if scale <= 0.0 => return Err(format!("The scale of the view should be positive: {}", s)),
+            _ => (),
+        }
+        Ok(command)
+    }
+}
+
+///A sequence of `Command`s, separated by semicolons or newlines, e.g. `view -0.74 0.1 1e-5; iter 5000; ssaa 8; save name`.
+///
+///Everything after a '#' on a line is a comment
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    pub commands: Vec<Command>,
+}
+
+impl Script {
+    ///Reads a `Script` from the script file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be read or contains a command that cannot be parsed
+    pub fn read_from_file(path: &str) -> Result<Script, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", err, path))?;
+        text.parse().map_err(|err| format!("Invalid script: {}: {}", err, path))
+    }
+}
+
+impl FromStr for Script {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Script, String> {
+        let mut commands = Vec::new();
+        for (line_number, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            for command in line.split(';').filter(|command| !command.trim().is_empty()) {
+                commands.push(command.parse().map_err(|err| format!("{} on line {}", err, line_number + 1))?);
+            }
+        }
+        Ok(Script { commands })
+    }
+}
+
+///The state `Command`s are run on: the view, the rendered `PixelBuffer`, the Mandelbrot set parameters and the rendering settings.
+///
+///Borrowed from the viewer, or owned by `run_headless`
+pub struct CommandContext<'a> {
+    pub c: &'a mut ComplexPlane,
+    pub p: &'a mut PixelBuffer,
+    pub m: &'a mut MandelbrotSet,
+    pub supersampling_amount: &'a mut u8,
+    pub image_supersampling_amount: &'a mut u8,
+    pub coloring_algorithm: &'a mut ColoringAlgorithm,
+    ///Size of saved images in pixels
+    pub image_width: usize,
+    pub image_height: usize,
+    ///Whether `p` holds the rendered current view, cleared by every `Command` that changes what should be rendered
+    pub rendered: bool,
+    ///Whether images rendered by `save` are checkpointed in tiles, see `Checkpoint`
+    pub checkpoint: bool,
+    ///Addresses of the workers images rendered by `save` are rendered on, see `distributed::render_distributed`
+    pub workers: Vec<String>,
+    ///Format of images saved by `save` whose name has no image format extension
+    pub image_format: ImageFormat,
+    ///Where saved files are written and how they are named
+    pub output: Output,
+}
+
+impl CommandContext<'_> {
+    ///Runs the `Commands` of `script` in order
+    /// # Errors
+    /// Returns the Error of the first `Command` that fails, the remaining `Command`s are not run
+    pub fn run_script(&mut self, script: &Script) -> Result<(), Error> {
+        for command in &script.commands {
+            self.run(command).map_err(|err| Error::Command { command: format!("{:?}", command), source: Box::new(err) })?;
+        }
+        Ok(())
+    }
+
+    ///Runs a single `command`
+    /// # Errors
+    /// Returns an Error if a png image cannot be loaded, the view cannot be rendered, or an image, poster, pyramid or escape data cannot be saved
+    pub fn run(&mut self, command: &Command) -> Result<(), Error> {
+        match command {
+            Command::View(view) => self.c.set_view(view),
+            Command::Center(center) => {
+                self.c.set_center(center);
+            }
+            Command::Zoom(factor) => self.c.scale(1.0 / factor),
+            Command::Rotate(degrees) => self.c.rotate_view(*degrees),
+            Command::Reset => self.c.reset(),
+            Command::Iterations(max_iterations) => self.m.max_iterations = *max_iterations,
+            Command::Julia(julia_c) => self.m.julia_c = *julia_c,
+            Command::Supersampling(supersampling_amount) => {
+                *self.supersampling_amount = (*supersampling_amount).clamp(1, 64);
+                *self.image_supersampling_amount = *self.supersampling_amount;
+            }
+            Command::ImageSupersampling(supersampling_amount) => *self.image_supersampling_amount = (*supersampling_amount).clamp(1, 64),
+            Command::Coloring(coloring_algorithm) => *self.coloring_algorithm = *coloring_algorithm,
+            Command::Mapping(color_channel_mapping) => self.p.color_channel_mapping = *color_channel_mapping,
+            Command::PaletteOffset(palette_offset) => self.p.palette_offset = *palette_offset,
+            Command::Render => {
+                rendering::render_complex_plane_into_buffer(self.p, self.c, self.m, *self.supersampling_amount, self.coloring_algorithm.coloring_function())?;
+                self.rendered = true;
+                return Ok(());
+            }
+            Command::Save(name) => {
+                self.save(name.as_deref())?;
+                return Ok(());
+            }
+            Command::Poster(width, height, name) => {
+                self.poster(*width, *height, name.as_deref())?;
+                return Ok(());
+            }
+            Command::Hdr(format, name) => {
+                self.save_float_image(*format, name.as_deref())?;
+                return Ok(());
+            }
+            Command::Escape(format, name) => {
+                let metadata = self.image_metadata();
+                //The name of the sidecar JSON description is reserved together with the data file, so neither overwrites an existing file
+                let extensions: &[&str] = if format.has_sidecar() { &[format.extension(), "json"] } else { &[format.extension()] };
+                let paths = self.output.paths(name.as_deref(), &metadata, extensions)?;
+                EscapeData::new(self.image_width, self.image_height, metadata).save_at(*format, &paths[0], paths.last().unwrap_or(&paths[0]))?;
+                return Ok(());
+            }
+            Command::Pyramid { format, width, height, tile_size, overlap, name } => {
+                let metadata = self.image_metadata();
+                let extension = format.descriptor_extension();
+                let base = Output::strip_extension(&self.output.path(name.as_deref(), &metadata, extension)?, extension);
+                Pyramid::new(*format, *width, *height, *tile_size, *overlap, metadata).save(&base)?;
+                return Ok(());
+            }
+            Command::Load(path) => self.load(path)?,
+            Command::Print => {
+                self.c.print();
+                println!("Mandelbrot set parameters: max. iterations is {} and orbit radius is {}", self.m.max_iterations, self.m.orbit_radius);
+                return Ok(());
+            }
+        }
+        self.rendered = false;
+        Ok(())
+    }
+
+    ///Saves the current view in the output directory as `file_name`, or as the file name template if no name is given, see `Output::path`.
+    ///The view is rendered at the image resolution with the image SSAA multiplier, the rendered `PixelBuffer` is saved directly if it already has the image resolution.
+    ///The image format is chosen by the extension of `file_name`, e.g. `name.jpg`, or else is the `image_format`
+    /// # Errors
+    /// Returns an Error if the output directory cannot be created, the render is `checkpoint`ed and the `Checkpoint` cannot be used,
+    /// every worker of a distributed render failed, or the image cannot be saved
+    pub fn save(&self, file_name: Option<&str>) -> Result<(), Error> {
+        let (file_name_without_extension, format) = file_name.map_or((None, self.image_format), |file_name| {
+            let (file_name_without_extension, format) = self.image_format.split_file_name(file_name);
+            (Some(file_name_without_extension), format)
+        });
+        let metadata = self.image_metadata();
+        let path = self.output.path(file_name_without_extension, &metadata, format.extension())?;
+        if self.rendered && self.p.pixel_plane.width == self.image_width && self.p.pixel_plane.height == self.image_height {
+            self.p.save_at(&path, format, &metadata)?;
+        } else if !self.workers.is_empty() {
+            let image_p = distributed::render_distributed(self.image_width, self.image_height, &metadata, &self.workers)?;
+            image_p.save_at(&path, format, &metadata)?;
+        } else if self.checkpoint {
+            let checkpoint = Checkpoint::new(&self.output.directory, self.image_width, self.image_height, metadata.clone());
+            let image_p = checkpoint.render()?;
+            image_p.save_at(&path, format, &metadata)?;
+            checkpoint.remove()?;
+        } else {
+            let mut image_p: PixelBuffer = PixelBuffer::new(PixelPlane::new(self.image_width, self.image_height));
+            let mut image_c: ComplexPlane = ComplexPlane::new(self.image_width, self.image_height);
+            image_p.color_channel_mapping = self.p.color_channel_mapping;
+            image_p.palette_offset = self.p.palette_offset;
+            image_c.set_view(&metadata.view);
+            rendering::render_complex_plane_into_buffer(&mut image_p, &image_c, self.m, *self.image_supersampling_amount, self.coloring_algorithm.coloring_function())?;
+            image_p.save_at(&path, format, &metadata)?;
+        }
+        println!("Saved the image: {}", path.display());
+        Ok(())
+    }
+
+    ///Returns the `Metadata` of an image of the current view rendered with the image SSAA multiplier
+    pub fn image_metadata(&self) -> Metadata {
+        Metadata::new(self.c.get_view(), self.m.clone(), *self.coloring_algorithm, self.p.color_channel_mapping, self.p.palette_offset, *self.image_supersampling_amount)
+    }
+
+    ///Renders the current view as a `Poster` of `width` x `height` pixels with the image SSAA multiplier, streamed into a png image in the output directory, see `Output::path`
+    /// # Errors
+    /// Returns an Error if the output directory cannot be created, or the `Poster` cannot be saved, see `Poster::save_as_png_at`
+    pub fn poster(&self, width: usize, height: usize, file_name_without_extension: Option<&str>) -> Result<(), Error> {
+        let metadata = self.image_metadata();
+        let path = self.output.path(file_name_without_extension, &metadata, "png")?;
+        Poster::new(width, height, metadata).save_as_png_at(&path)
+    }
+
+    ///Renders the current view at the image resolution with the image SSAA multiplier as a `FloatImage`, and saves it as `format` in the output directory, see `Output::path`
+    /// # Errors
+    /// Returns an Error if the output directory cannot be created, or the `FloatImage` cannot be rendered or saved, see `FloatImage::save_at`
+    pub fn save_float_image(&self, format: FloatImageFormat, file_name_without_extension: Option<&str>) -> Result<(), Error> {
+        let metadata = self.image_metadata();
+        let path = self.output.path(file_name_without_extension, &metadata, format.extension())?;
+        FloatImage::render(self.image_width, self.image_height, metadata)?.save_at(format, &path)
+    }
+
+    ///Restores the view, Mandelbrot set parameters, coloring settings and image SSAA multiplier from the `Metadata` of the png image at `path`
+    /// # Errors
+    /// Returns an Error if the `Metadata` cannot be read, see `Metadata::read_from_png`
+    pub fn load(&mut self, path: &str) -> Result<(), String> {
+        let metadata = Metadata::read_from_png(path)?;
+        println!("Loaded metadata: {:?}", metadata);
+        self.c.set_view(&metadata.view);
+        *self.m = metadata.mandelbrot_set;
+        *self.coloring_algorithm = metadata.coloring_algorithm;
+        self.p.color_channel_mapping = metadata.color_channel_mapping;
+        self.p.palette_offset = metadata.palette_offset;
+        *self.image_supersampling_amount = metadata.supersampling_amount.clamp(1, 64);
+        self.rendered = false;
+        Ok(())
+    }
+}
+
+///Runs commands without opening the window, on a view of the image size given by `config`.
+///
+///Runs the script file given by `config.script`, or else reads commands from stdin until it is closed, printing the errors of failed commands
+/// # Errors
+/// Returns an Error if the script file cannot be read or one of its commands fails
+pub fn run_headless(config: &Config, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping) -> Result<(), Error> {
+    let mut c = ComplexPlane::new(config.image_width, config.image_height);
+    let mut p = PixelBuffer::new(PixelPlane::new(config.image_width, config.image_height));
+    p.color_channel_mapping = color_channel_mapping;
+    let mut m = MandelbrotSet::new(config.max_iterations, config.orbit_radius);
+    let (mut supersampling_amount, mut image_supersampling_amount, mut coloring_algorithm) = (config.supersampling_amount, config.supersampling_amount, coloring_algorithm);
+    let mut context = CommandContext {
+        c: &mut c,
+        p: &mut p,
+        m: &mut m,
+        supersampling_amount: &mut supersampling_amount,
+        image_supersampling_amount: &mut image_supersampling_amount,
+        coloring_algorithm: &mut coloring_algorithm,
+        image_width: config.image_width,
+        image_height: config.image_height,
+        rendered: false,
+        checkpoint: config.checkpoint,
+        workers: config.workers.clone(),
+        image_format: config.image_format,
+        output: config.output.clone(),
+    };
+    if let Some(path) = &config.script {
+        return context.run_script(&Script::read_from_file(path)?);
+    }
+    println!("Reading commands from stdin, e.g. view -0.74 0.1 1e-5; iter 5000; ssaa 8; save name");
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|err| Error::io(Path::new("stdin"), err))?;
+        if let Err(err) = line.parse().map_err(Error::Other).and_then(|script| context.run_script(&script)) {
+            eprintln!("Error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(s: &str) -> String {
+        s.parse::<Command>().unwrap_err()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn parses_commands_and_their_arguments() {
+        assert!(matches!("view -0.74 0.1 1e-5".parse(), Ok(Command::View(View { x, y, scale, rotation })) if x == -0.74 && y == 0.1 && scale == 1e-5 && rotation == 0.0));
+        assert!(matches!("view 0 0 1 90".parse(), Ok(Command::View(View { rotation, .. })) if rotation == 90.0));
+        assert!(matches!("  zoom   2.5 ".parse(), Ok(Command::Zoom(factor)) if factor == 2.5));
+        assert!(matches!("julia off".parse(), Ok(Command::Julia(None))));
+        assert!(matches!("julia -0.8 0.156".parse(), Ok(Command::Julia(Some(c))) if c.x == -0.8 && c.y == 0.156));
+        assert!(matches!("save".parse(), Ok(Command::Save(None))));
+        assert!(matches!("save name.jpg".parse(), Ok(Command::Save(Some(name))) if name == "name.jpg"));
+        assert!(matches!("poster 8000 6000".parse(), Ok(Command::Poster(8000, 6000, None))));
+        assert!(matches!("pyramid dzi 4096 4096 256 1 tiles".parse(), Ok(Command::Pyramid { width: 4096, tile_size: 256, overlap: 1, name: Some(_), .. })));
+        assert!(matches!("coloring hsv".parse(), Ok(Command::Coloring(ColoringAlgorithm::HSV))));
+    }
+
+    #[test]
+    fn rejects_malformed_commands() {
+        assert_eq!(parse_error(""), "Empty command");
+        assert_eq!(parse_error("   "), "Empty command");
+        assert_eq!(parse_error("fly 1 2"), "Unknown command: fly");
+        assert_eq!(parse_error("render now"), "Too many arguments for render: render now");
+        assert_eq!(parse_error("view 0 0 1 0 5"), "Too many arguments for view: view 0 0 1 0 5");
+        assert_eq!(parse_error("view 0 0"), "Missing argument 3 of view");
+        assert_eq!(parse_error("center 0.5"), "Missing argument 2 of center");
+        assert!(parse_error("iter many").ends_with(": many for argument 1 of iter"));
+        assert!(parse_error("iter -5").ends_with(": -5 for argument 1 of iter"));
+        assert!(parse_error("ssaa 300").ends_with(": 300 for argument 1 of ssaa"));
+        assert!(parse_error("mapping rgbx").ends_with(": rgbx for argument 1 of mapping"));
+        assert_eq!(parse_error("zoom 0"), "The zoom factor should be positive: zoom 0");
+        assert_eq!(parse_error("zoom -2"), "The zoom factor should be positive: zoom -2");
+        assert_eq!(parse_error("zoom nan"), "Argument 1 of zoom should be a finite number: nan");
+        assert_eq!(parse_error("zoom inf"), "Argument 1 of zoom should be a finite number: inf");
+        assert_eq!(parse_error("rotate NaN"), "Argument 1 of rotate should be a finite number: NaN");
+        assert_eq!(parse_error("center 0 -inf"), "Argument 2 of center should be a finite number: -inf");
+        assert_eq!(parse_error("julia nan 0"), "Argument 1 of julia should be a finite number: nan");
+        assert_eq!(parse_error("view 0 0 0"), "The scale of the view should be positive: view 0 0 0");
+        assert_eq!(parse_error("view 0 0 -1"), "The scale of the view should be positive: view 0 0 -1");
+        assert_eq!(parse_error("view nan 0 1"), "Argument 1 of view should be a finite number: nan");
+        assert_eq!(parse_error("view 0 0 1 infinity"), "Argument 4 of view should be a finite number: infinity");
+    }
+
+    #[test]
+    fn parses_scripts_with_separators_and_comments() {
+        let script: Script = "view -0.74 0.1 1e-5; iter 5000 # deep\n\n# only a comment\nssaa 8;;save name;\n".parse().unwrap();
+        assert_eq!(script.commands.len(), 4);
+        assert!(matches!(script.commands[1], Command::Iterations(5000)));
+        assert!(matches!(script.commands[3], Command::Save(Some(ref name)) if name == "name"));
+        assert!("".parse::<Script>().unwrap().commands.is_empty());
+    }
+
+    #[test]
+    fn script_errors_give_the_line_number() {
+        assert_eq!("render\nzoom 2; fly\n".parse::<Script>().unwrap_err(), "Unknown command: fly on line 2");
+        assert_eq!("iter 10 # iter x\nview 1".parse::<Script>().unwrap_err(), "Missing argument 2 of view on line 2");
+    }
+}
@@ -1,6 +1,9 @@
 use std::fmt;
 
+use serde::{Deserialize, Serialize};
+
 ///Complex number (x + bi), i^2 = -1: <https://en.wikipedia.org/wiki/Complex_number>
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct Complex {
     /// Real part
     pub x: f64,
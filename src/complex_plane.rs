@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::complex::Complex;
 
 /// # Complex plane
@@ -32,6 +34,9 @@ pub struct ComplexPlane {
     // Complex plane increments
     pub increment_x: f64,
     pub increment_y: f64,
+    // Rotation in degrees of the view around its center, counterclockwise, and its (sine, cosine)
+    rotation: f64,
+    rotation_sin_cos: (f64, f64),
     // Pixel plane width and height
     width: usize,
     height: usize,
@@ -63,6 +68,8 @@ impl ComplexPlane {
             length_y,
             increment_x,
             increment_y,
+            rotation: 0.0,
+            rotation_sin_cos: (0.0, 1.0),
             width,
             height,
         }
@@ -76,26 +83,67 @@ impl ComplexPlane {
         self.max_y += y;
     }
 
+    /// Translate the Complex plane along the axes of the (rotated) pixel plane, by x along its x-axis and y along its y-axis.
+    ///
+    /// Equal to `translate(x, y)` if the Complex plane is not rotated. Translating the pixel plane by whole pixels this way keeps the rendered pixels valid,
+    /// which is what `PixelBuffer::translate_buffer` requires
+    pub fn translate_along_view(&mut self, x: f64, y: f64) {
+        let translation = self.rotate(&Complex::new(x, -y)); //Negate y because the Complex plane and pixel plane are flipped
+        self.translate(translation.x, -translation.y);
+    }
+
     /// Convert the point (x,y) in the pixel plane to the complex number a+bi in the complex plane
     pub fn complex_from_pixel_plane(&self, x: f64, y: f64) -> Complex {
         let x = x.mul_add(self.increment_x, self.min_x);
         let y = -y.mul_add(self.increment_y, self.min_y); //Negate because math plane is bottom-top, and screen plane is top-bottom
-        let c = Complex::new(x, y);
+        //Rotate (x,y) around the center
+        let center = self.center();
+        let rotated = self.rotate(&Complex::new(x - center.x, y - center.y));
+        let c = center.add(&rotated);
         c
     }
 
+    /// Rotates the vector `v` counterclockwise by the rotation of the Complex plane
+    pub fn rotate(&self, v: &Complex) -> Complex {
+        let (sin, cos) = self.rotation_sin_cos;
+        Complex::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+    }
+
+    /// Rotates the vector `v` clockwise by the rotation of the Complex plane, the inverse of `rotate`
+    pub fn unrotate(&self, v: &Complex) -> Complex {
+        let (sin, cos) = self.rotation_sin_cos;
+        Complex::new(v.x * cos + v.y * sin, -v.x * sin + v.y * cos)
+    }
+
+    /// Returns the rotation of the view around its center in degrees, counterclockwise, ∈ [0, 360)
+    pub fn get_rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// Sets the rotation of the view around its center to `degrees`, counterclockwise
+    pub fn set_rotation(&mut self, degrees: f64) {
+        self.rotation = degrees.rem_euclid(360.0);
+        self.rotation_sin_cos = self.rotation.to_radians().sin_cos();
+    }
+
+    /// Rotates the view around its center by `degrees`, counterclockwise
+    pub fn rotate_view(&mut self, degrees: f64) {
+        self.set_rotation(self.rotation + degrees);
+    }
+
     /// Prints: "Complex plane: R ∈ [{},{}] and C ∈ [{},{}]",`c.min_x`, `c.max_x`, `c.min_y`, `c.max_y`y
     pub fn print(&self) {
         println!("Complex plane:\tR ∈ [{},{}]", self.min_x, self.max_x);
         println!("\t\tC ∈ [{},{}]", self.min_y, self.max_y);
         println!(
-            "\t\tCenter is {:?} and scale is {}",
+            "\t\tCenter is {:?}, scale is {} and rotation is {}°",
             self.center(),
-            self.get_scale()
+            self.get_scale(),
+            self.rotation
         );
     }
 
-    /// Resets the total translation and scaling applied to the Complex plane by the translate() and scale() functions
+    /// Resets the total translation and scaling applied to the Complex plane by the `translate()` and `scale()` functions
     pub fn reset(&mut self) {
         *self = ComplexPlane::new(self.width, self.height);
     }
@@ -122,6 +170,27 @@ impl ComplexPlane {
         self.set_center(&center);
     }
 
+    /// Scale the complex plane by factor around `anchor`, instead of around the center: the point `anchor` stays at the same position in the pixel plane.
+    /// If factor > 1.0: zoom out
+    /// If factor < 1.0: zoom in
+    pub fn scale_around(&mut self, factor: f64, anchor: &Complex) {
+        let center = self.center();
+        self.scale(factor);
+        let new_center = center.add(&anchor.subtract(&center).multiply_real(1.0 - factor));
+        self.set_center(&new_center);
+    }
+
+    /// Resize the Complex plane to a pixel plane of `width` x `height` pixels, keeping the scale and rotation of the view.
+    /// The complex number at pixel (`old_width / 2`, `old_height / 2`) moves to pixel (`width / 2`, `height / 2`), so the center moves by at most half a pixel, see `PixelBuffer::resize`
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let anchor = self.complex_from_pixel_plane(f64::from((self.width / 2) as u32), f64::from((self.height / 2) as u32));
+        let view = self.get_view();
+        *self = ComplexPlane::new(width, height);
+        self.set_view(&view);
+        let translation = anchor.subtract(&self.complex_from_pixel_plane(f64::from((width / 2) as u32), f64::from((height / 2) as u32)));
+        self.set_center(&self.center().add(&translation));
+    }
+
     /// Returns the center of the Complex plane bounded by `min_x`, `min_y`, `max_x`, `max_y`
     pub fn center(&self) -> Complex {
         let x = self.min_x + (self.max_x - self.min_x) / 2.0;
@@ -150,10 +219,11 @@ impl ComplexPlane {
         self.scale(scale);
     }
 
-    /// Set the Complex plane at Center (x,y) at the given scale, where scale == 1 => max_x-min_x=2.5
+    /// Set the Complex plane at Center (x,y) at the given scale and rotation, where scale == 1 => max_x-min_x=2.5
     pub fn set_view(&mut self, view: &View)
     {
         self.set_view_separated(view.x, view.y, view.scale);
+        self.set_rotation(view.rotation);
     }
 
     pub fn pixels_to_imaginary(&self, amount: u8) -> f64 {
@@ -175,24 +245,38 @@ impl ComplexPlane {
     pub fn get_view(&self) -> View {
         let center = self.center();
         let scale = self.get_scale();
-        View::new(center.x, center.y, scale)
+        View::new_with_rotation(center.x, center.y, scale, self.rotation)
     }
 }
 
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub struct View {
-    x: f64,
-    y: f64,
-    scale: f64
+    /// Real part of the center
+    pub x: f64,
+    /// Imaginary part of the center
+    pub y: f64,
+    pub scale: f64,
+    /// Rotation in degrees around the center, counterclockwise
+    #[serde(default)]
+    pub rotation: f64,
 }
 
 impl View {
     pub const fn new(x: f64, y: f64, scale: f64) -> View {
-        View {x,y,scale}
+        View {x,y,scale,rotation: 0.0}
+    }
+
+    pub const fn new_with_rotation(x: f64, y: f64, scale: f64, rotation: f64) -> View {
+        View {x,y,scale,rotation}
     }
 }
 
 impl std::fmt::Debug for View {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "z = {:?}, scale = {}", Complex::new(self.x, self.y), self.scale)
+        write!(f, "z = {:?}, scale = {}", Complex::new(self.x, self.y), self.scale)?;
+        if self.rotation.abs() > f64::EPSILON {
+            write!(f, ", rotation = {}°", self.rotation)?;
+        }
+        Ok(())
     }
 }
\ No newline at end of file
@@ -1,5 +1,7 @@
 use std::{str::FromStr, fmt::{Display, self}};
 
+use crate::{error::Error, history::HISTORY_CACHE_SIZE, image_format::ImageFormat, output::{Output, DEFAULT_FILE_NAME_TEMPLATE, DEFAULT_OUTPUT_DIRECTORY}};
+
 //Argument default values
 static WIDTH: usize = 1200;
 static HEIGHT: usize = 800;
@@ -7,7 +9,10 @@ static MAX_ITERATIONS: u32 = 1000;
 static ORBIT_RADIUS: f64 = 2.0;
 static SUPERSAMPLING_AMOUNT: u8 = 1;
 static WINDOW_SCALE: f64 = 1.0;
+static SCREEN_WIDTH: usize = 1920;
+static SCREEN_HEIGHT: usize = 1080;
 
+#[derive(Clone)]
 pub struct Config {
     // Window dimensions in pixels
     pub window_width: usize,
@@ -21,20 +26,110 @@ pub struct Config {
     pub window_scale: f64,
     //Scaled window dimensions in pixels (used in images)
     pub image_width: usize,
-    pub image_height: usize
+    pub image_height: usize,
+    //Path of a png image saved by this application, its metadata is used to restore the view at startup (--load <path>)
+    pub load: Option<String>,
+    //Path of a JSON zoom animation file, its frames are rendered instead of opening the window (--animate <path>)
+    pub animate: Option<String>,
+    //Path of a JSON keyframe file, its frames are rendered instead of opening the window (--keyframes <path>)
+    pub keyframes: Option<String>,
+    //Screen dimensions in pixels, used as the window dimensions in fullscreen mode (--screen <width>x<height>)
+    pub screen_width: usize,
+    pub screen_height: usize,
+    //Path of a JSON key map file that remaps the key bindings (--keys <path>)
+    pub keys: Option<String>,
+    //Path of a JSON history file exported by the viewer, its navigation history is continued at its current view (--history <path>)
+    pub history: Option<String>,
+    //Amount of most recent views in the navigation history that keep their rendered image, 0 disables caching (--history-cache <n>)
+    pub history_cache_size: usize,
+    //Whether to restore the session saved on exit (--resume)
+    pub resume: bool,
+    //Path of a script file, its commands are run at startup (--script <path>)
+    pub script: Option<String>,
+    //Whether to run the script, or the commands read from stdin, instead of opening the window (--headless)
+    pub headless: bool,
+    //Address to serve tiles at over HTTP, instead of opening the window (--serve <address>)
+    pub serve: Option<String>,
+    //Directory in which the served tiles are cached (--tile-cache <path>)
+    pub tile_cache: Option<String>,
+    //Whether renders of --headless, --animate and --keyframes are checkpointed in tiles, continuing an earlier checkpoint (--checkpoint)
+    pub checkpoint: bool,
+    //Address to render jobs of distributed renders at, instead of opening the window (--worker <address>)
+    pub worker: Option<String>,
+    //Addresses of the workers saved images are rendered on (--workers <address,...>)
+    pub workers: Vec<String>,
+    //Format of saved images whose name has no image format extension (--format <png|ppm|bmp|tiff|jpeg[:quality]>)
+    pub image_format: ImageFormat,
+    //Directory saved images, posters, pyramids, escape data and animations are written to (--output <directory>), the template of the names of
+    //files saved without a name, e.g. {coloring}_{iter}_{seq} (--name <template>), and whether saving overwrites existing files (--overwrite)
+    pub output: Output,
 }
 
 
 impl Config {
     /// Parse the command line arguments from e.g. `env::args` in the following format
     /// ```ignore
-    /// cargo run -- width height max_iterations
+    /// cargo run -- width height max_iterations supersampling_amount window_scale [--load <path>] [--animate <path>] [--keyframes <path>] [--screen <width>x<height>] [--keys <path>] [--history <path>] [--history-cache <n>] [--resume] [--script <path>] [--headless] [--serve <address>] [--tile-cache <path>] [--checkpoint] [--worker <address>] [--workers <address,...>] [--format <png|ppm|bmp|tiff|jpeg[:quality]>] [--output <directory>] [--name <template>] [--overwrite]
     /// ```
     /// # Errors
-    /// Returns an Error if any of the given arguments couldn't be parsed into their types
-    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
+    /// Returns an Error if any of the given arguments couldn't be parsed into their types, or if an unknown option is given
+    pub fn build(args: impl Iterator<Item = String>) -> Result<Config, Error> {
+        Config::parse(args).map_err(Error::Config)
+    }
+
+    ///Parses the command line arguments, see `build`
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
         args.next(); //Skip the first argument as it is the name of the executable
 
+        //Split the options from the positional arguments
+        let mut load = None;
+        let mut animate = None;
+        let mut keyframes = None;
+        let mut screen = (SCREEN_WIDTH, SCREEN_HEIGHT);
+        let mut keys = None;
+        let mut history = None;
+        let mut history_cache_size = HISTORY_CACHE_SIZE;
+        let mut resume = false;
+        let mut script = None;
+        let mut headless = false;
+        let mut serve = None;
+        let mut tile_cache = None;
+        let mut checkpoint = false;
+        let mut worker = None;
+        let mut workers = Vec::new();
+        let mut image_format = ImageFormat::Png;
+        let mut output_directory = String::from(DEFAULT_OUTPUT_DIRECTORY);
+        let mut file_name_template = String::from(DEFAULT_FILE_NAME_TEMPLATE);
+        let mut overwrite = false;
+        let mut positional_arguments = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--load" => load = Some(Config::parse_option_value("--load", args.next())?),
+                "--animate" => animate = Some(Config::parse_option_value("--animate", args.next())?),
+                "--keyframes" => keyframes = Some(Config::parse_option_value("--keyframes", args.next())?),
+                "--screen" => screen = Config::parse_size("--screen", &Config::parse_option_value("--screen", args.next())?)?,
+                "--keys" => keys = Some(Config::parse_option_value("--keys", args.next())?),
+                "--history" => history = Some(Config::parse_option_value("--history", args.next())?),
+                "--history-cache" => history_cache_size = Config::parse_option_value("--history-cache", args.next())?.parse()
+                    .map_err(|err| format!("Invalid value for --history-cache option: {}", err))?,
+                "--resume" => resume = true,
+                "--script" => script = Some(Config::parse_option_value("--script", args.next())?),
+                "--headless" => headless = true,
+                "--serve" => serve = Some(Config::parse_option_value("--serve", args.next())?),
+                "--tile-cache" => tile_cache = Some(Config::parse_option_value("--tile-cache", args.next())?),
+                "--checkpoint" => checkpoint = true,
+                "--worker" => worker = Some(Config::parse_option_value("--worker", args.next())?),
+                "--workers" => workers = Config::parse_option_value("--workers", args.next())?.split(',').filter(|address| !address.is_empty()).map(String::from).collect(),
+                "--format" => image_format = Config::parse_option_value("--format", args.next())?.parse()?,
+                "--output" => output_directory = Config::parse_option_value("--output", args.next())?,
+                "--name" => file_name_template = Config::parse_option_value("--name", args.next())?,
+                "--overwrite" => overwrite = true,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option: {}", arg)),
+                _ => positional_arguments.push(arg),
+            }
+        }
+        let mut args = positional_arguments.into_iter();
+
         //First argument
         let image_width = Config::parse_argument("width", args.next(), WIDTH)?; 
 
@@ -53,7 +148,28 @@ impl Config {
         let window_width = (f64::from(image_width as u32) * window_scale) as usize;
         let window_height = (f64::from(image_height as u32) * window_scale) as usize;
 
-        Ok(Config {window_width, window_height, max_iterations, orbit_radius: ORBIT_RADIUS, supersampling_amount, window_scale, image_width, image_height})
+        Ok(Config {window_width, window_height, max_iterations, orbit_radius: ORBIT_RADIUS, supersampling_amount, window_scale, image_width, image_height, load, animate, keyframes, screen_width: screen.0, screen_height: screen.1, keys, history, history_cache_size, resume, script, headless, serve, tile_cache, checkpoint, worker, workers, image_format, output: Output::new(&output_directory, &file_name_template, overwrite)})
+    }
+
+    ///Returns the value given to `option`, or an Error if no value was given
+    /// # Errors
+    /// Returns an Error if `value` is None
+    pub fn parse_option_value(option: &str, value: Option<String>) -> Result<String, String> {
+        value.ok_or_else(|| format!("No value given for {} option", option))
+    }
+
+    ///Parses a size in pixels given to `option` in the format `<width>x<height>`, e.g. `1920x1080`
+    /// # Errors
+    /// Returns an Error if `value` is not in the format `<width>x<height>`, or if the width or height is 0
+    pub fn parse_size(option: &str, value: &str) -> Result<(usize, usize), String> {
+        let invalid = || format!("Invalid size for {} option, expected <width>x<height>: {}", option, value);
+        let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+        let width: usize = width.trim().parse().map_err(|_| invalid())?;
+        let height: usize = height.trim().parse().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            return Err(invalid());
+        }
+        Ok((width, height))
     }
 
     ///Parses an argument to a T value if possible, returns an error if not. Returns default if argument is None </br>
@@ -87,6 +203,6 @@ impl Config {
 
 impl fmt::Debug for Config {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { //TODO: Improve debug printing format legibility
-        f.debug_struct("Config").field("window_width", &self.window_width).field("window_height", &self.window_height).field("max_iterations", &self.max_iterations).field("orbit_radius", &self.orbit_radius).field("supersampling_amount", &self.supersampling_amount).field("window_scale", &self.window_scale).field("image_width", &self.image_width).field("image_height", &self.image_height).finish()
+        f.debug_struct("Config").field("window_width", &self.window_width).field("window_height", &self.window_height).field("max_iterations", &self.max_iterations).field("orbit_radius", &self.orbit_radius).field("supersampling_amount", &self.supersampling_amount).field("window_scale", &self.window_scale).field("image_width", &self.image_width).field("image_height", &self.image_height).field("load", &self.load).field("animate", &self.animate).field("keyframes", &self.keyframes).field("screen_width", &self.screen_width).field("screen_height", &self.screen_height).field("keys", &self.keys).field("history", &self.history).field("history_cache_size", &self.history_cache_size).field("resume", &self.resume).field("script", &self.script).field("headless", &self.headless).field("serve", &self.serve).field("tile_cache", &self.tile_cache).field("checkpoint", &self.checkpoint).field("worker", &self.worker).field("workers", &self.workers).field("image_format", &self.image_format).field("output", &self.output).finish()
     }
 }
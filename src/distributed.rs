@@ -0,0 +1,229 @@
+use std::{collections::VecDeque, io::{BufReader, BufWriter}, net::{TcpListener, TcpStream, ToSocketAddrs}, sync::{Mutex, atomic::{AtomicUsize, Ordering}, mpsc::{self, Sender}},
+    thread, time::{Duration, Instant}};
+
+use crate::{metadata::Metadata, pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane}, rendering::{self, RenderBox}};
+
+use self::protocol::{Job, JobResult};
+
+pub mod protocol;
+
+///Width and height in pixels of the `RenderBox` of a `Job`
+pub static JOB_TILE_SIZE: usize = 128;
+///Time after which a worker that has not returned the result of its `Job` is considered dead
+#[allow(clippy::duration_suboptimal_units)] //Duration::from_mins requires a much newer toolchain than the rest of the crate
+pub static JOB_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+///Time after which connecting to a worker fails
+static CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+///Time an idle worker waits before checking the queue again, while the `Job`s of other workers could still be re-queued
+static IDLE_WAIT: Duration = Duration::from_millis(50);
+
+///The `Job`s of a distributed render that are waiting for a worker, and the amount of `Job`s that have not been completed
+struct JobQueue {
+    jobs: Mutex<VecDeque<Job>>,
+    remaining: AtomicUsize,
+}
+
+impl JobQueue {
+    ///Returns the next `Job`, waiting while the `Job`s of other workers could still be re-queued, or None if every `Job` has been completed
+    fn next(&self) -> Option<Job> {
+        loop {
+            let job = self.jobs.lock().unwrap().pop_front();
+            if job.is_some() || self.remaining.load(Ordering::SeqCst) == 0 {
+                return job;
+            }
+            thread::sleep(IDLE_WAIT);
+        }
+    }
+
+    ///Puts the `Job` of a dead worker back at the front of the queue
+    fn requeue(&self, job: Job) {
+        self.jobs.lock().unwrap().push_front(job);
+    }
+}
+
+///Sends `Job`s from the `queue` to the worker at `address` one at a time, and sends their results to `results`, until every `Job` has been completed
+/// # Errors
+/// Returns an Error if the worker cannot be reached or fails, its current `Job` is re-queued
+fn coordinate_worker(address: &str, queue: &JobQueue, results: &Sender<(Job, JobResult)>) -> Result<(), String> {
+    let socket_address = address.to_socket_addrs().map_err(|err| format!("{}: {}", err, address))?.next().ok_or_else(|| format!("Unknown address: {}", address))?;
+    let stream = TcpStream::connect_timeout(&socket_address, CONNECT_TIMEOUT).map_err(|err| err.to_string())?;
+    stream.set_read_timeout(Some(JOB_TIMEOUT)).map_err(|err| err.to_string())?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|err| err.to_string())?);
+    let mut writer = BufWriter::new(stream);
+    while let Some(job) = queue.next() {
+        match job.write_to(&mut writer).and_then(|()| JobResult::read_from(&mut reader, &job)) {
+            Ok(result) => {
+                results.send((job, result)).map_err(|err| err.to_string())?;
+                queue.remaining.fetch_sub(1, Ordering::SeqCst);
+            }
+            Err(err) => {
+                let id = job.id;
+                queue.requeue(job);
+                return Err(format!("{}, job {} was re-queued", err, id));
+            }
+        }
+    }
+    Ok(())
+}
+
+///Renders an image of `width` x `height` pixels from `metadata` on the `workers`, e.g. 127.0.0.1:9000, and assembles it into a `PixelBuffer`.
+///
+///The image is split into `Job`s of `JOB_TILE_SIZE` x `JOB_TILE_SIZE` pixels, which are sent to the workers as they complete their previous `Job`.
+///The `Job` of a worker that cannot be reached, closes the connection or times out is re-queued for the other workers
+/// # Errors
+/// Returns an Error if every worker failed before all `Job`s were completed
+/// # Panics
+/// If the `JobQueue` mutex is poisoned
+pub fn render_distributed(width: usize, height: usize, metadata: &Metadata, workers: &[String]) -> Result<PixelBuffer, String> {
+    let jobs: VecDeque<Job> = RenderBox::split(width, height, JOB_TILE_SIZE).into_iter().enumerate()
+        .map(|(id, render_box)| Job { id, width, height, render_box, metadata: metadata.clone() })
+        .collect();
+    let job_count = jobs.len();
+    let queue = JobQueue { jobs: Mutex::new(jobs), remaining: AtomicUsize::new(job_count) };
+    let mut p = PixelBuffer::new(PixelPlane::new(width, height));
+    p.color_channel_mapping = metadata.color_channel_mapping;
+    p.palette_offset = metadata.palette_offset;
+    println!("Rendering {} jobs on {} workers: {}", job_count, workers.len(), workers.join(", "));
+    let time = Instant::now();
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for address in workers {
+            let (queue, sender) = (&queue, sender.clone());
+            scope.spawn(move || {
+                if let Err(err) = coordinate_worker(address, queue, &sender) {
+                    eprintln!("\nWorker {} failed: {}", address, err);
+                }
+            });
+        }
+        //The results end when every worker has stopped
+        drop(sender);
+        for (completed, (job, result)) in (1..).zip(receiver) {
+            rendering::copy_rgb_into_buffer(&mut p, job.render_box, &result.rgb);
+            rendering::print_progress_with_eta(completed, job_count, "jobs", time.elapsed());
+        }
+        println!();
+    });
+    let remaining = queue.remaining.load(Ordering::SeqCst);
+    if remaining > 0 {
+        return Err(format!("Every worker failed, {} of {} jobs were not rendered", remaining, job_count));
+    }
+    p.update_pixels();
+    p.render_time = Some(time.elapsed());
+    Ok(p)
+}
+
+///Renders the `Job`s sent by a coordinator over `stream` until it closes the connection
+fn serve_coordinator(stream: TcpStream) -> Result<(), String> {
+    let peer = stream.peer_addr().map_err(|err| err.to_string())?;
+    println!("Coordinator {} connected", peer);
+    let mut reader = BufReader::new(stream.try_clone().map_err(|err| err.to_string())?);
+    let mut writer = BufWriter::new(stream);
+    while let Some(job) = Job::read_from(&mut reader)? {
+        println!("Rendering job {} of {}: {:?}", job.id, peer, job.render_box);
+        job.render().write_to(&mut writer)?;
+    }
+    println!("Coordinator {} disconnected", peer);
+    Ok(())
+}
+
+///Works as a worker of distributed renders at `address`, e.g. 127.0.0.1:9000: renders the `Job`s sent by coordinators over TCP, every coordinator on its own thread, until the process is stopped
+/// # Errors
+/// Returns an Error if the worker cannot listen at `address`
+pub fn work(address: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(address).map_err(|err| format!("{}: {}", err, address))?;
+    println!("Worker listening at {}", listener.local_addr().map_err(|err| err.to_string())?);
+    serve_coordinators(&listener);
+    Ok(())
+}
+
+///Renders the `Job`s sent by the coordinators that connect to `listener`, every coordinator on its own thread
+pub fn serve_coordinators(listener: &TcpListener) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                thread::spawn(move || {
+                    if let Err(err) = serve_coordinator(stream) {
+                        eprintln!("Error: could not render the jobs of the coordinator: {}", err);
+                    }
+                });
+            }
+            Err(err) => eprintln!("Error: could not accept the connection: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufRead, net::SocketAddr};
+
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::{ComplexPlane, View}, mandelbrot_set::MandelbrotSet};
+
+    use super::*;
+
+    static WIDTH: usize = 400;
+    static HEIGHT: usize = 300;
+
+    fn metadata() -> Metadata {
+        Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(6, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::BGR, 0, 1)
+    }
+
+    ///Starts a worker on an ephemeral port of 127.0.0.1, returns its address
+    fn start_worker() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || serve_coordinators(&listener));
+        address.to_string()
+    }
+
+    ///Returns the fraction of the pixels of `p` that differ from a local render of the same image.
+    ///Every pixel is a random sample inside the pixel, so pixels at the edges of the bands of equal iterations can differ
+    fn difference_with_local_render(p: &PixelBuffer) -> f64 {
+        let metadata = metadata();
+        let mut local = PixelBuffer::new(PixelPlane::new(WIDTH, HEIGHT));
+        local.color_channel_mapping = metadata.color_channel_mapping;
+        let mut c = ComplexPlane::new(WIDTH, HEIGHT);
+        c.set_view(&metadata.view);
+        rendering::render_complex_plane_into_buffer(&mut local, &c, &metadata.mandelbrot_set, metadata.supersampling_amount, metadata.coloring_algorithm.coloring_function()).unwrap();
+        let different = p.pixels.iter().zip(&local.pixels).filter(|(pixel, local_pixel)| pixel != local_pixel).count();
+        f64::from(different as u32) / f64::from((WIDTH * HEIGHT) as u32)
+    }
+
+    #[test]
+    fn assembles_the_jobs_rendered_by_the_workers() {
+        let workers = [start_worker(), start_worker()];
+        let p = render_distributed(WIDTH, HEIGHT, &metadata(), &workers).unwrap();
+        assert_eq!(p.pixels.len(), WIDTH * HEIGHT);
+        let difference = difference_with_local_render(&p);
+        assert!(difference < 0.05, "{} of the pixels differ", difference);
+    }
+
+    #[test]
+    fn requeues_the_job_of_a_killed_worker() {
+        //A worker that is killed after receiving its first job
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let killed_worker: SocketAddr = listener.local_addr().unwrap();
+        let (received, received_job) = mpsc::channel();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            received.send(line).unwrap();
+        });
+
+        let workers = [killed_worker.to_string(), start_worker(), start_worker()];
+        let p = render_distributed(WIDTH, HEIGHT, &metadata(), &workers).unwrap();
+        let job = received_job.recv_timeout(Duration::from_secs(1)).expect("The killed worker should have received a job");
+        assert!(job.contains("\"render_box\""));
+        let difference = difference_with_local_render(&p);
+        assert!(difference < 0.05, "{} of the pixels differ", difference);
+    }
+
+    #[test]
+    fn fails_if_every_worker_fails() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let err = render_distributed(WIDTH, HEIGHT, &metadata(), &[address]).err().unwrap();
+        assert_eq!(err, "Every worker failed, 12 of 12 jobs were not rendered");
+    }
+}
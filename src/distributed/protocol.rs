@@ -0,0 +1,149 @@
+use std::io::{BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coloring::ColorChannelMapping, complex_plane::ComplexPlane, metadata::Metadata, rendering::{self, RenderBox}};
+
+///A part of a render sent from the coordinator to a worker: the pixels inside `render_box` of an image of `width` x `height` pixels, rendered from `metadata`.
+///
+///Sent as a single line of JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: usize,
+    pub width: usize,
+    pub height: usize,
+    pub render_box: RenderBox,
+    ///The view, Mandelbrot set parameters, coloring settings and SSAA multiplier to render
+    pub metadata: Metadata,
+}
+
+///The header of a `JobResult`, sent as a single line of JSON followed by `length` bytes
+#[derive(Serialize, Deserialize)]
+struct JobResultHeader {
+    id: usize,
+    length: usize,
+}
+
+///The rendered pixels of the `Job` with `id`, sent from a worker to the coordinator as RGB bytes, see `rendering::render_box_into_rgb`
+pub struct JobResult {
+    pub id: usize,
+    pub rgb: Vec<u8>,
+}
+
+///Reads a line of JSON from `r` and deserializes it, returns None if the connection was closed
+fn read_line<T: for<'a> Deserialize<'a>>(r: &mut impl BufRead) -> Result<Option<T>, String> {
+    let mut line = String::new();
+    if r.read_line(&mut line).map_err(|err| err.to_string())? == 0 {
+        return Ok(None);
+    }
+    serde_json::from_str(&line).map(Some).map_err(|err| format!("Invalid message: {}", err))
+}
+
+///Serializes `message` as a line of JSON and writes it to `w`
+fn write_line<T: Serialize>(w: &mut impl Write, message: &T) -> Result<(), String> {
+    let json = serde_json::to_string(message).map_err(|err| err.to_string())?;
+    writeln!(w, "{}", json).map_err(|err| err.to_string())
+}
+
+impl Job {
+    ///Writes the `Job` to `w`
+    /// # Errors
+    /// Returns an Error if the `Job` cannot be written
+    pub fn write_to(&self, w: &mut impl Write) -> Result<(), String> {
+        write_line(w, self)?;
+        w.flush().map_err(|err| err.to_string())
+    }
+
+    ///Reads a `Job` from `r`, returns None if the connection was closed
+    /// # Errors
+    /// Returns an Error if the `Job` cannot be read, or its `render_box` is not inside the image
+    pub fn read_from(r: &mut impl BufRead) -> Result<Option<Job>, String> {
+        let Some(job) = read_line::<Job>(r)? else {
+            return Ok(None);
+        };
+        let RenderBox { min_x, max_x, min_y, max_y } = job.render_box;
+        if min_x > max_x || min_y > max_y || max_x > job.width || max_y > job.height {
+            return Err(format!("The render box of job {} is not inside the {}x{} image: {:?}", job.id, job.width, job.height, job.render_box));
+        }
+        Ok(Some(job))
+    }
+
+    ///Renders the pixels inside the `render_box` using `ColorChannelMapping::RGB`, see `rendering::render_box_into_rgb`
+    pub fn render(&self) -> JobResult {
+        let mut c = ComplexPlane::new(self.width, self.height);
+        c.set_view(&self.metadata.view);
+        let mut rgb = vec![0; 3 * self.render_box.compute_pixel_count()];
+        let m = &self.metadata;
+        rendering::render_box_into_rgb(&mut rgb, &c, &m.mandelbrot_set, self.render_box, m.supersampling_amount, m.palette_offset, ColorChannelMapping::RGB, m.coloring_algorithm.coloring_function());
+        JobResult { id: self.id, rgb }
+    }
+}
+
+impl JobResult {
+    ///Writes the `JobResult` to `w`
+    /// # Errors
+    /// Returns an Error if the `JobResult` cannot be written
+    pub fn write_to(&self, w: &mut impl Write) -> Result<(), String> {
+        write_line(w, &JobResultHeader { id: self.id, length: self.rgb.len() })?;
+        w.write_all(&self.rgb).and_then(|()| w.flush()).map_err(|err| err.to_string())
+    }
+
+    ///Reads the `JobResult` of `job` from `r`
+    /// # Errors
+    /// Returns an Error if the connection was closed, or the `JobResult` cannot be read or does not belong to `job`
+    pub fn read_from(r: &mut impl BufRead, job: &Job) -> Result<JobResult, String> {
+        let header = read_line::<JobResultHeader>(r)?.ok_or_else(|| String::from("The connection was closed"))?;
+        if header.id != job.id || header.length != 3 * job.render_box.compute_pixel_count() {
+            return Err(format!("Unexpected result of job {} with {} bytes, expected job {}", header.id, header.length, job.id));
+        }
+        let mut rgb = vec![0; header.length];
+        r.read_exact(&mut rgb).map_err(|err| err.to_string())?;
+        Ok(JobResult { id: header.id, rgb })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{coloring::ColoringAlgorithm, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    use super::*;
+
+    fn job(render_box: RenderBox) -> Job {
+        let metadata = Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(20, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1);
+        Job { id: 3, width: 40, height: 30, render_box, metadata }
+    }
+
+    #[test]
+    fn jobs_and_results_are_read_back_as_they_were_written() {
+        let job = job(RenderBox::new(10, 40, 0, 8));
+        let result = job.render();
+        let mut message = Vec::new();
+        job.write_to(&mut message).unwrap();
+        result.write_to(&mut message).unwrap();
+
+        let mut r = Cursor::new(message);
+        let read_job = Job::read_from(&mut r).unwrap().unwrap();
+        assert_eq!((read_job.id, read_job.render_box), (job.id, job.render_box));
+        let read_result = JobResult::read_from(&mut r, &read_job).unwrap();
+        assert_eq!(read_result.rgb, result.rgb);
+        assert_eq!(read_result.rgb.len(), 3 * 30 * 8);
+        //The connection was closed
+        assert!(Job::read_from(&mut r).unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_jobs_outside_of_the_image_and_unexpected_results() {
+        let mut message = Vec::new();
+        job(RenderBox::new(0, 41, 0, 8)).write_to(&mut message).unwrap();
+        assert!(Job::read_from(&mut Cursor::new(message)).err().unwrap().starts_with("The render box of job 3 is not inside the 40x30 image"));
+        assert!(Job::read_from(&mut Cursor::new(b"not json\n")).err().unwrap().starts_with("Invalid message"));
+
+        let (small, large) = (job(RenderBox::new(0, 1, 0, 1)), job(RenderBox::new(0, 2, 0, 1)));
+        let mut message = Vec::new();
+        small.render().write_to(&mut message).unwrap();
+        assert_eq!(JobResult::read_from(&mut Cursor::new(message), &large).err().unwrap(), "Unexpected result of job 3 with 3 bytes, expected job 3");
+        assert_eq!(JobResult::read_from(&mut Cursor::new(Vec::new()), &large).err().unwrap(), "The connection was closed");
+    }
+}
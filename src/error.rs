@@ -0,0 +1,59 @@
+use std::{fmt, io, path::{Path, PathBuf}};
+
+///The errors of saving, loading the configuration and rendering, which are shown to the user instead of crashing the application
+#[derive(Debug)]
+pub enum Error {
+    ///A file or directory at `path` could not be created, read or written, e.g. because the disk is full or the directory is read-only
+    Io { path: PathBuf, source: io::Error },
+    ///An image could not be encoded or written, e.g. because its size is not supported by its format
+    Image(String),
+    ///The command line arguments or a configuration file are not valid
+    Config(String),
+    ///The view could not be rendered or shown, e.g. because a rendering thread panicked
+    Render(String),
+    ///The `Command` `command` failed because of the Error `source`
+    Command { command: String, source: Box<Error> },
+    ///Any other error, e.g. of a `Command` that is not valid
+    Other(String),
+}
+
+impl Error {
+    ///Returns an `Error::Io` of the file or directory at `path`
+    pub fn io(path: &Path, source: io::Error) -> Error {
+        Error::Io { path: path.to_path_buf(), source }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", source, path.display()),
+            Error::Command { command, source } => write!(f, "{} failed: {}", command, source),
+            Error::Image(message) | Error::Config(message) | Error::Render(message) | Error::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            Error::Command { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+///Errors of modules that describe their errors as `String`s become `Error::Other`
+impl From<String> for Error {
+    fn from(message: String) -> Error {
+        Error::Other(message)
+    }
+}
+
+///An `Error` can be returned by modules that describe their errors as `String`s
+impl From<Error> for String {
+    fn from(err: Error) -> String {
+        err.to_string()
+    }
+}
@@ -0,0 +1,293 @@
+use std::{fs::{self, File}, io::{BufWriter, Write}, path::Path, str::FromStr, time::Instant};
+
+use serde::Serialize;
+
+use crate::{complex::Complex, complex_plane::ComplexPlane, error::Error, mandelbrot_set::Escape, metadata::Metadata, poster::{MAX_BAND_HEIGHT, MAX_POSTER_SIZE}, rendering::{self, RenderBox}};
+
+///The magic number at the start of an `EscapeDataFormat::Binary` file
+pub static BINARY_MAGIC_NUMBER: &[u8; 8] = b"MBESCAPE";
+///The version of the `EscapeDataFormat::Binary` format
+pub static BINARY_VERSION: u32 = 1;
+///The names of the fields of the `Escape` data of every pixel, in the order they are stored
+pub static FIELDS: [&str; 4] = ["iterations", "smooth_iterations", "final_abs", "distance_estimate"];
+
+///The file formats the `EscapeData` of a view can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeDataFormat {
+    ///A `NumPy` `.npy` file of a structured array of `height` x `width` records of the `FIELDS`, with a sidecar JSON description
+    Npy,
+    ///A `.bin` file: the magic number `MBESCAPE`, the version, width, height and length of the JSON description as little-endian u32s, the JSON description,
+    ///and a packed little-endian record of every pixel in row-major order: the iterations as u32, followed by the other `FIELDS` as f64s
+    Binary,
+    ///A `.csv` file with a row of the pixel, its complex number and its `FIELDS` for every pixel, with a sidecar JSON description. Only suitable for small grids
+    Csv,
+}
+
+impl EscapeDataFormat {
+    ///Returns the file extension of this `EscapeDataFormat`
+    pub fn extension(self) -> &'static str {
+        match self {
+            EscapeDataFormat::Npy => "npy",
+            EscapeDataFormat::Binary => "bin",
+            EscapeDataFormat::Csv => "csv",
+        }
+    }
+
+    ///Returns whether the JSON description is saved in a sidecar file, because the format has no header for it
+    pub fn has_sidecar(self) -> bool {
+        self != EscapeDataFormat::Binary
+    }
+}
+
+impl FromStr for EscapeDataFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<EscapeDataFormat, String> {
+        match s.to_lowercase().as_str() {
+            "npy" => Ok(EscapeDataFormat::Npy),
+            "bin" | "binary" => Ok(EscapeDataFormat::Binary),
+            "csv" => Ok(EscapeDataFormat::Csv),
+            _ => Err(format!("Unknown escape data format: {}, should be npy, bin or csv", s)),
+        }
+    }
+}
+
+///The JSON description of exported `EscapeData`, stored in the header of a binary file or in a sidecar file
+#[derive(Serialize)]
+struct EscapeDataDescription<'a> {
+    width: usize,
+    height: usize,
+    fields: [&'static str; 4],
+    ///Where in a pixel its point is sampled
+    sample: &'static str,
+    ///The view and `MandelbrotSet` parameters the data was computed from
+    metadata: &'a Metadata,
+}
+
+///The maximum amount of `Escape`s held in memory while computing `EscapeData`, 32 bytes each
+pub static BAND_ESCAPES: usize = 1 << 20;
+
+///The raw `Escape` data of every pixel of a view of `width` x `height` pixels, sampled at the center of the pixel without SSAA.
+///
+///Computed in bands of rows, which are written one at a time like a `Poster`, so at most `BAND_ESCAPES` `Escape`s are held in memory
+pub struct EscapeData {
+    pub width: usize,
+    pub height: usize,
+    ///The view and `MandelbrotSet` parameters to compute the data of
+    pub metadata: Metadata,
+}
+
+impl EscapeData {
+    pub fn new(width: usize, height: usize, metadata: Metadata) -> EscapeData {
+        EscapeData { width, height, metadata }
+    }
+
+    ///Returns the height in rows of the bands the `EscapeData` is computed in
+    pub fn band_height(&self) -> usize {
+        (BAND_ESCAPES / self.width.max(1)).clamp(1, MAX_BAND_HEIGHT)
+    }
+
+    ///Returns an Error if the size of the `EscapeData` is not a valid image size
+    fn check_size(&self) -> Result<(), String> {
+        if !(1..=MAX_POSTER_SIZE).contains(&self.width) || !(1..=MAX_POSTER_SIZE).contains(&self.height) {
+            return Err(format!("The escape data size should be between 1x1 and {}x{} pixels: {}x{}", MAX_POSTER_SIZE, MAX_POSTER_SIZE, self.width, self.height));
+        }
+        Ok(())
+    }
+
+    ///Computes the `EscapeData` band by band, passing the first row of every band and its `Escape`s in row-major order to `write_band`,
+    ///printing the progress and the estimated remaining time
+    fn compute_bands(&self, mut write_band: impl FnMut(usize, &[Escape]) -> Result<(), String>) -> Result<(), String> {
+        self.check_size()?;
+        let mut c = ComplexPlane::new(self.width, self.height);
+        c.set_view(&self.metadata.view);
+        let band_height = self.band_height();
+        let mut band = vec![Escape::default(); band_height * self.width];
+        let time = Instant::now();
+        for min_y in (0..self.height).step_by(band_height) {
+            let max_y = (min_y + band_height).min(self.height);
+            let band = &mut band[..(max_y - min_y) * self.width];
+            rendering::render_box_into_samples(band, RenderBox::new(0, self.width, min_y, max_y), |point| [self.metadata.mandelbrot_set.escape(&EscapeData::complex(&c, point))]);
+            write_band(min_y, band)?;
+            rendering::print_progress_with_eta(max_y, self.height, "rows", time.elapsed());
+        }
+        println!();
+        Ok(())
+    }
+
+    ///Returns the complex number at the center of the pixel at `point`
+    fn complex(c: &ComplexPlane, point: (usize, usize)) -> Complex {
+        c.complex_from_pixel_plane(f64::from(point.0 as u32) + 0.5, f64::from(point.1 as u32) + 0.5)
+    }
+
+    ///Returns the JSON description of the `EscapeData`
+    fn description(&self) -> String {
+        let description = EscapeDataDescription { width: self.width, height: self.height, fields: FIELDS, sample: "center", metadata: &self.metadata };
+        serde_json::to_string_pretty(&description).unwrap_or_default()
+    }
+
+    ///Computes the `EscapeData` and saves it as `format` to `path`, and the JSON description to the `sidecar` path if the format has no header for it
+    /// # Errors
+    /// Returns an Error if the size is not a valid image size, or a file cannot be created or written
+    pub fn save_at(&self, format: EscapeDataFormat, path: &Path, sidecar: &Path) -> Result<(), Error> {
+        self.check_size().map_err(Error::Image)?;
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        println!("Computing {}x{} escape data in bands of {} rows into {}", self.width, self.height, self.band_height(), path.display());
+        let mut w = BufWriter::new(file);
+        match format {
+            EscapeDataFormat::Npy => self.write_npy(&mut w),
+            EscapeDataFormat::Binary => self.write_binary(&mut w),
+            EscapeDataFormat::Csv => self.write_csv(&mut w),
+        }.and_then(|()| w.flush().map_err(|err| err.to_string())).map_err(|err| Error::Image(format!("{}: {}", err, path.display())))?;
+        println!("Saved the escape data: {}", path.display());
+        if format.has_sidecar() {
+            fs::write(sidecar, self.description()).map_err(|err| Error::io(sidecar, err))?;
+            println!("Saved the escape data description: {}", sidecar.display());
+        }
+        Ok(())
+    }
+
+    ///Appends the packed little-endian record of `escape` to `bytes`
+    fn push_record(bytes: &mut Vec<u8>, escape: &Escape) {
+        bytes.extend_from_slice(&escape.iterations.to_le_bytes());
+        for value in [escape.smooth_iterations, escape.final_abs, escape.distance_estimate] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    ///Computes the packed records of all pixels and writes them to `w`, a band at a time
+    fn write_records<W: Write>(&self, w: &mut W) -> Result<(), String> {
+        let mut bytes = Vec::new();
+        self.compute_bands(|_, band| {
+            bytes.clear();
+            for escape in band {
+                EscapeData::push_record(&mut bytes, escape);
+            }
+            w.write_all(&bytes).map_err(|err| err.to_string())
+        })
+    }
+
+    ///Encodes the `EscapeData` as a version 1.0 `.npy` file of a structured array into `w`, which can be read using `numpy.load`
+    /// # Errors
+    /// Returns an Error if the size is not a valid image size, or the file cannot be written
+    pub fn write_npy<W: Write>(&self, w: &mut W) -> Result<(), String> {
+        let descr = format!("[('{}', '<u4'), ('{}', '<f8'), ('{}', '<f8'), ('{}', '<f8')]", FIELDS[0], FIELDS[1], FIELDS[2], FIELDS[3]);
+        let mut header = format!("{{'descr': {}, 'fortran_order': False, 'shape': ({}, {}), }}", descr, self.height, self.width);
+        //The magic string, version, header length and header are padded with spaces and a newline to a multiple of 64 bytes
+        let padded_length = (10 + header.len() + 1).next_multiple_of(64) - 10;
+        header.push_str(&" ".repeat(padded_length - header.len() - 1));
+        header.push('\n');
+        let header_length = u16::try_from(header.len()).map_err(|err| err.to_string())?;
+        w.write_all(b"\x93NUMPY\x01\x00").and_then(|()| w.write_all(&header_length.to_le_bytes())).and_then(|()| w.write_all(header.as_bytes())).map_err(|err| err.to_string())?;
+        self.write_records(w)
+    }
+
+    ///Encodes the `EscapeData` in the `EscapeDataFormat::Binary` format into `w`
+    /// # Errors
+    /// Returns an Error if the size is not a valid image size or does not fit in the header, or the file cannot be written
+    pub fn write_binary<W: Write>(&self, w: &mut W) -> Result<(), String> {
+        let description = self.description();
+        let mut header = BINARY_MAGIC_NUMBER.to_vec();
+        for value in [BINARY_VERSION as usize, self.width, self.height, description.len()] {
+            header.extend_from_slice(&u32::try_from(value).map_err(|err| err.to_string())?.to_le_bytes());
+        }
+        header.extend_from_slice(description.as_bytes());
+        w.write_all(&header).map_err(|err| err.to_string())?;
+        self.write_records(w)
+    }
+
+    ///Encodes the `EscapeData` as CSV into `w`: a header row, followed by the pixel x and y, the real and imaginary part of its complex number and its `FIELDS` for every pixel
+    /// # Errors
+    /// Returns an Error if the size is not a valid image size, or the file cannot be written
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> Result<(), String> {
+        let mut c = ComplexPlane::new(self.width, self.height);
+        c.set_view(&self.metadata.view);
+        writeln!(w, "x,y,re,im,{}", FIELDS.join(",")).map_err(|err| err.to_string())?;
+        self.compute_bands(|min_y, band| {
+            for (i, escape) in band.iter().enumerate() {
+                let point = (i % self.width, min_y + i / self.width);
+                let complex = EscapeData::complex(&c, point);
+                writeln!(w, "{},{},{},{},{},{},{},{}", point.0, point.1, complex.x, complex.y, escape.iterations, escape.smooth_iterations, escape.final_abs, escape.distance_estimate)
+                    .map_err(|err| err.to_string())?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    fn escape_data(width: usize, height: usize) -> EscapeData {
+        let metadata = Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1);
+        EscapeData::new(width, height, metadata)
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn writes_npy_files() {
+        let mut npy = Vec::new();
+        escape_data(5, 3).write_npy(&mut npy).unwrap();
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        let header_length = usize::from(u16::from_le_bytes([npy[8], npy[9]]));
+        //The header is padded to a multiple of 64 bytes and ends with a newline
+        assert_eq!((10 + header_length) % 64, 0);
+        assert_eq!(npy[10 + header_length - 1], b'\n');
+        let header = std::str::from_utf8(&npy[10..10 + header_length]).unwrap();
+        assert!(header.starts_with("{'descr': [('iterations', '<u4'), ('smooth_iterations', '<f8'), ('final_abs', '<f8'), ('distance_estimate', '<f8')], 'fortran_order': False, 'shape': (3, 5), }"));
+        //Every record is a u32 and three f64s
+        assert_eq!(npy.len() - 10 - header_length, 5 * 3 * 28);
+    }
+
+    #[test]
+    fn writes_binary_files() {
+        let data = escape_data(4, 2);
+        let mut binary = Vec::new();
+        data.write_binary(&mut binary).unwrap();
+        assert_eq!(&binary[..8], BINARY_MAGIC_NUMBER);
+        assert_eq!([u32_at(&binary, 8), u32_at(&binary, 12), u32_at(&binary, 16)], [BINARY_VERSION, 4, 2]);
+        let description_length = u32_at(&binary, 20) as usize;
+        assert_eq!(std::str::from_utf8(&binary[24..24 + description_length]).unwrap(), data.description());
+        let records = &binary[24 + description_length..];
+        assert_eq!(records.len(), 4 * 2 * 28);
+        //The records are stored in row-major order
+        let mut c = ComplexPlane::new(4, 2);
+        c.set_view(&data.metadata.view);
+        let escape = data.metadata.mandelbrot_set.escape(&EscapeData::complex(&c, (1, 1)));
+        let mut record = Vec::new();
+        EscapeData::push_record(&mut record, &escape);
+        assert_eq!(&records[5 * 28..6 * 28], record.as_slice());
+    }
+
+    #[test]
+    fn writes_csv_files() {
+        let mut csv = Vec::new();
+        escape_data(3, 2).write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "x,y,re,im,iterations,smooth_iterations,final_abs,distance_estimate");
+        assert_eq!(lines.len(), 1 + 3 * 2);
+        assert!(lines[1].starts_with("0,0,"));
+        assert!(lines[6].starts_with("2,1,"));
+        assert!(lines[1..].iter().all(|line| line.split(',').count() == 8));
+    }
+
+    #[test]
+    fn computes_escape_data_in_bands() {
+        let data = escape_data(BAND_ESCAPES / 3, 200);
+        assert_eq!(data.band_height(), 3);
+        assert_eq!(escape_data(10, 10).band_height(), MAX_BAND_HEIGHT);
+        let mut rows = Vec::new();
+        escape_data(20, MAX_BAND_HEIGHT * 2 + 1).compute_bands(|min_y, band| {
+            rows.push((min_y, band.len()));
+            Ok(())
+        }).unwrap();
+        assert_eq!(rows, [(0, 20 * MAX_BAND_HEIGHT), (MAX_BAND_HEIGHT, 20 * MAX_BAND_HEIGHT), (MAX_BAND_HEIGHT * 2, 20)]);
+        assert!(escape_data(0, 10).write_npy(&mut Vec::new()).is_err());
+    }
+}
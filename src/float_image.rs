@@ -0,0 +1,147 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path, str::FromStr, time::Instant};
+
+use crate::{complex_plane::ComplexPlane, error::Error, image_format::tiff, metadata::Metadata, pixel_buffer::PixelBuffer, poster::{MAX_BAND_HEIGHT, MAX_POSTER_SIZE}, rendering::{self, RenderBox}};
+
+pub mod exr;
+
+///The high precision file formats a `FloatImage` can be saved as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatImageFormat {
+    ///An RGB png image with 16 bits per channel, in the same color space as the 8-bit png images
+    Png16,
+    ///An uncompressed scanline `OpenEXR` image with 32-bit float channels in linear light, for compositing
+    Exr,
+    ///An uncompressed RGB TIFF image with 32-bit float samples in linear light, for compositing
+    Tiff,
+}
+
+impl FloatImageFormat {
+    ///Returns the file extension of this `FloatImageFormat`
+    pub fn extension(self) -> &'static str {
+        match self {
+            FloatImageFormat::Png16 => "png",
+            FloatImageFormat::Exr => "exr",
+            FloatImageFormat::Tiff => "tif",
+        }
+    }
+}
+
+impl FromStr for FloatImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<FloatImageFormat, String> {
+        match s.to_lowercase().as_str() {
+            "png16" => Ok(FloatImageFormat::Png16),
+            "exr" => Ok(FloatImageFormat::Exr),
+            "tiff" | "tif" => Ok(FloatImageFormat::Tiff),
+            _ => Err(format!("Unknown high precision format: {}, should be png16, exr or tiff", s)),
+        }
+    }
+}
+
+///Converts a channel ∈ [0.0, 1.0] from the sRGB transfer function, which the coloring functions are designed in, to linear light
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+///An image rendered at full precision: the SSAA samples of every pixel are averaged as `FloatColor`s instead of being rounded to 8 bits,
+///so it can be exported without banding, see `FloatImageFormat`
+pub struct FloatImage {
+    pub width: usize,
+    pub height: usize,
+    ///RGB floats ∈ [0.0, 1.0] in the sRGB color space, ordered by the `ColorChannelMapping` of the `metadata`
+    pub rgb: Vec<f32>,
+    ///The view, Mandelbrot set parameters and coloring settings the image was rendered from, stored in the metadata of the saved image
+    pub metadata: Metadata,
+}
+
+impl FloatImage {
+    ///Renders the view of `metadata` as a `FloatImage` of `width` x `height` pixels, printing the progress and the estimated remaining time
+    /// # Errors
+    /// Returns an Error if the size is not a valid image size
+    pub fn render(width: usize, height: usize, metadata: Metadata) -> Result<FloatImage, String> {
+        if !(1..=MAX_POSTER_SIZE).contains(&width) || !(1..=MAX_POSTER_SIZE).contains(&height) {
+            return Err(format!("The image size should be between 1x1 and {}x{} pixels: {}x{}", MAX_POSTER_SIZE, MAX_POSTER_SIZE, width, height));
+        }
+        let mut c = ComplexPlane::new(width, height);
+        c.set_view(&metadata.view);
+        let float_coloring_function = metadata.coloring_algorithm.float_coloring_function();
+        let mut rgb = vec![0.0; width * height * 3];
+        let time = Instant::now();
+        for (band, min_y) in rgb.chunks_mut(MAX_BAND_HEIGHT * width * 3).zip((0..height).step_by(MAX_BAND_HEIGHT)) {
+            let max_y = (min_y + MAX_BAND_HEIGHT).min(height);
+            rendering::render_box_into_float_rgb(band, &c, &metadata.mandelbrot_set, RenderBox::new(0, width, min_y, max_y), metadata.supersampling_amount, metadata.palette_offset, metadata.color_channel_mapping, float_coloring_function);
+            rendering::print_progress_with_eta(max_y, height, "rows", time.elapsed());
+        }
+        println!();
+        Ok(FloatImage { width, height, rgb, metadata })
+    }
+
+    ///Returns the RGB floats of the image converted to linear light, as expected by `OpenEXR` and float TIFF readers
+    pub fn linear_rgb(&self) -> Vec<f32> {
+        self.rgb.iter().map(|value| srgb_to_linear(*value)).collect()
+    }
+
+    ///Saves the `FloatImage` as `format` to `path`
+    /// # Errors
+    /// Returns an Error if the file at `path` cannot be created, or the image cannot be encoded or written
+    pub fn save_at(&self, format: FloatImageFormat, path: &Path) -> Result<(), Error> {
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        let mut w = BufWriter::new(file);
+        match format {
+            FloatImageFormat::Png16 => self.write_png16(&mut w),
+            FloatImageFormat::Exr => exr::write_exr(&mut w, self.width, self.height, &self.linear_rgb(), &self.metadata),
+            FloatImageFormat::Tiff => tiff::write_tiff(&mut w, self.width, self.height, &self.linear_rgb(), &self.metadata),
+        }.and_then(|()| w.flush().map_err(|err| err.to_string())).map_err(|err| Error::Image(format!("{}: {}", err, path.display())))?;
+        println!("Saved the {:?} image: {}", format, path.display());
+        Ok(())
+    }
+
+    ///Encodes the `FloatImage` as an RGB png image with 16 bits per channel into `w`, with the same metadata as `PixelBuffer::write_png`
+    /// # Errors
+    /// Returns an Error if the png image cannot be encoded or written
+    pub fn write_png16<W: Write>(&self, w: W) -> Result<(), String> {
+        let encoder = PixelBuffer::png_encoder(w, self.width, self.height, png::BitDepth::Sixteen, &self.metadata)?;
+        //16-bit png samples are big-endian
+        let data: Vec<u8> = self.rgb.iter().flat_map(|value| ((value.clamp(0.0, 1.0) * f32::from(u16::MAX)).round() as u16).to_be_bytes()).collect();
+        let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+        writer.write_image_data(&data).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn converts_srgb_to_linear_light() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.040_45) - 0.040_45 / 12.92).abs() < 1e-9);
+        //The linear segment and the power curve meet at the threshold
+        assert!((srgb_to_linear(0.040_46) - srgb_to_linear(0.040_45)).abs() < 1e-5);
+        assert!(srgb_to_linear(0.5) < 0.5);
+    }
+
+    #[test]
+    fn writes_16_bit_png_images() {
+        let metadata = Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1);
+        let image = FloatImage { width: 2, height: 1, rgb: vec![1.0, 0.5, 0.0, 2.0, -1.0, 0.25], metadata };
+        let mut png = Vec::new();
+        image.write_png16(&mut png).unwrap();
+        let mut reader = png::Decoder::new(Cursor::new(png)).read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        assert_eq!((info.width, info.height, info.bit_depth, info.color_type), (2, 1, png::BitDepth::Sixteen, png::ColorType::Rgb));
+        let samples: Vec<u16> = data[..info.buffer_size()].chunks_exact(2).map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]])).collect();
+        assert_eq!(samples, [65535, 32768, 0, 65535, 0, 16384]);
+    }
+}
@@ -0,0 +1,135 @@
+use std::io::Write;
+
+use crate::metadata::{Metadata, METADATA_KEYWORD};
+
+///The magic number at the start of every `OpenEXR` file
+static MAGIC_NUMBER: [u8; 4] = [0x76, 0x2f, 0x31, 0x01];
+///Version 2 of the file format, single-part scanline image
+static VERSION: [u8; 4] = [2, 0, 0, 0];
+///The `OpenEXR` pixel type of 32-bit float channels
+static PIXEL_TYPE_FLOAT: i32 = 2;
+
+///Appends an `OpenEXR` header attribute: its `name`, `kind` and the size of its `value`, followed by the `value`
+fn push_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for string in [name, kind] {
+        header.extend_from_slice(string.as_bytes());
+        header.push(0);
+    }
+    header.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+///Returns the header of an uncompressed `OpenEXR` image of `width` x `height` pixels with 32-bit float B, G and R channels, storing `metadata` as a string attribute
+fn header(width: i32, height: i32, metadata: &Metadata) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&MAGIC_NUMBER);
+    header.extend_from_slice(&VERSION);
+    //The channels are stored in alphabetical order
+    let mut channels = Vec::new();
+    for channel in ["B", "G", "R"] {
+        channels.extend_from_slice(channel.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]); //pLinear and reserved
+        channels.extend_from_slice(&1_i32.to_le_bytes()); //x sampling
+        channels.extend_from_slice(&1_i32.to_le_bytes()); //y sampling
+    }
+    channels.push(0);
+    push_attribute(&mut header, "channels", "chlist", &channels);
+    push_attribute(&mut header, "compression", "compression", &[0]); //NO_COMPRESSION
+    let window: Vec<u8> = [0, 0, width - 1, height - 1].iter().flat_map(|value: &i32| value.to_le_bytes()).collect();
+    push_attribute(&mut header, "dataWindow", "box2i", &window);
+    push_attribute(&mut header, "displayWindow", "box2i", &window);
+    push_attribute(&mut header, "lineOrder", "lineOrder", &[0]); //INCREASING_Y
+    push_attribute(&mut header, "pixelAspectRatio", "float", &1.0_f32.to_le_bytes());
+    push_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    push_attribute(&mut header, "screenWindowWidth", "float", &1.0_f32.to_le_bytes());
+    push_attribute(&mut header, METADATA_KEYWORD, "string", metadata.to_json().as_bytes());
+    header.push(0);
+    header
+}
+
+///Encodes `rgb`, the RGB floats of an image of `width` x `height` pixels, as an uncompressed scanline `OpenEXR` image into `w`, storing `metadata` in the `METADATA_KEYWORD` attribute
+/// # Errors
+/// Returns an Error if the size is too large for an `OpenEXR` image, or the image cannot be written
+pub fn write_exr<W: Write>(w: &mut W, width: usize, height: usize, rgb: &[f32], metadata: &Metadata) -> Result<(), String> {
+    //Every scanline is a chunk of its y coordinate, the size of its pixel data and the pixel data
+    let pixel_data_size = width * 3 * 4;
+    let (Ok(exr_width), Ok(exr_height), Ok(exr_pixel_data_size)) = (i32::try_from(width), i32::try_from(height), i32::try_from(pixel_data_size)) else {
+        return Err(format!("The image is too large for an OpenEXR image: {}x{}", width, height));
+    };
+    let header = header(exr_width, exr_height, metadata);
+    let chunk_size = 8 + pixel_data_size;
+    let first_chunk = header.len() + 8 * height;
+    let offsets: Vec<u8> = (0..height).flat_map(|y| ((first_chunk + y * chunk_size) as u64).to_le_bytes()).collect();
+    w.write_all(&header).and_then(|()| w.write_all(&offsets)).map_err(|err| err.to_string())?;
+    let mut chunk = Vec::with_capacity(chunk_size);
+    for (y, row) in (0..exr_height).zip(rgb.chunks_exact(width * 3)) {
+        chunk.clear();
+        chunk.extend_from_slice(&y.to_le_bytes());
+        chunk.extend_from_slice(&exr_pixel_data_size.to_le_bytes());
+        //The samples of a scanline are grouped per channel, in the order of the channels in the header
+        for channel in [2, 1, 0] {
+            for pixel in row.chunks_exact(3) {
+                chunk.extend_from_slice(&pixel[channel].to_le_bytes());
+            }
+        }
+        w.write_all(&chunk).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    ///Returns the names and types of the attributes of the `OpenEXR` header, and the offset of the end of the header
+    fn attributes(exr: &[u8]) -> (Vec<(String, String)>, usize) {
+        let mut attributes = Vec::new();
+        let mut offset = 8;
+        let string = |offset: &mut usize| {
+            let end = *offset + exr[*offset..].iter().position(|byte| *byte == 0).unwrap();
+            let string = String::from_utf8(exr[*offset..end].to_vec()).unwrap();
+            *offset = end + 1;
+            string
+        };
+        loop {
+            let name = string(&mut offset);
+            if name.is_empty() {
+                return (attributes, offset);
+            }
+            let kind = string(&mut offset);
+            offset += 4 + u32_at(exr, offset) as usize;
+            attributes.push((name, kind));
+        }
+    }
+
+    #[test]
+    fn writes_exr_images() {
+        let metadata = Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1);
+        let rgb: Vec<f32> = (0..12_u8).map(|value| f32::from(value) / 16.0).collect();
+        let mut exr = Vec::new();
+        write_exr(&mut exr, 2, 2, &rgb, &metadata).unwrap();
+        assert_eq!(exr[..4], MAGIC_NUMBER);
+        assert_eq!(exr[4..8], VERSION);
+        let (attributes, header_length) = attributes(&exr);
+        for required in [("channels", "chlist"), ("compression", "compression"), ("dataWindow", "box2i"), ("displayWindow", "box2i"), ("lineOrder", "lineOrder"),
+            ("pixelAspectRatio", "float"), ("screenWindowCenter", "v2f"), ("screenWindowWidth", "float"), (METADATA_KEYWORD, "string")] {
+            assert!(attributes.iter().any(|(name, kind)| (name.as_str(), kind.as_str()) == required), "missing attribute {:?}", required);
+        }
+        //The line offset table points at the scanline chunks of a y coordinate, a size and 2 pixels of 3 floats
+        let offsets: Vec<usize> = (0..2).map(|y| u64::from_le_bytes(exr[header_length + 8 * y..header_length + 8 * y + 8].try_into().unwrap()) as usize).collect();
+        let chunk_size = 8 + 2 * 3 * 4;
+        assert_eq!(offsets, [header_length + 16, header_length + 16 + chunk_size]);
+        assert_eq!(exr.len(), offsets[1] + chunk_size);
+        assert_eq!([u32_at(&exr, offsets[1]), u32_at(&exr, offsets[1] + 4)], [1, 24]);
+        //The samples of the second scanline are grouped per channel in B, G, R order
+        let samples: Vec<f32> = exr[offsets[1] + 8..].chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect();
+        assert_eq!(samples, [rgb[8], rgb[11], rgb[7], rgb[10], rgb[6], rgb[9]]);
+    }
+}
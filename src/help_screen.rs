@@ -0,0 +1,107 @@
+use crate::{key_bindings::KeyBindings, pixel_buffer::overlay::{Overlay, font::{self, GLYPH_ADVANCE, LINE_HEIGHT}}};
+
+//Colors of the help screen text and category headers
+static HELP_COLOR: u32 = 0x00FF_FFFF;
+static CATEGORY_COLOR: u32 = 0x00FF_D700;
+//Distance of the help screen to the edges of the window in pixels
+static HELP_MARGIN: usize = 8;
+
+///Paged help screen drawn over the window, listing the key and description of every `KeyAction` in the `KeyBindings`, grouped by category
+#[derive(Default)]
+pub struct HelpScreen {
+    pub visible: bool,
+    page: usize,
+    page_count: usize,
+    overlay: Overlay,
+}
+
+impl HelpScreen {
+    pub fn new() -> HelpScreen {
+        HelpScreen::default()
+    }
+
+    ///Shows the `HelpScreen` at its first page if it is hidden, hides it if it is shown
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+        self.page = 0;
+    }
+
+    pub fn next_page(&mut self) {
+        if self.page + 1 < self.page_count {
+            self.page += 1;
+        }
+    }
+
+    pub fn previous_page(&mut self) {
+        self.page = self.page.saturating_sub(1);
+    }
+
+    ///Rebuilds the current page of the `HelpScreen` for a window of `width` x `height` pixels, does nothing if the `HelpScreen` is hidden
+    pub fn update(&mut self, k: &KeyBindings, width: usize, height: usize) {
+        self.overlay.clear();
+        if !self.visible {
+            return;
+        }
+        let scale = font::scale_for_width(width);
+        let line_height = LINE_HEIGHT * scale;
+        let max_characters = (width.saturating_sub(2 * HELP_MARGIN) / (GLYPH_ADVANCE * scale)).max(1);
+        //Leave room for the title and an empty line
+        let lines_per_page = (height.saturating_sub(2 * HELP_MARGIN) / line_height).saturating_sub(2).max(1);
+        let lines = HelpScreen::lines(k, max_characters);
+        let pages: Vec<&[(String, u32)]> = lines.chunks(lines_per_page).collect();
+        self.page_count = pages.len();
+        self.page = self.page.min(self.page_count.saturating_sub(1));
+        let title = format!("Help, page {}/{}: F1 closes, PageUp and PageDown change the page", self.page + 1, self.page_count);
+        self.overlay.add_text((HELP_MARGIN, HELP_MARGIN), &title, CATEGORY_COLOR, scale);
+        if let Some(page) = pages.get(self.page) {
+            for (i, (line, color)) in page.iter().enumerate() {
+                self.overlay.add_text((HELP_MARGIN, HELP_MARGIN + (i + 2) * line_height), line, *color, scale);
+            }
+        }
+    }
+
+    ///Draws the `HelpScreen` over `pixels`, an image of `width` x `height` pixels
+    pub fn draw(&self, pixels: &mut [u32], width: usize, height: usize) {
+        self.overlay.draw(pixels, width, height);
+    }
+
+    ///Returns the lines of the help screen and their colors: a header for every category, followed by the key and description of its `KeyAction`s.
+    ///Lines longer than `max_characters` are wrapped
+    pub fn lines(k: &KeyBindings, max_characters: usize) -> Vec<(String, u32)> {
+        let mut lines = Vec::new();
+        for (category, key_actions) in k.categories() {
+            lines.push((format!("[{}]", category), CATEGORY_COLOR));
+            for key_action in key_actions {
+                let text = format!("{:?}: {} ({})", key_action.keys, key_action.description, key_action.name);
+                lines.extend(wrap(&text, max_characters, "    ").into_iter().map(|line| (line, HELP_COLOR)));
+            }
+        }
+        lines
+    }
+}
+
+///Wraps `text` at word boundaries into lines of at most `max_characters` characters, every line except the first is prefixed by `indent`.
+///Words longer than a line are split
+fn wrap(text: &str, max_characters: usize, indent: &str) -> Vec<String> {
+    let indent = if max_characters > 2 * indent.len() {indent} else {""}; //No room to indent
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let at_line_start = line.is_empty() || (!lines.is_empty() && line == indent);
+        if !at_line_start && line.chars().count() + 1 + word.chars().count() > max_characters {
+            lines.push(std::mem::replace(&mut line, String::from(indent)));
+        } else if !at_line_start {
+            line.push(' ');
+        }
+        line.push_str(word);
+        while line.chars().count() > max_characters {
+            let rest: String = line.chars().skip(max_characters).collect();
+            lines.push(line.chars().take(max_characters).collect());
+            line = format!("{}{}", indent, rest);
+        }
+    }
+    if !line.trim().is_empty() {
+        lines.push(line);
+    }
+    lines
+}
@@ -0,0 +1,261 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{coloring::ColoringAlgorithm, complex_plane::View, mandelbrot_set::MandelbrotSet, pixel_buffer::PixelBuffer};
+
+///The file in the output directory the viewer exports the navigation history to
+pub static HISTORY_FILE_NAME: &str = "history.json";
+///The amount of most recent `HistoryEntry`s that keep their rendered `PixelBuffer` if no `--history-cache` is given, so that going back to them is instant
+pub static HISTORY_CACHE_SIZE: usize = 16;
+///Views whose centers, scales and rotations differ by less than this fraction of the scale are the same view
+static VIEW_TOLERANCE: f64 = 1e-9;
+
+///A rendered `PixelBuffer`, together with the settings it was rendered with that are not stored in the `PixelBuffer` itself
+struct CachedRender {
+    p: PixelBuffer,
+    coloring_algorithm: ColoringAlgorithm,
+    supersampling_amount: u8,
+}
+
+///A visited view and the `MandelbrotSet` parameters it was rendered with, optionally with its rendered `PixelBuffer`
+#[derive(Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub view: View,
+    pub mandelbrot_set: MandelbrotSet,
+    #[serde(skip)]
+    render: Option<CachedRender>,
+}
+
+impl HistoryEntry {
+    ///Returns whether `view` and `m` are the view and parameters of this `HistoryEntry`, up to rounding errors in the view
+    pub fn is(&self, view: &View, m: &MandelbrotSet) -> bool {
+        let tolerance = VIEW_TOLERANCE * self.view.scale;
+        (self.view.x - view.x).abs() <= tolerance && (self.view.y - view.y).abs() <= tolerance && (self.view.scale - view.scale).abs() <= tolerance
+            && (self.view.rotation - view.rotation).abs() <= VIEW_TOLERANCE
+            && self.mandelbrot_set.max_iterations == m.max_iterations && (self.mandelbrot_set.orbit_radius - m.orbit_radius).abs() <= f64::EPSILON
+            && self.mandelbrot_set.julia_c.map(|c| (c.x, c.y)) == m.julia_c.map(|c| (c.x, c.y))
+    }
+
+    ///Returns the cached `PixelBuffer` of this `HistoryEntry` if it can replace `p`: it has the same size and was rendered with the same coloring settings
+    pub fn cached_pixel_buffer(&self, p: &PixelBuffer, coloring_algorithm: ColoringAlgorithm, supersampling_amount: u8) -> Option<&PixelBuffer> {
+        self.render.as_ref().filter(|render| {
+            render.p.pixel_plane.width == p.pixel_plane.width && render.p.pixel_plane.height == p.pixel_plane.height
+                && render.p.color_channel_mapping == p.color_channel_mapping && render.p.palette_offset == p.palette_offset
+                && render.coloring_algorithm == coloring_algorithm && render.supersampling_amount == supersampling_amount
+        }).map(|render| &render.p)
+    }
+}
+
+///Navigation history of the viewer: the visited views, the current one, and the views that were undone and can be redone.
+///
+///Can be exported to and read from a JSON history file, the cached `PixelBuffer`s are not exported
+#[derive(Serialize, Deserialize)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    ///Index of the current `HistoryEntry`
+    current: usize,
+    ///The amount of most recent `HistoryEntry`s that keep their rendered `PixelBuffer`, 0 disables caching
+    #[serde(skip, default = "default_cache_size")]
+    cache_size: usize,
+}
+
+impl History {
+    pub fn new(cache_size: usize) -> History {
+        History { entries: Vec::new(), current: 0, cache_size }
+    }
+
+    ///Returns the current `HistoryEntry`, None if the `History` is empty
+    pub fn current(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.current)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    ///Returns the position of the current `HistoryEntry`, counting from 1, and the amount of `HistoryEntry`s
+    pub fn position(&self) -> (usize, usize) {
+        (self.current + 1, self.entries.len())
+    }
+
+    ///Records `view` and `m`, rendered into `p`, as the current `HistoryEntry` if they differ from the current `HistoryEntry`.
+    ///Recording a new view discards the `HistoryEntry`s that were undone. Returns whether a new `HistoryEntry` was recorded
+    pub fn record(&mut self, view: View, m: &MandelbrotSet, p: &PixelBuffer, coloring_algorithm: ColoringAlgorithm, supersampling_amount: u8) -> bool {
+        if self.current().is_some_and(|entry| entry.is(&view, m)) {
+            return false;
+        }
+        self.entries.truncate(self.current + 1);
+        self.entries.push(HistoryEntry { view, mandelbrot_set: m.clone(), render: None });
+        self.current = self.entries.len() - 1;
+        self.cache(p, coloring_algorithm, supersampling_amount);
+        self.evict();
+        true
+    }
+
+    ///Sets the amount of most recent `HistoryEntry`s that keep their rendered `PixelBuffer`, 0 disables caching
+    pub fn set_cache_size(&mut self, cache_size: usize) {
+        self.cache_size = cache_size;
+        self.evict();
+    }
+
+    ///Drops the rendered `PixelBuffer`s of all but the `cache_size` most recent `HistoryEntry`s
+    fn evict(&mut self) {
+        let uncached = self.entries.len().saturating_sub(self.cache_size);
+        for entry in &mut self.entries[..uncached] {
+            entry.render = None;
+        }
+    }
+
+    ///Caches `p` as the rendered `PixelBuffer` of the current `HistoryEntry`, e.g. after it has been rendered with other coloring settings,
+    ///if it is one of the `cache_size` most recent `HistoryEntry`s
+    pub fn cache(&mut self, p: &PixelBuffer, coloring_algorithm: ColoringAlgorithm, supersampling_amount: u8) {
+        //Only the most recent HistoryEntrys keep their PixelBuffers
+        if self.current + self.cache_size < self.entries.len() {
+            return;
+        }
+        if let Some(entry) = self.entries.get_mut(self.current) {
+            entry.render = Some(CachedRender { p: p.clone(), coloring_algorithm, supersampling_amount });
+        }
+    }
+
+    ///Goes back to the previous `HistoryEntry` and returns it, None if the current `HistoryEntry` is the first
+    pub fn undo(&mut self) -> Option<&HistoryEntry> {
+        if self.current == 0 {
+            return None;
+        }
+        self.current -= 1;
+        self.current()
+    }
+
+    ///Goes forward to the `HistoryEntry` that was undone last and returns it, None if nothing was undone
+    pub fn redo(&mut self) -> Option<&HistoryEntry> {
+        if self.current + 1 >= self.entries.len() {
+            return None;
+        }
+        self.current += 1;
+        self.current()
+    }
+
+    ///Writes the `History` to the JSON history file at `path`, creating its directory if it does not exist
+    /// # Errors
+    /// Returns an Error if the directory or the file cannot be written
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        if let Some(directory) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|err| format!("{}: {}", err, directory.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("{}: {}", err, path))?;
+        fs::write(path, json).map_err(|err| format!("{}: {}", err, path))
+    }
+
+    ///Reads a `History` from the JSON history file at `path`, keeping the rendered `PixelBuffer`s of the `cache_size` most recent `HistoryEntry`s from now on
+    /// # Errors
+    /// Returns an Error if the file cannot be read, is not a valid history file, or does not contain any views
+    pub fn read_from_file(path: &str, cache_size: usize) -> Result<History, String> {
+        let json = fs::read_to_string(path).map_err(|err| format!("{}: {}", err, path))?;
+        let mut history: History = serde_json::from_str(&json).map_err(|err| format!("Invalid history file: {}: {}", err, path))?;
+        if history.current >= history.entries.len() {
+            return Err(format!("The current view of a history file should be one of its views: {}", path));
+        }
+        history.cache_size = cache_size;
+        Ok(history)
+    }
+}
+
+fn default_cache_size() -> usize {
+    HISTORY_CACHE_SIZE
+}
+
+impl Default for History {
+    fn default() -> History {
+        History::new(HISTORY_CACHE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{complex::Complex, pixel_buffer::pixel_plane::PixelPlane, test_utils::temporary_directory};
+
+    fn view(x: f64) -> View {
+        View::new(x, 0.0, 1.0)
+    }
+
+    fn pixel_buffer() -> PixelBuffer {
+        PixelBuffer::new(PixelPlane::new(4, 3))
+    }
+
+    ///Returns a `History` of the views at x = 0, 1, ..., `length` - 1
+    fn history(length: usize, cache_size: usize) -> History {
+        let mut history = History::new(cache_size);
+        let (m, p) = (MandelbrotSet::new(100, 2.0), pixel_buffer());
+        for x in 0..length {
+            history.record(view(f64::from(x as u32)), &m, &p, ColoringAlgorithm::HSV, 1);
+        }
+        history
+    }
+
+    fn is_cached(history: &History) -> Vec<bool> {
+        history.entries.iter().map(|entry| entry.render.is_some()).collect()
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn recording_after_undoing_discards_the_undone_views() {
+        let mut history = history(3, HISTORY_CACHE_SIZE);
+        assert_eq!(history.undo().map(|entry| entry.view.x), Some(1.0));
+        assert_eq!(history.undo().map(|entry| entry.view.x), Some(0.0));
+        assert!(history.undo().is_none());
+        assert_eq!(history.redo().map(|entry| entry.view.x), Some(1.0));
+        assert!(history.record(view(5.0), &MandelbrotSet::new(100, 2.0), &pixel_buffer(), ColoringAlgorithm::HSV, 1));
+        assert_eq!(history.position(), (3, 3));
+        assert!(history.redo().is_none());
+        assert_eq!(history.entries.iter().map(|entry| entry.view.x).collect::<Vec<f64>>(), [0.0, 1.0, 5.0]);
+    }
+
+    #[test]
+    fn ignores_rounding_errors_in_views() {
+        let m = MandelbrotSet::new(100, 2.0);
+        let mut history = history(1, HISTORY_CACHE_SIZE);
+        let entry = history.current().unwrap();
+        assert!(entry.is(&View::new_with_rotation(1e-12, -1e-12, 1.0 + 1e-12, 1e-12), &m));
+        assert!(!entry.is(&View::new(1e-6, 0.0, 1.0), &m));
+        assert!(!entry.is(&view(0.0), &MandelbrotSet::new(200, 2.0)));
+        assert!(!entry.is(&view(0.0), &MandelbrotSet { julia_c: Some(Complex::new(-0.8, 0.156)), ..m.clone() }));
+        //Recording the same view with rounding errors does not add a HistoryEntry
+        assert!(!history.record(View::new(1e-12, 0.0, 1.0), &m, &pixel_buffer(), ColoringAlgorithm::HSV, 1));
+        assert_eq!(history.position(), (1, 1));
+    }
+
+    #[test]
+    fn only_caches_the_most_recent_views() {
+        let mut history = history(5, 2);
+        assert_eq!(is_cached(&history), [false, false, false, true, true]);
+        let p = pixel_buffer();
+        assert!(history.current().unwrap().cached_pixel_buffer(&p, ColoringAlgorithm::HSV, 1).is_some());
+        assert!(history.current().unwrap().cached_pixel_buffer(&p, ColoringAlgorithm::HSV, 2).is_none());
+        //Views that are not one of the most recent ones are not cached again
+        history.undo();
+        history.undo();
+        history.cache(&p, ColoringAlgorithm::HSV, 1);
+        assert_eq!(is_cached(&history), [false, false, false, true, true]);
+        history.set_cache_size(1);
+        assert_eq!(is_cached(&history), [false, false, false, false, true]);
+        assert_eq!(is_cached(&self::history(3, 0)), [false, false, false]);
+    }
+
+    #[test]
+    fn rejects_history_files_with_the_current_view_out_of_range() {
+        let directory = temporary_directory("history_files");
+        let path = directory.join("history.json").to_string_lossy().into_owned();
+        let mut history = history(3, HISTORY_CACHE_SIZE);
+        history.undo();
+        history.save_to_file(&path).unwrap();
+        let read = History::read_from_file(&path, 1).unwrap();
+        assert_eq!((read.position(), read.cache_size), ((2, 3), 1));
+        history.current = 3;
+        history.save_to_file(&path).unwrap();
+        assert_eq!(History::read_from_file(&path, 1).err().unwrap(), format!("The current view of a history file should be one of its views: {}", path));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use minifb::{MouseMode, Window};
+
+use crate::{coloring::ColoringAlgorithm, complex_plane::ComplexPlane, mandelbrot_set::MandelbrotSet, pixel_buffer::{PixelBuffer, overlay::{Overlay, font::{self, LINE_HEIGHT}}}};
+
+//Color of the HUD text
+static HUD_COLOR: u32 = 0x00FF_FFFF;
+//Distance of the HUD to the top left corner of the window in pixels
+static HUD_MARGIN: usize = 8;
+//Color of the error line
+static ERROR_COLOR: u32 = 0x00FF_5050;
+//How long the error line is shown
+static ERROR_DURATION: Duration = Duration::from_secs(5);
+
+///Heads-up display drawn over the window, showing the current view, the rendering parameters, and the complex number and iteration count
+///of the point under the mouse cursor.
+///
+///The last error is shown at the bottom of the window for `ERROR_DURATION`, even if the `Hud` is hidden
+#[derive(Default)]
+pub struct Hud {
+    pub visible: bool,
+    overlay: Overlay,
+    ///The message of the last error and when it occurred
+    error: Option<(String, Instant)>,
+}
+
+impl Hud {
+    pub fn new(visible: bool) -> Hud {
+        Hud { visible, overlay: Overlay::new(), error: None }
+    }
+
+    ///Shows the error `message` at the bottom of the window for `ERROR_DURATION`, replacing the previous error
+    pub fn show_error(&mut self, message: &str) {
+        self.error = Some((format!("Error: {}", message), Instant::now()));
+    }
+
+    ///Hides the error line before `ERROR_DURATION` has passed
+    pub fn dismiss_error(&mut self) {
+        self.error = None;
+    }
+
+    ///Returns whether the `Hud` or its error line is shown, forgetting the error once `ERROR_DURATION` has passed
+    pub fn is_shown(&mut self) -> bool {
+        if self.error.as_ref().is_some_and(|(_, time)| time.elapsed() >= ERROR_DURATION) {
+            self.error = None;
+        }
+        self.visible || self.error.is_some()
+    }
+
+    ///Shows the `Hud` if it is hidden, hides it if it is shown
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    ///Rebuilds the text of the `Hud` from the current state, only the error line if the `Hud` is hidden
+    pub fn update(&mut self, window: &Window, c: &ComplexPlane, p: &PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_algorithm: ColoringAlgorithm) {
+        self.overlay.clear();
+        if !self.is_shown() {
+            return;
+        }
+        let scale = font::scale_for_width(p.pixel_plane.width);
+        if self.visible {
+            let cursor = window.get_mouse_pos(MouseMode::Discard);
+            for (i, line) in Hud::lines(c, p, m, supersampling_amount, coloring_algorithm, cursor).iter().enumerate() {
+                self.overlay.add_text((HUD_MARGIN, HUD_MARGIN + i * LINE_HEIGHT * scale), line, HUD_COLOR, scale);
+            }
+        }
+        if let Some((message, _)) = &self.error {
+            let y = p.pixel_plane.height.saturating_sub(HUD_MARGIN + LINE_HEIGHT * scale);
+            self.overlay.add_text((HUD_MARGIN, y), message, ERROR_COLOR, scale);
+        }
+    }
+
+    ///Draws the `Hud` over `pixels`, an image of `width` x `height` pixels
+    pub fn draw(&self, pixels: &mut [u32], width: usize, height: usize) {
+        self.overlay.draw(pixels, width, height);
+    }
+
+    ///Returns the lines of text shown by the `Hud`, `cursor` is the position of the mouse cursor in the window if it is inside the window
+    pub fn lines(c: &ComplexPlane, p: &PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_algorithm: ColoringAlgorithm, cursor: Option<(f32, f32)>) -> Vec<String> {
+        let mut lines = vec![
+            format!("Center: {:?}", c.center()),
+            format!("Scale: {:e}, rotation: {} degrees", c.get_scale(), c.get_rotation()),
+            format!("max_iterations: {}, SSAA: {}x", m.max_iterations, supersampling_amount),
+            format!("Coloring: {}, {:?}", coloring_algorithm.name(), p.color_channel_mapping),
+            p.render_time.map_or_else(|| String::from("Render time: -"), |render_time| format!("Render time: {:.2?}", render_time)),
+        ];
+        if let Some((x, y)) = cursor {
+            let complex = c.complex_from_pixel_plane(x.into(), y.into());
+            lines.push(format!("Cursor: {:?}", complex));
+            lines.push(format!("Iterations: {}", m.iterate(&complex)));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_errors_until_they_expire_or_are_dismissed() {
+        let mut hud = Hud::new(false);
+        assert!(!hud.is_shown());
+        hud.show_error("No space left on device");
+        assert!(hud.is_shown());
+        assert_eq!(hud.error.as_ref().map(|(message, _)| message.as_str()), Some("Error: No space left on device"));
+        hud.dismiss_error();
+        assert!(!hud.is_shown());
+        hud.error = Some((String::from("Error: expired"), Instant::now().checked_sub(ERROR_DURATION).unwrap()));
+        assert!(!hud.is_shown());
+        assert!(hud.error.is_none());
+    }
+}
@@ -0,0 +1,135 @@
+use std::{io::Write, path::Path, str::FromStr};
+
+use crate::{metadata::Metadata, pixel_buffer::PixelBuffer};
+
+pub mod bmp;
+pub mod jpeg;
+pub mod ppm;
+pub mod tiff;
+
+///The quality of `ImageFormat::Jpeg` images if it is not given, from 1 to 100
+pub static DEFAULT_JPEG_QUALITY: u8 = 90;
+
+///The file formats 8-bit RGB images can be saved as, chosen by the extension of the file name or by `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    ///A png image, storing the `Metadata` in an iTXt chunk, see `PixelBuffer::write_png`
+    Png,
+    ///A binary PPM (P6) image, storing the `Metadata` in a header comment
+    Ppm,
+    ///A 24-bit BMP image, which cannot store the `Metadata`
+    Bmp,
+    ///An uncompressed TIFF image, storing the `Metadata` in the image description
+    Tiff,
+    ///A baseline JPEG image of the given quality from 1 to 100, storing the `Metadata` in a COM segment
+    Jpeg(u8),
+}
+
+impl ImageFormat {
+    ///Returns the file extension of this `ImageFormat`
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Bmp => "bmp",
+            ImageFormat::Tiff => "tif",
+            ImageFormat::Jpeg(_) => "jpg",
+        }
+    }
+
+    ///Returns the `ImageFormat` of a file `extension`, case-insensitive, e.g. `jpg` or `TIFF`.
+    ///
+    ///A JPEG extension keeps the quality of this `ImageFormat` if it is a JPEG format as well
+    fn with_extension(self, extension: &str) -> Option<ImageFormat> {
+        match extension.to_lowercase().as_str() {
+            "jpg" | "jpeg" if matches!(self, ImageFormat::Jpeg(_)) => Some(self),
+            extension => extension.parse().ok(),
+        }
+    }
+
+    ///Splits `file_name` into the file name without its extension and the `ImageFormat` of its extension, e.g. `name.jpg`.
+    ///
+    ///Returns `file_name` and this `ImageFormat` if the extension is not an image format
+    pub fn split_file_name(self, file_name: &str) -> (&str, ImageFormat) {
+        let Some(extension) = Path::new(file_name).extension().map(|extension| extension.to_string_lossy()) else {
+            return (file_name, self);
+        };
+        //Only the extension is removed, the name may contain directories with dots, e.g. `a.b/name.jpg`
+        match (self.with_extension(&extension), file_name.strip_suffix(&format!(".{}", extension))) {
+            (Some(format), Some(file_name_without_extension)) => (file_name_without_extension, format),
+            _ => (file_name, self),
+        }
+    }
+
+    ///Encodes `rgb`, the RGB bytes of an image of `width` x `height` pixels, as this `ImageFormat` into `w`, storing `metadata` if the format supports it
+    /// # Errors
+    /// Returns an Error if the size is not supported by the format, or the image cannot be encoded or written
+    pub fn write<W: Write>(self, mut w: W, width: usize, height: usize, rgb: &[u8], metadata: &Metadata) -> Result<(), String> {
+        match self {
+            ImageFormat::Png => {
+                let encoder = PixelBuffer::png_encoder(&mut w, width, height, png::BitDepth::Eight, metadata)?;
+                encoder.write_header().and_then(|mut writer| writer.write_image_data(rgb)).map_err(|err| err.to_string())
+            }
+            ImageFormat::Ppm => ppm::write_ppm(&mut w, width, height, rgb, metadata),
+            ImageFormat::Bmp => bmp::write_bmp(&mut w, width, height, rgb),
+            ImageFormat::Tiff => tiff::write_tiff(&mut w, width, height, rgb, metadata),
+            ImageFormat::Jpeg(quality) => jpeg::write_jpeg(&mut w, width, height, rgb, quality, metadata),
+        }?;
+        w.flush().map_err(|err| err.to_string())
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    ///Parses an `ImageFormat`, case-insensitive: `png`, `ppm`, `bmp`, `tiff` or `jpeg`, with an optional JPEG quality, e.g. `jpeg:85`
+    fn from_str(s: &str) -> Result<ImageFormat, String> {
+        let (name, quality) = s.split_once(':').map_or((s, None), |(name, quality)| (name, Some(quality)));
+        match (name.to_lowercase().as_str(), quality) {
+            ("png", None) => Ok(ImageFormat::Png),
+            ("ppm", None) => Ok(ImageFormat::Ppm),
+            ("bmp", None) => Ok(ImageFormat::Bmp),
+            ("tiff" | "tif", None) => Ok(ImageFormat::Tiff),
+            ("jpeg" | "jpg", None) => Ok(ImageFormat::Jpeg(DEFAULT_JPEG_QUALITY)),
+            ("jpeg" | "jpg", Some(quality)) => match quality.parse::<u8>() {
+                Ok(quality) if (1..=100).contains(&quality) => Ok(ImageFormat::Jpeg(quality)),
+                _ => Err(format!("The JPEG quality should be between 1 and 100: {}", quality)),
+            },
+            _ => Err(format!("Unknown image format: {}, should be png, ppm, bmp, tiff or jpeg[:quality]", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_extension_of_image_formats_off_file_names() {
+        let png = ImageFormat::Png;
+        assert_eq!(png.split_file_name("name.jpg"), ("name", ImageFormat::Jpeg(DEFAULT_JPEG_QUALITY)));
+        assert_eq!(png.split_file_name("dir/name.jpg"), ("dir/name", ImageFormat::Jpeg(DEFAULT_JPEG_QUALITY)));
+        assert_eq!(png.split_file_name("a.b/name.jpg"), ("a.b/name", ImageFormat::Jpeg(DEFAULT_JPEG_QUALITY)));
+        assert_eq!(png.split_file_name("renders/zoom.TIFF"), ("renders/zoom", ImageFormat::Tiff));
+        assert_eq!(png.split_file_name("name.unknown"), ("name.unknown", png));
+        assert_eq!(png.split_file_name("a.b/name"), ("a.b/name", png));
+        //The quality of --format is kept for JPEG file names
+        assert_eq!(ImageFormat::Jpeg(50).split_file_name("name.jpeg"), ("name", ImageFormat::Jpeg(50)));
+        assert_eq!(ImageFormat::Jpeg(50).split_file_name("name.bmp"), ("name", ImageFormat::Bmp));
+    }
+
+    #[test]
+    fn parses_image_formats_with_a_jpeg_quality() {
+        assert_eq!("PNG".parse(), Ok(ImageFormat::Png));
+        assert_eq!("tif".parse(), Ok(ImageFormat::Tiff));
+        assert_eq!("jpeg".parse(), Ok(ImageFormat::Jpeg(DEFAULT_JPEG_QUALITY)));
+        assert_eq!("jpg:85".parse(), Ok(ImageFormat::Jpeg(85)));
+        assert_eq!("jpeg:1".parse(), Ok(ImageFormat::Jpeg(1)));
+        assert_eq!("jpeg:100".parse(), Ok(ImageFormat::Jpeg(100)));
+        for quality in ["0", "101", "256", "-1", "high", ""] {
+            assert_eq!(format!("jpeg:{}", quality).parse::<ImageFormat>(), Err(format!("The JPEG quality should be between 1 and 100: {}", quality)));
+        }
+        assert_eq!("png:5".parse::<ImageFormat>(), Err(String::from("Unknown image format: png:5, should be png, ppm, bmp, tiff or jpeg[:quality]")));
+        assert!("gif".parse::<ImageFormat>().is_err());
+    }
+}
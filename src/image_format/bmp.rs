@@ -0,0 +1,78 @@
+use std::io::Write;
+
+///The size in bytes of the BITMAPFILEHEADER and BITMAPINFOHEADER of a BMP image
+static FILE_HEADER_SIZE: usize = 14;
+static INFO_HEADER_SIZE: usize = 40;
+
+///Encodes `rgb`, the RGB bytes of an image of `width` x `height` pixels, as an uncompressed 24-bit BMP image into `w`.
+///
+///The rows are stored bottom-up as BGR bytes, every row padded to a multiple of 4 bytes
+/// # Errors
+/// Returns an Error if the image is too large for a BMP image, or cannot be written
+pub fn write_bmp<W: Write>(w: &mut W, width: usize, height: usize, rgb: &[u8]) -> Result<(), String> {
+    let too_large = || format!("The image is too large for a BMP image: {}x{}", width, height);
+    let row_size = (width * 3).next_multiple_of(4);
+    let image_size = u32::try_from(row_size * height).map_err(|_| too_large())?;
+    let file_size = image_size.checked_add((FILE_HEADER_SIZE + INFO_HEADER_SIZE) as u32).ok_or_else(too_large)?;
+    let (bmp_width, bmp_height) = (i32::try_from(width).map_err(|_| too_large())?, i32::try_from(height).map_err(|_| too_large())?);
+    let mut header = Vec::with_capacity(FILE_HEADER_SIZE + INFO_HEADER_SIZE);
+    header.extend_from_slice(b"BM");
+    header.extend_from_slice(&file_size.to_le_bytes());
+    header.extend_from_slice(&[0; 4]); //Reserved
+    header.extend_from_slice(&((FILE_HEADER_SIZE + INFO_HEADER_SIZE) as u32).to_le_bytes()); //Offset of the pixels
+    header.extend_from_slice(&(INFO_HEADER_SIZE as u32).to_le_bytes());
+    header.extend_from_slice(&bmp_width.to_le_bytes());
+    header.extend_from_slice(&bmp_height.to_le_bytes()); //A positive height means the rows are stored bottom-up
+    header.extend_from_slice(&1_u16.to_le_bytes()); //Planes
+    header.extend_from_slice(&24_u16.to_le_bytes()); //Bits per pixel
+    header.extend_from_slice(&0_u32.to_le_bytes()); //Compression: BI_RGB
+    header.extend_from_slice(&image_size.to_le_bytes());
+    header.extend_from_slice(&2835_i32.to_le_bytes()); //Horizontal resolution: 72 DPI
+    header.extend_from_slice(&2835_i32.to_le_bytes()); //Vertical resolution: 72 DPI
+    header.extend_from_slice(&[0; 8]); //Colors in the palette and important colors
+    w.write_all(&header).map_err(|err| err.to_string())?;
+    let mut row = vec![0; row_size];
+    for pixels in rgb.chunks_exact(width * 3).rev() {
+        for (bgr, pixel) in row.chunks_exact_mut(3).zip(pixels.chunks_exact(3)) {
+            bgr.copy_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+        }
+        w.write_all(&row).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn stores_the_rows_bottom_up_padded_to_4_bytes() {
+        let rgb: Vec<u8> = (1..=18).collect();
+        let mut bmp = Vec::new();
+        write_bmp(&mut bmp, 3, 2, &rgb).unwrap();
+        //9 bytes of pixels per row are padded to 12 bytes
+        assert_eq!(bmp.len(), 54 + 2 * 12);
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(u32_at(&bmp, 2), 78); //File size
+        assert_eq!(u32_at(&bmp, 10), 54); //Offset of the pixels
+        assert_eq!(u32_at(&bmp, 14), 40); //Size of the BITMAPINFOHEADER
+        assert_eq!((u32_at(&bmp, 18), u32_at(&bmp, 22)), (3, 2));
+        assert_eq!(&bmp[26..30], &[1, 0, 24, 0]); //Planes and bits per pixel
+        assert_eq!(u32_at(&bmp, 30), 0); //No compression
+        assert_eq!(u32_at(&bmp, 34), 24); //Image size
+        //The bottom row comes first, as BGR bytes
+        assert_eq!(&bmp[54..66], &[12, 11, 10, 15, 14, 13, 18, 17, 16, 0, 0, 0]);
+        assert_eq!(&bmp[66..78], &[3, 2, 1, 6, 5, 4, 9, 8, 7, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rows_of_a_multiple_of_4_bytes_are_not_padded() {
+        let mut bmp = Vec::new();
+        write_bmp(&mut bmp, 4, 1, &[0; 12]).unwrap();
+        assert_eq!(bmp.len(), 54 + 12);
+    }
+}
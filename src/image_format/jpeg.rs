@@ -0,0 +1,364 @@
+use std::{f32::consts::PI, io::Write};
+
+use crate::metadata::Metadata;
+
+///The index in a block of 8x8 samples of every coefficient in zigzag order
+static ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+///The quantization tables of the JPEG standard (Annex K.1) at quality 50, for the luminance and chrominance components
+static LUMINANCE_QUANTIZATION: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56, 14, 17, 22, 29, 51, 87, 80, 62,
+    18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113, 92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+static CHROMINANCE_QUANTIZATION: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99, 47, 66, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+///The Huffman tables of the JPEG standard (Annex K.3): the amount of codes of every length from 1 to 16 bits, followed by the symbols in order of their codes
+static LUMINANCE_DC_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+static LUMINANCE_DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+static CHROMINANCE_DC_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+static CHROMINANCE_DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+static LUMINANCE_AC_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+static LUMINANCE_AC_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07, 0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08,
+    0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0, 0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59,
+    0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6,
+    0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa,
+];
+static CHROMINANCE_AC_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+static CHROMINANCE_AC_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71, 0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91,
+    0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0, 0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58,
+    0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4,
+    0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa,
+];
+///The symbols of the end of block and of a run of 16 zero AC coefficients
+static END_OF_BLOCK: u8 = 0x00;
+static ZERO_RUN: u8 = 0xf0;
+
+///The codes of a Huffman table: the code and its length in bits of every symbol
+struct HuffmanTable {
+    codes: [(u16, u8); 256],
+}
+
+impl HuffmanTable {
+    ///Creates the canonical Huffman codes of the `bits` and `values` of a table, see JPEG Annex C
+    fn new(bits: &[u8; 16], values: &[u8]) -> HuffmanTable {
+        let mut codes = [(0, 0); 256];
+        let mut code: u16 = 0;
+        let mut values = values.iter();
+        for (length, amount) in (1..=16).zip(bits) {
+            for value in values.by_ref().take(usize::from(*amount)) {
+                codes[usize::from(*value)] = (code, length);
+                code += 1;
+            }
+            code <<= 1;
+        }
+        HuffmanTable { codes }
+    }
+}
+
+///A component of the YCbCr image: its quantization table in natural order, its Huffman tables and the DC coefficient of its previous block
+struct Component {
+    quantization: [u16; 64],
+    dc_table: HuffmanTable,
+    ac_table: HuffmanTable,
+    previous_dc: i32,
+}
+
+///Writes the entropy coded data, stuffing a zero byte after every 0xFF byte
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    length: u8,
+}
+
+impl BitWriter {
+    ///Writes the lowest `length` bits of `bits`
+    fn write(&mut self, bits: u32, length: u8) {
+        self.buffer = (self.buffer << length) | (bits & ((1 << length) - 1));
+        self.length += length;
+        while self.length >= 8 {
+            self.length -= 8;
+            let byte = (self.buffer >> self.length) as u8;
+            self.bytes.push(byte);
+            if byte == 0xff {
+                self.bytes.push(0);
+            }
+        }
+        self.buffer &= (1 << self.length) - 1;
+    }
+
+    ///Pads the last byte with one bits
+    fn pad(&mut self) {
+        if self.length > 0 {
+            self.write(0xff, 8 - self.length);
+        }
+    }
+}
+
+///Scales a quantization table of quality 50 to `quality`, using the scaling of the Independent JPEG Group
+fn scale_quantization(table: &[u16; 64], quality: u8) -> [u16; 64] {
+    let quality = u32::from(quality.clamp(1, 100));
+    let scale = if quality < 50 {5000 / quality} else {200 - 2 * quality};
+    table.map(|value| ((u32::from(value) * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+///Returns the amount of bits needed to store the magnitude of `value`, its category in the JPEG standard
+fn category(value: i32) -> u8 {
+    (u32::BITS - value.unsigned_abs().leading_zeros()) as u8
+}
+
+///Returns the bits stored after the Huffman code of `value` in its `category`: the value itself, or its one's complement if it is negative
+fn magnitude_bits(value: i32, category: u8) -> u32 {
+    if value < 0 {(value - 1) as u32 & ((1 << category) - 1)} else {value as u32}
+}
+
+///Returns the cosines of the forward DCT: `[u][x]` = C(u) / 2 * cos((2x + 1)uπ / 16)
+fn dct_cosines() -> [[f32; 8]; 8] {
+    let mut cosines = [[0.0; 8]; 8];
+    for (u, row) in (0_u8..).zip(&mut cosines) {
+        let normalization = if u == 0 {0.5 / 2.0_f32.sqrt()} else {0.5};
+        for (x, cosine) in (0_u8..).zip(row.iter_mut()) {
+            *cosine = normalization * (f32::from(2 * x + 1) * f32::from(u) * PI / 16.0).cos();
+        }
+    }
+    cosines
+}
+
+impl Component {
+    ///Transforms, quantizes and Huffman codes a block of 8x8 level shifted samples into `writer`
+    fn encode_block(&mut self, block: &[f32; 64], cosines: &[[f32; 8]; 8], writer: &mut BitWriter) {
+        //The 2D DCT is a 1D DCT of the rows followed by a 1D DCT of the columns
+        let mut rows = [0.0; 64];
+        for y in 0..8 {
+            for u in 0..8 {
+                rows[y * 8 + u] = (0..8).map(|x| cosines[u][x] * block[y * 8 + x]).sum();
+            }
+        }
+        let mut coefficients = [0; 64];
+        for v in 0..8 {
+            for u in 0..8 {
+                let coefficient: f32 = (0..8).map(|y| cosines[v][y] * rows[y * 8 + u]).sum();
+                coefficients[v * 8 + u] = (coefficient / f32::from(self.quantization[v * 8 + u])).round() as i32;
+            }
+        }
+        let difference = coefficients[0] - self.previous_dc;
+        self.previous_dc = coefficients[0];
+        let dc_category = category(difference);
+        let (code, length) = self.dc_table.codes[usize::from(dc_category)];
+        writer.write(u32::from(code), length);
+        writer.write(magnitude_bits(difference, dc_category), dc_category);
+        let mut zeros = 0;
+        for index in &ZIGZAG[1..] {
+            let coefficient = coefficients[*index];
+            if coefficient == 0 {
+                zeros += 1;
+                continue;
+            }
+            while zeros > 15 {
+                let (code, length) = self.ac_table.codes[usize::from(ZERO_RUN)];
+                writer.write(u32::from(code), length);
+                zeros -= 16;
+            }
+            let ac_category = category(coefficient);
+            let (code, length) = self.ac_table.codes[usize::from((zeros << 4) | ac_category)];
+            writer.write(u32::from(code), length);
+            writer.write(magnitude_bits(coefficient, ac_category), ac_category);
+            zeros = 0;
+        }
+        if zeros > 0 {
+            let (code, length) = self.ac_table.codes[usize::from(END_OF_BLOCK)];
+            writer.write(u32::from(code), length);
+        }
+    }
+}
+
+///Writes a marker segment: the `marker`, the length of the segment and its `data`
+fn write_segment<W: Write>(w: &mut W, marker: u8, data: &[u8]) -> Result<(), String> {
+    w.write_all(&[0xff, marker]).and_then(|()| w.write_all(&((data.len() + 2) as u16).to_be_bytes())).and_then(|()| w.write_all(data)).map_err(|err| err.to_string())
+}
+
+///Writes the DHT segment of the Huffman table with `class` (0 for DC, 1 for AC) and `id`
+fn write_huffman_table<W: Write>(w: &mut W, class: u8, id: u8, bits: &[u8; 16], values: &[u8]) -> Result<(), String> {
+    let mut data = vec![(class << 4) | id];
+    data.extend_from_slice(bits);
+    data.extend_from_slice(values);
+    write_segment(w, 0xc4, &data)
+}
+
+///Encodes `rgb`, the RGB bytes of an image of `width` x `height` pixels, as a baseline JPEG image of `quality` from 1 to 100 into `w`, storing `metadata` as JSON in a COM segment.
+///
+///The image is converted to YCbCr without chroma subsampling, and encoded using the quantization and Huffman tables of the JPEG standard
+/// # Errors
+/// Returns an Error if the image is larger than 65535x65535 pixels, or cannot be written
+pub fn write_jpeg<W: Write>(w: &mut W, width: usize, height: usize, rgb: &[u8], quality: u8, metadata: &Metadata) -> Result<(), String> {
+    let (Ok(jpeg_width), Ok(jpeg_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(format!("The image is too large for a JPEG image: {}x{}", width, height));
+    };
+    w.write_all(&[0xff, 0xd8]).map_err(|err| err.to_string())?; //SOI
+    write_segment(w, 0xe0, &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0])?; //APP0: JFIF 1.1, 1:1 pixel aspect ratio, no thumbnail
+    for comment in metadata.to_json().as_bytes().chunks(usize::from(u16::MAX) - 2) {
+        write_segment(w, 0xfe, comment)?; //COM
+    }
+    let mut components = [
+        (scale_quantization(&LUMINANCE_QUANTIZATION, quality), &LUMINANCE_DC_BITS, &LUMINANCE_DC_VALUES, &LUMINANCE_AC_BITS, &LUMINANCE_AC_VALUES),
+        (scale_quantization(&CHROMINANCE_QUANTIZATION, quality), &CHROMINANCE_DC_BITS, &CHROMINANCE_DC_VALUES, &CHROMINANCE_AC_BITS, &CHROMINANCE_AC_VALUES),
+        (scale_quantization(&CHROMINANCE_QUANTIZATION, quality), &CHROMINANCE_DC_BITS, &CHROMINANCE_DC_VALUES, &CHROMINANCE_AC_BITS, &CHROMINANCE_AC_VALUES),
+    ].map(|(quantization, dc_bits, dc_values, ac_bits, ac_values)| Component { quantization, dc_table: HuffmanTable::new(dc_bits, dc_values), ac_table: HuffmanTable::new(ac_bits, ac_values), previous_dc: 0 });
+    //DQT: the tables are stored in zigzag order
+    for (id, component) in (0..).zip(&components[..2]) {
+        let mut data = vec![id];
+        data.extend(ZIGZAG.iter().map(|index| component.quantization[*index] as u8));
+        write_segment(w, 0xdb, &data)?;
+    }
+    //SOF0: 8-bit samples, the size, and the Y, Cb and Cr components with 1x1 sampling and their quantization table
+    let mut frame = vec![8];
+    frame.extend_from_slice(&jpeg_height.to_be_bytes());
+    frame.extend_from_slice(&jpeg_width.to_be_bytes());
+    frame.extend_from_slice(&[3, 1, 0x11, 0, 2, 0x11, 1, 3, 0x11, 1]);
+    write_segment(w, 0xc0, &frame)?;
+    write_huffman_table(w, 0, 0, &LUMINANCE_DC_BITS, &LUMINANCE_DC_VALUES)?;
+    write_huffman_table(w, 1, 0, &LUMINANCE_AC_BITS, &LUMINANCE_AC_VALUES)?;
+    write_huffman_table(w, 0, 1, &CHROMINANCE_DC_BITS, &CHROMINANCE_DC_VALUES)?;
+    write_huffman_table(w, 1, 1, &CHROMINANCE_AC_BITS, &CHROMINANCE_AC_VALUES)?;
+    //SOS: the Y component uses Huffman tables 0, Cb and Cr use Huffman tables 1, and every coefficient is in the single scan
+    write_segment(w, 0xda, &[3, 1, 0x00, 2, 0x11, 3, 0x11, 0, 63, 0])?;
+    let cosines = dct_cosines();
+    let mut writer = BitWriter { bytes: Vec::new(), buffer: 0, length: 0 };
+    let mut blocks = [[0.0; 64]; 3];
+    for block_y in (0..height).step_by(8) {
+        for block_x in (0..width).step_by(8) {
+            let [luminance, blue_difference, red_difference] = &mut blocks;
+            for (i, ((y_sample, cb_sample), cr_sample)) in luminance.iter_mut().zip(blue_difference.iter_mut()).zip(red_difference.iter_mut()).enumerate() {
+                //The edges of the image are repeated to fill the blocks at the right and bottom edges
+                let (x, y) = ((block_x + i % 8).min(width - 1), (block_y + i / 8).min(height - 1));
+                let pixel = &rgb[(y * width + x) * 3..(y * width + x) * 3 + 3];
+                let (red, green, blue) = (f32::from(pixel[0]), f32::from(pixel[1]), f32::from(pixel[2]));
+                //The samples are level shifted by -128, which cancels the offset of 128 of Cb and Cr
+                *y_sample = 0.299 * red + 0.587 * green + 0.114 * blue - 128.0;
+                *cb_sample = -0.168_736 * red - 0.331_264 * green + 0.5 * blue;
+                *cr_sample = 0.5 * red - 0.418_688 * green - 0.081_312 * blue;
+            }
+            for (component, block) in components.iter_mut().zip(&blocks) {
+                component.encode_block(block, &cosines, &mut writer);
+            }
+        }
+        //Write the entropy coded data of every row of blocks, so the image is not held in memory twice
+        w.write_all(&writer.bytes).map_err(|err| err.to_string())?;
+        writer.bytes.clear();
+    }
+    writer.pad();
+    w.write_all(&writer.bytes).and_then(|()| w.write_all(&[0xff, 0xd9])).map_err(|err| err.to_string()) //EOI
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    use super::*;
+
+    fn metadata() -> Metadata {
+        Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1)
+    }
+
+    ///Splits a JPEG image into its marker segments by their lengths, returns the markers and data of the segments up to and including SOS, and the entropy coded data
+    fn segments(jpeg: &[u8]) -> (Vec<(u8, &[u8])>, &[u8]) {
+        assert_eq!(&jpeg[..2], &[0xff, 0xd8]); //SOI
+        assert_eq!(&jpeg[jpeg.len() - 2..], &[0xff, 0xd9]); //EOI
+        let mut segments = Vec::new();
+        let mut offset = 2;
+        loop {
+            assert_eq!(jpeg[offset], 0xff);
+            let marker = jpeg[offset + 1];
+            let length = usize::from(u16::from_be_bytes([jpeg[offset + 2], jpeg[offset + 3]]));
+            segments.push((marker, &jpeg[offset + 4..offset + 2 + length]));
+            offset += 2 + length;
+            if marker == 0xda {
+                return (segments, &jpeg[offset..jpeg.len() - 2]);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_the_segments_of_a_baseline_jpeg_image() {
+        let rgb: Vec<u8> = (0..20 * 10 * 3).map(|i| (i * 7 % 256) as u8).collect();
+        let mut jpeg = Vec::new();
+        write_jpeg(&mut jpeg, 20, 10, &rgb, 85, &metadata()).unwrap();
+        let (segments, scan) = segments(&jpeg);
+        let markers: Vec<u8> = segments.iter().map(|(marker, _)| *marker).collect();
+        assert_eq!(markers, [0xe0, 0xfe, 0xdb, 0xdb, 0xc0, 0xc4, 0xc4, 0xc4, 0xc4, 0xda]);
+        assert_eq!(&segments[0].1[..5], b"JFIF\0");
+        assert_eq!(segments[1].1, metadata().to_json().as_bytes());
+        //Quantization tables of 64 8-bit values, scaled to quality 85
+        assert_eq!(segments[2].1.len(), 65);
+        assert_eq!(&segments[2].1[..2], &[0, 5]);
+        //SOF0: 8-bit samples, 10 rows of 20 pixels and 3 components
+        assert_eq!(&segments[4].1[..6], &[8, 0, 10, 0, 20, 3]);
+        assert_eq!(segments[5].1.len(), 1 + 16 + 12);
+        assert_eq!(segments[6].1.len(), 1 + 16 + 162);
+        //Every 0xFF byte of the entropy coded data is stuffed, so it contains no markers
+        for (i, byte) in scan.iter().enumerate() {
+            if *byte == 0xff {
+                assert_eq!(scan.get(i + 1), Some(&0), "unstuffed 0xFF at {}", i);
+            }
+        }
+    }
+
+    #[test]
+    fn a_gray_block_has_only_zero_coefficients() {
+        //Y = 128 and Cb = Cr = 0 after level shifting: every block is a DC difference of 0 followed by an end of block.
+        //Y: 00 (DC) 1010 (EOB), Cb and Cr: 00 (DC) 00 (EOB), padded with one bits
+        let mut jpeg = Vec::new();
+        write_jpeg(&mut jpeg, 8, 8, &[128; 8 * 8 * 3], 90, &metadata()).unwrap();
+        assert_eq!(segments(&jpeg).1, &[0b0010_1000, 0b0000_0011]);
+        //A second block of the same color has a DC difference of 0 as well
+        let mut jpeg = Vec::new();
+        write_jpeg(&mut jpeg, 16, 3, &[128; 16 * 3 * 3], 90, &metadata()).unwrap();
+        assert_eq!(segments(&jpeg).1, &[0b0010_1000, 0b0000_0000, 0b1010_0000, 0b0000_1111]);
+    }
+
+    #[test]
+    fn stuffs_a_zero_byte_after_every_0xff_byte() {
+        let mut writer = BitWriter { bytes: Vec::new(), buffer: 0, length: 0 };
+        writer.write(0xff, 8);
+        writer.write(0b1111, 4);
+        writer.write(0b1111_0000, 8);
+        writer.write(0b1, 1);
+        writer.pad();
+        assert_eq!(writer.bytes, [0xff, 0, 0xff, 0, 0b0000_1111]);
+    }
+
+    #[test]
+    fn creates_the_canonical_huffman_codes() {
+        let table = HuffmanTable::new(&LUMINANCE_DC_BITS, &LUMINANCE_DC_VALUES);
+        assert_eq!(table.codes[0], (0b00, 2));
+        assert_eq!(table.codes[1], (0b010, 3));
+        assert_eq!(table.codes[5], (0b110, 3));
+        assert_eq!(table.codes[6], (0b1110, 4));
+        assert_eq!(table.codes[11], (0b1_1111_1110, 9));
+        let table = HuffmanTable::new(&LUMINANCE_AC_BITS, &LUMINANCE_AC_VALUES);
+        assert_eq!(table.codes[usize::from(END_OF_BLOCK)], (0b1010, 4));
+        assert_eq!(table.codes[usize::from(ZERO_RUN)], (0b111_1111_1001, 11));
+    }
+
+    #[test]
+    fn scales_the_quantization_tables_and_codes_the_magnitudes() {
+        assert_eq!(scale_quantization(&LUMINANCE_QUANTIZATION, 50), LUMINANCE_QUANTIZATION);
+        assert_eq!(scale_quantization(&LUMINANCE_QUANTIZATION, 100), [1; 64]);
+        assert_eq!(scale_quantization(&LUMINANCE_QUANTIZATION, 1)[0], 255);
+        assert_eq!((category(0), category(1), category(-1), category(-3), category(255)), (0, 1, 1, 2, 8));
+        assert_eq!((magnitude_bits(3, 2), magnitude_bits(-3, 2), magnitude_bits(-1, 1)), (0b11, 0b00, 0b0));
+    }
+}
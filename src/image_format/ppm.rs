@@ -0,0 +1,30 @@
+use std::io::Write;
+
+use crate::metadata::{Metadata, METADATA_KEYWORD};
+
+///Encodes `rgb`, the RGB bytes of an image of `width` x `height` pixels, as a binary PPM (P6) image into `w`, storing `metadata` as JSON in a header comment
+/// # Errors
+/// Returns an Error if the image cannot be written
+pub fn write_ppm<W: Write>(w: &mut W, width: usize, height: usize, rgb: &[u8], metadata: &Metadata) -> Result<(), String> {
+    //The JSON of the metadata is a single line, so it fits in a comment
+    write!(w, "P6\n# {} {}\n{} {}\n255\n", METADATA_KEYWORD, metadata.to_json(), width, height).and_then(|()| w.write_all(rgb)).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    use super::*;
+
+    #[test]
+    fn writes_the_header_with_the_metadata_comment_followed_by_the_pixels() {
+        let metadata = Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1);
+        let rgb: Vec<u8> = (0..18).collect();
+        let mut ppm = Vec::new();
+        write_ppm(&mut ppm, 3, 2, &rgb, &metadata).unwrap();
+        let header = format!("P6\n# mandelbrot {}\n3 2\n255\n", metadata.to_json());
+        assert_eq!(&ppm[..header.len()], header.as_bytes());
+        assert_eq!(&ppm[header.len()..], rgb.as_slice());
+        assert!(!metadata.to_json().contains('\n'));
+    }
+}
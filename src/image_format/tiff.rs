@@ -0,0 +1,204 @@
+use std::io::Write;
+
+use crate::metadata::Metadata;
+
+///The TIFF field types used by `write_tiff`
+static SHORT: u16 = 3;
+static LONG: u16 = 4;
+static ASCII: u16 = 2;
+///The size in bytes of the TIFF header and of an IFD entry
+static HEADER_SIZE: usize = 8;
+static ENTRY_SIZE: usize = 12;
+
+///A sample type of a TIFF image
+pub trait TiffSample: Copy {
+    ///The value of the `BitsPerSample` tag
+    const BITS_PER_SAMPLE: u16;
+    ///The value of the `SampleFormat` tag: 1 for unsigned integers, 3 for IEEE floating point
+    const SAMPLE_FORMAT: u16;
+
+    ///Appends the sample to `bytes` as little-endian bytes
+    fn push_le_bytes(self, bytes: &mut Vec<u8>);
+}
+
+impl TiffSample for u8 {
+    const BITS_PER_SAMPLE: u16 = 8;
+    const SAMPLE_FORMAT: u16 = 1;
+
+    fn push_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.push(self);
+    }
+}
+
+impl TiffSample for f32 {
+    const BITS_PER_SAMPLE: u16 = 32;
+    const SAMPLE_FORMAT: u16 = 3;
+
+    fn push_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+///An IFD entry: its `tag`, field `kind`, amount of values and the values as little-endian bytes
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: u32,
+    value: Vec<u8>,
+}
+
+impl Entry {
+    fn shorts(tag: u16, values: &[u16]) -> Entry {
+        Entry { tag, kind: SHORT, count: values.len() as u32, value: values.iter().flat_map(|value| value.to_le_bytes()).collect() }
+    }
+
+    fn long(tag: u16, value: u32) -> Entry {
+        Entry { tag, kind: LONG, count: 1, value: value.to_le_bytes().to_vec() }
+    }
+
+    fn ascii(tag: u16, value: &str) -> Entry {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.push(0);
+        Entry { tag, kind: ASCII, count: bytes.len() as u32, value: bytes }
+    }
+}
+
+///Encodes `rgb`, the RGB samples of an image of `width` x `height` pixels, as an uncompressed little-endian TIFF image into `w`, e.g. with 8-bit or 32-bit float samples.
+///
+///Stores `metadata` as JSON in the image description
+/// # Errors
+/// Returns an Error if the image is larger than the 4 GiB a TIFF file can address, or cannot be written
+pub fn write_tiff<W: Write, T: TiffSample>(w: &mut W, width: usize, height: usize, rgb: &[T], metadata: &Metadata) -> Result<(), String> {
+    let too_large = || format!("The image is too large for a TIFF image: {}x{}", width, height);
+    let strip_size = u32::try_from(width * height * 3 * usize::from(T::BITS_PER_SAMPLE / 8)).map_err(|_| too_large())?;
+    let (tiff_width, tiff_height) = (u32::try_from(width).map_err(|_| too_large())?, u32::try_from(height).map_err(|_| too_large())?);
+    //The strip offset is filled in once the size of the values stored after the IFD is known
+    let mut entries = vec![
+        Entry::long(256, tiff_width), //ImageWidth
+        Entry::long(257, tiff_height), //ImageLength
+        Entry::shorts(258, &[T::BITS_PER_SAMPLE; 3]), //BitsPerSample
+        Entry::shorts(259, &[1]), //Compression: none
+        Entry::shorts(262, &[2]), //PhotometricInterpretation: RGB
+        Entry::ascii(270, &metadata.to_json()), //ImageDescription
+        Entry::long(273, 0), //StripOffsets
+        Entry::shorts(277, &[3]), //SamplesPerPixel
+        Entry::long(278, tiff_height), //RowsPerStrip
+        Entry::long(279, strip_size), //StripByteCounts
+        Entry::shorts(284, &[1]), //PlanarConfiguration: chunky
+        Entry::ascii(305, "Mandelbrot by Jort (https://github.com/jortrr/mandelbrot)"), //Software
+        Entry::shorts(339, &[T::SAMPLE_FORMAT; 3]), //SampleFormat
+    ];
+    //Values that do not fit in an entry are stored after the IFD at word aligned offsets, followed by the strip
+    let values_offset = HEADER_SIZE + 2 + entries.len() * ENTRY_SIZE + 4;
+    let values_size: usize = entries.iter().filter(|entry| entry.value.len() > 4).map(|entry| entry.value.len().next_multiple_of(2)).sum();
+    let strip_offset = u32::try_from(values_offset + values_size).map_err(|_| too_large())?;
+    strip_offset.checked_add(strip_size).ok_or_else(too_large)?;
+    if let Some(entry) = entries.iter_mut().find(|entry| entry.tag == 273) {
+        entry.value = strip_offset.to_le_bytes().to_vec();
+    }
+    let mut head = Vec::new();
+    head.extend_from_slice(b"II");
+    head.extend_from_slice(&42_u16.to_le_bytes());
+    head.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    head.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    let mut values = Vec::new();
+    for entry in &entries {
+        head.extend_from_slice(&entry.tag.to_le_bytes());
+        head.extend_from_slice(&entry.kind.to_le_bytes());
+        head.extend_from_slice(&entry.count.to_le_bytes());
+        if entry.value.len() > 4 {
+            head.extend_from_slice(&((values_offset + values.len()) as u32).to_le_bytes());
+            values.extend_from_slice(&entry.value);
+            values.resize(values.len().next_multiple_of(2), 0);
+        } else {
+            //Values that fit are stored left-justified in the entry
+            let mut value = [0; 4];
+            value[..entry.value.len()].copy_from_slice(&entry.value);
+            head.extend_from_slice(&value);
+        }
+    }
+    head.extend_from_slice(&0_u32.to_le_bytes()); //No next IFD
+    w.write_all(&head).and_then(|()| w.write_all(&values)).map_err(|err| err.to_string())?;
+    let mut bytes = Vec::new();
+    for row in rgb.chunks(width * 3) {
+        bytes.clear();
+        for sample in row {
+            sample.push_le_bytes(&mut bytes);
+        }
+        w.write_all(&bytes).map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+
+    use super::*;
+
+    fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(bytes[offset..offset + 2].try_into().unwrap())
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    ///Returns the bytes of the values of the IFD entry with `tag`, and its field kind and amount of values
+    fn entry(tiff: &[u8], tag: u16) -> (u16, u32, &[u8]) {
+        let ifd = u32_at(tiff, 4) as usize;
+        let entries = usize::from(u16_at(tiff, ifd));
+        let offset = (0..entries).map(|i| ifd + 2 + i * ENTRY_SIZE).find(|offset| u16_at(tiff, *offset) == tag).unwrap();
+        let (kind, count) = (u16_at(tiff, offset + 2), u32_at(tiff, offset + 4));
+        let size = count as usize * if kind == ASCII {1} else if kind == SHORT {2} else {4};
+        let value_offset = if size > 4 {u32_at(tiff, offset + 8) as usize} else {offset + 8};
+        (kind, count, &tiff[value_offset..value_offset + size])
+    }
+
+    fn metadata() -> Metadata {
+        Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1)
+    }
+
+    #[test]
+    fn writes_an_ifd_pointing_at_the_values_and_the_strip() {
+        let rgb: Vec<u8> = (0..18).collect();
+        let mut tiff = Vec::new();
+        write_tiff(&mut tiff, 3, 2, &rgb, &metadata()).unwrap();
+        assert_eq!(&tiff[..4], b"II\x2a\x00");
+        assert_eq!(u32_at(&tiff, 4), 8);
+        let entries = usize::from(u16_at(&tiff, 8));
+        assert_eq!(entries, 13);
+        //The tags are sorted in ascending order, and there is no next IFD
+        let tags: Vec<u16> = (0..entries).map(|i| u16_at(&tiff, 10 + i * ENTRY_SIZE)).collect();
+        assert!(tags.is_sorted());
+        assert_eq!(u32_at(&tiff, 10 + entries * ENTRY_SIZE), 0);
+
+        assert_eq!(entry(&tiff, 256), (LONG, 1, 3_u32.to_le_bytes().as_slice()));
+        assert_eq!(entry(&tiff, 257), (LONG, 1, 2_u32.to_le_bytes().as_slice()));
+        assert_eq!(entry(&tiff, 258), (SHORT, 3, [8, 0, 8, 0, 8, 0].as_slice()));
+        assert_eq!(entry(&tiff, 277), (SHORT, 1, [3, 0].as_slice()));
+        assert_eq!(entry(&tiff, 339), (SHORT, 3, [1, 0, 1, 0, 1, 0].as_slice()));
+        let description = format!("{}\0", metadata().to_json());
+        assert_eq!(entry(&tiff, 270), (ASCII, description.len() as u32, description.as_bytes()));
+        //Values stored after the IFD start at word aligned offsets
+        assert_eq!(u32_at(&tiff, 10 + 5 * ENTRY_SIZE + 8) % 2, 0);
+
+        let strip_offset = u32::from_le_bytes(entry(&tiff, 273).2.try_into().unwrap()) as usize;
+        assert_eq!(entry(&tiff, 279).2, 18_u32.to_le_bytes());
+        assert_eq!(strip_offset % 2, 0);
+        assert_eq!(&tiff[strip_offset..], rgb.as_slice());
+    }
+
+    #[test]
+    fn writes_float_samples_as_ieee_floating_point() {
+        let rgb = [0.0, 0.5, 1.0, 0.25, 2.0, -1.0_f32];
+        let mut tiff = Vec::new();
+        write_tiff(&mut tiff, 2, 1, &rgb, &metadata()).unwrap();
+        assert_eq!(entry(&tiff, 258), (SHORT, 3, [32, 0, 32, 0, 32, 0].as_slice()));
+        assert_eq!(entry(&tiff, 339), (SHORT, 3, [3, 0, 3, 0, 3, 0].as_slice()));
+        assert_eq!(entry(&tiff, 279).2, 24_u32.to_le_bytes());
+        let strip_offset = u32::from_le_bytes(entry(&tiff, 273).2.try_into().unwrap()) as usize;
+        let samples: Vec<f32> = tiff[strip_offset..].chunks_exact(4).map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap())).collect();
+        assert_eq!(samples, rgb);
+    }
+}
@@ -1,36 +1,65 @@
 use std::fmt;
 
-use minifb::Key;
+use crate::{AppState, error::Error};
+
+use self::{key_chord::{KeyChord, KeyStroke}, key_map::KeyMap};
+
+pub mod key_chord;
+pub mod key_map;
+
+///The behavior of a `KeyAction`, run on the application state, which returns an Error if it failed
+pub type Action = Box<dyn Fn(&mut AppState) -> Result<(), Error>>;
 
 //https://stackoverflow.com/questions/68066875/how-to-store-a-closure-inside-rust-struct
 //https://stackoverflow.com/questions/65756096/how-can-i-store-a-closure-object-in-a-struct
 pub struct KeyAction {
-    pub key: Key,
+    ///The key, key with modifiers, or chord of two keys that runs the action
+    pub keys: KeyChord,
+    ///Name of the `KeyAction` used to remap it in a key map file, e.g. `move_up`
+    pub name: &'static str,
+    ///Category the `KeyAction` is grouped under in the help screen, e.g. "Navigation"
+    pub category: &'static str,
     pub description: &'static str,
-    action: Box<dyn Fn()>,
+    ///Whether the view should be rendered after running the action
+    pub rerender: bool,
+    action: Action,
 }
 
 impl KeyAction {
-    pub fn new(key: Key, description: &'static str, action: Box<dyn Fn()>) -> KeyAction {
+    pub fn new(keys: KeyChord, name: &'static str, category: &'static str, description: &'static str, rerender: bool, action: Action) -> KeyAction {
         KeyAction {
-            key,
+            keys,
+            name,
+            category,
             description,
+            rerender,
             action,
         }
     }
 
-    ///Run self.action
-    pub fn action(&self) {
-        (self.action)();
+    ///Run self.action on the application `state`
+    /// # Errors
+    /// Returns the Error of the action if it failed, e.g. because the view could not be saved
+    pub fn action(&self, state: &mut AppState) -> Result<(), Error> {
+        (self.action)(state)
     }
 }
 
 impl fmt::Debug for KeyAction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-            write!(f,"{:?} -> {}", &self.key, &self.description)
+            write!(f,"{:?} -> {}", self.keys, self.description)
     }
 }
 
+///The result of looking up a `KeyStroke` in the `KeyBindings`, see `KeyBindings::lookup`
+pub enum KeyLookup<'a> {
+    ///The `KeyStroke`s are bound to this `KeyAction`
+    Action(&'a KeyAction),
+    ///The `KeyStroke` is the first `KeyStroke` of a chord, the next `KeyStroke` completes it
+    ChordPrefix,
+    Unbound,
+}
+
 pub struct KeyBindings {
     key_actions: Vec<KeyAction>,
 }
@@ -40,37 +69,116 @@ impl KeyBindings {
         KeyBindings { key_actions }
     }
 
-    ///Adds a `KeyAction` to these `KeyBindings`, will remove any existing `KeyAction` `x` where `x.key` == `key_action.key`
+    ///Adds a `KeyAction` to these `KeyBindings`. An existing `KeyAction` `x` with the same name, or whose keys conflict with the keys of `key_action`
+    ///(see `KeyChord::conflicts_with`), is overridden: it is removed and a warning is printed to stderr
     pub fn add_key(&mut self, key_action: KeyAction) {
-        //Remove any KeyAction x where x.key == key_action.key
-        self.key_actions.retain(|x| x.key != key_action.key);
-
+        self.key_actions.retain(|x| {
+            let overridden = x.name == key_action.name || x.keys.conflicts_with(&key_action.keys);
+            if overridden {
+                eprintln!("Warning: {:?} ({}) overrides the key binding {:?} ({})", key_action.keys, key_action.name, x.keys, x.name);
+            }
+            !overridden
+        });
         self.key_actions.push(key_action);
     }
 
-    ///Adds a `KeyAction` to these `KeyBindings`, will remove any existing `KeyAction` `x` where `x.key` == `key`
-    pub fn add<F: Fn() + 'static>(&mut self, key: Key, description: &'static str, action: F) {
-        self.add_key(KeyAction::new(key, description, Box::new(action)));
+    ///Adds a `KeyAction` named `name` to these `KeyBindings`, bound to `keys`, see `add_key`
+    pub fn add<F: Fn(&mut AppState) + 'static>(&mut self, keys: impl Into<KeyChord>, name: &'static str, category: &'static str, description: &'static str, action: F) {
+        self.add_key(KeyAction::new(keys.into(), name, category, description, false, Box::new(move |state| {
+            action(state);
+            Ok(())
+        })));
+    }
+
+    ///Adds a `KeyAction` whose `action` can fail to these `KeyBindings`, see `add`. The Error of a failed `action` is shown by the viewer, which keeps running
+    pub fn add_fallible<F: Fn(&mut AppState) -> Result<(), Error> + 'static>(&mut self, keys: impl Into<KeyChord>, name: &'static str, category: &'static str, description: &'static str, action: F) {
+        self.add_key(KeyAction::new(keys.into(), name, category, description, false, Box::new(action)));
+    }
+
+    ///Adds a `KeyAction` that rerenders the view after running its `action` to these `KeyBindings`, see `add`
+    pub fn add_rendering<F: Fn(&mut AppState) + 'static>(&mut self, keys: impl Into<KeyChord>, name: &'static str, category: &'static str, description: &'static str, action: F) {
+        self.add_key(KeyAction::new(keys.into(), name, category, description, true, Box::new(move |state| {
+            action(state);
+            Ok(())
+        })));
+    }
+
+    ///Returns the `KeyAction` bound to `keys`, if any
+    pub fn get(&self, keys: &KeyChord) -> Option<&KeyAction> {
+        self.key_actions.iter().find(|key_action| key_action.keys == *keys)
+    }
+
+    ///Looks up `key_stroke`, pressed after the first `KeyStroke` of a chord if `pending` is Some
+    pub fn lookup(&self, pending: Option<KeyStroke>, key_stroke: KeyStroke) -> KeyLookup<'_> {
+        let keys = KeyChord::new(pending.unwrap_or(key_stroke), pending.map(|_| key_stroke));
+        match self.get(&keys) {
+            Some(key_action) => KeyLookup::Action(key_action),
+            None if pending.is_none() && self.key_actions.iter().any(|key_action| key_action.keys.key_strokes().first() == Some(&key_stroke)) => KeyLookup::ChordPrefix,
+            None => KeyLookup::Unbound,
+        }
     }
 
     pub fn key_actions(&self) -> &Vec<KeyAction> {
         &self.key_actions
     }
 
+    ///Remaps the `KeyAction`s named in `key_map` to their new keys. Default key bindings that conflict with a remapped `KeyAction` are overridden, see `add_key`.
+    ///
+    ///Returns the problems found in `key_map`: unknown action names, action names that were already remapped earlier in the file,
+    ///and remappings that conflict with an earlier remapping in the file, which are skipped
+    pub fn apply_key_map(&mut self, key_map: &KeyMap) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut remapped: Vec<KeyAction> = Vec::new();
+        let mut names: Vec<&str> = Vec::new();
+        for (name, keys) in &key_map.remappings {
+            if names.contains(&name.as_str()) {
+                problems.push(format!("{} is remapped more than once, skipping {:?}", name, keys));
+                continue;
+            }
+            names.push(name);
+            if let Some(earlier) = remapped.iter().find(|x| x.keys.conflicts_with(keys)) {
+                problems.push(format!("{:?} for {} conflicts with {:?} for {}, skipping it", keys, name, earlier.keys, earlier.name));
+                continue;
+            }
+            match self.key_actions.iter().position(|x| x.name == name) {
+                Some(index) => {
+                    let mut key_action = self.key_actions.remove(index);
+                    key_action.keys = keys.clone();
+                    remapped.push(key_action);
+                }
+                None => problems.push(format!("Unknown action: {}", name)),
+            }
+        }
+        //Take all remapped KeyActions out first, so that two KeyActions can swap their keys
+        for key_action in remapped {
+            self.add_key(key_action);
+        }
+        problems
+    }
+
+    ///Returns the `KeyAction`s grouped by category, the categories and the `KeyAction`s within a category are in the order they were added
+    pub fn categories(&self) -> Vec<(&'static str, Vec<&KeyAction>)> {
+        let mut categories: Vec<(&'static str, Vec<&KeyAction>)> = Vec::new();
+        for key_action in &self.key_actions {
+            match categories.iter_mut().find(|(category, _)| *category == key_action.category) {
+                Some((_, key_actions)) => key_actions.push(key_action),
+                None => categories.push((key_action.category, vec![key_action])),
+            }
+        }
+        categories
+    }
+
     /// Prints all `KeyAction`s in these `KeyBindings` to stdout
     pub fn print(&self) {
         println!("{:?}",self);
     }
 
-    pub fn print_key(&self, key: &Key) {
-        for key_action in &self.key_actions {
-            if key_action.key == *key {
-                println!("{:?}", key_action);
-                return;
-            }
+    pub fn print_key(&self, keys: &KeyChord) {
+        match self.get(keys) {
+            Some(key_action) => println!("{:?}", key_action),
+            //KeyBindings does not contain a KeyAction x with x.keys == keys, unbound
+            None => println!("{:?}", keys),
         }
-        //KeyBindings does not contain a KeyAction x with x.key == key, unbound
-        println!("{:?}", key);
     }
 }
 
@@ -88,8 +196,53 @@ impl Default for KeyBindings {
     /// Define all your keybindings here
     fn default() -> KeyBindings {
         let mut key_bindings = KeyBindings::new(Vec::new());
-        key_bindings.add(Key::A, "This is the A key", ||println!("Action A"));
-        key_bindings.add(Key::B, "This is the B key", ||println!("Action B"));
+        key_bindings.add(minifb::Key::A, "example_a", "Example", "This is the A key", |_|println!("Action A"));
+        key_bindings.add(minifb::Key::B, "example_b", "Example", "This is the B key", |_|println!("Action B"));
+        key_bindings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use minifb::Key;
+
+    use super::*;
+
+    fn key_bindings() -> KeyBindings {
+        let mut key_bindings = KeyBindings::new(Vec::new());
+        key_bindings.add(Key::Up, "move_up", "Navigation", "Move up", |_| ());
+        key_bindings.add(Key::Down, "move_down", "Navigation", "Move down", |_| ());
+        key_bindings.add(Key::D, "zoom_in", "Navigation", "Zoom in", |_| ());
         key_bindings
     }
+
+    fn keys_of(key_bindings: &KeyBindings, name: &str) -> String {
+        key_bindings.key_actions().iter().find(|key_action| key_action.name == name).unwrap().keys.to_string()
+    }
+
+    #[test]
+    fn remappings_can_swap_keys() {
+        let mut key_bindings = key_bindings();
+        let problems = key_bindings.apply_key_map(&KeyMap::from_json(r#"{"move_up": "Down", "move_down": "Up"}"#).unwrap());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert_eq!((keys_of(&key_bindings, "move_up"), keys_of(&key_bindings, "move_down")), (String::from("Down"), String::from("Up")));
+    }
+
+    #[test]
+    fn skips_remappings_that_conflict_with_an_earlier_remapping_in_the_file() {
+        let mut key_bindings = key_bindings();
+        //move_up sorts before zoom_in, but comes later in the file
+        let problems = key_bindings.apply_key_map(&KeyMap::from_json(r#"{"zoom_in": "W", "move_up": "W"}"#).unwrap());
+        assert_eq!(problems, ["W for move_up conflicts with W for zoom_in, skipping it"]);
+        assert_eq!((keys_of(&key_bindings, "zoom_in"), keys_of(&key_bindings, "move_up")), (String::from("W"), String::from("Up")));
+    }
+
+    #[test]
+    fn reports_duplicate_and_unknown_action_names() {
+        let mut key_bindings = key_bindings();
+        let problems = key_bindings.apply_key_map(&KeyMap::from_json(r#"{"move_up": "W", "fly": "F", "move_up": "I"}"#).unwrap());
+        assert_eq!(problems, ["Unknown action: fly", "move_up is remapped more than once, skipping I"]);
+        assert_eq!(keys_of(&key_bindings, "move_up"), "W");
+        assert_eq!(key_bindings.key_actions().len(), 3);
+    }
 }
@@ -0,0 +1,194 @@
+use std::{fmt, str::FromStr};
+
+use minifb::{Key, Window};
+
+///Every `Key` that can be bound, used to parse key names. Modifier keys are not included, they are part of a `KeyStroke`
+static KEYS: [Key; 98] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12, Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up,
+    Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma, Key::Equal, Key::LeftBracket, Key::Minus, Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::Menu, Key::PageDown, Key::PageUp, Key::Pause, Key::Space, Key::Tab,
+    Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6, Key::NumPad7, Key::NumPad8, Key::NumPad9,
+    Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk, Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter,
+];
+
+///Returns whether `key` is a Ctrl, Shift, Alt or Super key
+pub fn is_modifier(key: Key) -> bool {
+    matches!(key, Key::LeftCtrl | Key::RightCtrl | Key::LeftShift | Key::RightShift | Key::LeftAlt | Key::RightAlt | Key::LeftSuper | Key::RightSuper)
+}
+
+///Parses the name of a `Key`, the name of its variant, case-insensitive, e.g. `A`, `Key1`, `LeftBracket` or `NumPadPlus`
+/// # Errors
+/// Returns an Error if `name` is not the name of a `Key` that can be bound
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    KEYS.iter().find(|key| format!("{:?}", key).eq_ignore_ascii_case(name)).copied().ok_or_else(|| format!("Unknown key: {}", name))
+}
+
+///A `Key` pressed while holding down a combination of the Ctrl, Shift and Alt modifiers, e.g. Ctrl+Shift+S
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KeyStroke {
+    pub key: Key,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyStroke {
+    pub const fn new(key: Key, ctrl: bool, shift: bool, alt: bool) -> KeyStroke {
+        KeyStroke { key, ctrl, shift, alt }
+    }
+
+    ///Returns the `KeyStroke` of `key` together with the modifiers currently held down in the `window`
+    pub fn from_window(key: Key, window: &Window) -> KeyStroke {
+        let down = |left: Key, right: Key| window.is_key_down(left) || window.is_key_down(right);
+        KeyStroke::new(key, down(Key::LeftCtrl, Key::RightCtrl), down(Key::LeftShift, Key::RightShift), down(Key::LeftAlt, Key::RightAlt))
+    }
+}
+
+impl From<Key> for KeyStroke {
+    fn from(key: Key) -> KeyStroke {
+        KeyStroke::new(key, false, false, false)
+    }
+}
+
+impl FromStr for KeyStroke {
+    type Err = String;
+
+    ///Parses a `KeyStroke` in the format `[Ctrl+][Shift+][Alt+]<key>`, e.g. "Ctrl+S", the modifiers are case-insensitive and in any order
+    fn from_str(s: &str) -> Result<KeyStroke, String> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let key = parse_key(parts.pop().unwrap_or_default())?;
+        let mut key_stroke = KeyStroke::from(key);
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => key_stroke.ctrl = true,
+                "shift" => key_stroke.shift = true,
+                "alt" => key_stroke.alt = true,
+                _ => return Err(format!("Unknown modifier: {} in {}", modifier, s)),
+            }
+        }
+        Ok(key_stroke)
+    }
+}
+
+impl fmt::Display for KeyStroke {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+///The `KeyStroke`s that run a `KeyAction`: a single `KeyStroke`, or a chord of two `KeyStroke`s pressed one after the other, e.g. "Ctrl+K S"
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeyChord {
+    key_strokes: Vec<KeyStroke>,
+}
+
+impl KeyChord {
+    pub fn new(first: KeyStroke, second: Option<KeyStroke>) -> KeyChord {
+        KeyChord { key_strokes: std::iter::once(first).chain(second).collect() }
+    }
+
+    pub fn key_strokes(&self) -> &[KeyStroke] {
+        &self.key_strokes
+    }
+
+    ///Returns whether this `KeyChord` and `other` cannot both be bound: they are equal, or one is the first `KeyStroke` of the other
+    pub fn conflicts_with(&self, other: &KeyChord) -> bool {
+        self.key_strokes.starts_with(&other.key_strokes) || other.key_strokes.starts_with(&self.key_strokes)
+    }
+}
+
+impl From<Key> for KeyChord {
+    fn from(key: Key) -> KeyChord {
+        KeyChord::new(KeyStroke::from(key), None)
+    }
+}
+
+impl From<KeyStroke> for KeyChord {
+    fn from(key_stroke: KeyStroke) -> KeyChord {
+        KeyChord::new(key_stroke, None)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    ///Parses a `KeyChord` of one or two whitespace separated `KeyStroke`s, e.g. "Ctrl+S" or "Ctrl+K S"
+    fn from_str(s: &str) -> Result<KeyChord, String> {
+        let key_strokes = s.split_whitespace().map(str::parse).collect::<Result<Vec<KeyStroke>, String>>()?;
+        match key_strokes.as_slice() {
+            [first] => Ok(KeyChord::new(*first, None)),
+            [first, second] => Ok(KeyChord::new(*first, Some(*second))),
+            _ => Err(format!("A key binding should consist of one or two keys: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key_strokes: Vec<String> = self.key_strokes.iter().map(ToString::to_string).collect();
+        write!(f, "{}", key_strokes.join(" "))
+    }
+}
+
+impl fmt::Debug for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys_case_insensitively() {
+        assert_eq!(parse_key("a"), Ok(Key::A));
+        assert_eq!(parse_key("numpadplus"), Ok(Key::NumPadPlus));
+        assert_eq!(parse_key("Key1"), Ok(Key::Key1));
+        assert_eq!(parse_key("LeftCtrl").unwrap_err(), "Unknown key: LeftCtrl");
+        assert!(parse_key("").is_err());
+    }
+
+    #[test]
+    fn parses_key_strokes_with_modifiers_in_any_order() {
+        let key_stroke: KeyStroke = "shift+Control+S".parse().unwrap();
+        assert!(key_stroke == KeyStroke::new(Key::S, true, true, false));
+        assert_eq!(key_stroke.to_string(), "Ctrl+Shift+S");
+        assert!("Alt + F4".parse::<KeyStroke>() == Ok(KeyStroke::new(Key::F4, false, false, true)));
+        assert_eq!("Hyper+S".parse::<KeyStroke>().err().unwrap(), "Unknown modifier: Hyper in Hyper+S");
+        assert!("Ctrl+".parse::<KeyStroke>().is_err());
+    }
+
+    #[test]
+    fn parses_key_chords_of_one_or_two_key_strokes() {
+        let chord: KeyChord = "Ctrl+K  r".parse().unwrap();
+        assert!(chord.key_strokes() == [KeyStroke::new(Key::K, true, false, false), KeyStroke::from(Key::R)]);
+        assert_eq!(chord.to_string(), "Ctrl+K R");
+        assert_eq!("W".parse::<KeyChord>(), Ok(KeyChord::from(Key::W)));
+        assert!("".parse::<KeyChord>().is_err());
+        assert_eq!("A B C".parse::<KeyChord>().unwrap_err(), "A key binding should consist of one or two keys: A B C");
+    }
+
+    #[test]
+    fn chords_conflict_with_their_first_key_stroke() {
+        let chord: KeyChord = "Ctrl+K S".parse().unwrap();
+        assert!(chord.conflicts_with(&"Ctrl+K".parse().unwrap()));
+        assert!(chord.conflicts_with(&chord));
+        assert!(!chord.conflicts_with(&"Ctrl+K R".parse().unwrap()));
+        assert!(!chord.conflicts_with(&"K".parse().unwrap()));
+    }
+}
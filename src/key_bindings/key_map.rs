@@ -0,0 +1,82 @@
+use std::{fmt, fs, path::Path};
+
+use serde::{Deserialize, Deserializer, de::{MapAccess, Visitor}};
+
+use crate::error::Error;
+
+use super::key_chord::KeyChord;
+
+///Remapping of `KeyAction`s to other keys, read from a JSON key map file that maps action names to key chords, e.g.
+///`{"move_up": "W", "save_image": "Ctrl+S", "reset": "Ctrl+K R"}`
+pub struct KeyMap {
+    ///The remapped action names and their new `KeyChord`s, in the order of the file, including action names that are remapped more than once
+    pub remappings: Vec<(String, KeyChord)>,
+}
+
+impl KeyMap {
+    ///Reads a `KeyMap` from the JSON key map file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be read, is not a JSON object of strings, or contains a key chord that cannot be parsed
+    pub fn read_from_file(path: &str) -> Result<KeyMap, Error> {
+        let json = fs::read_to_string(path).map_err(|err| Error::io(Path::new(path), err))?;
+        KeyMap::from_json(&json).map_err(|err| Error::Config(format!("Invalid key map file: {}: {}", err, path)))
+    }
+
+    ///Parses a `KeyMap` from a JSON object that maps action names to key chords
+    /// # Errors
+    /// Returns an Error if `json` is not a JSON object of strings, or contains a key chord that cannot be parsed
+    pub fn from_json(json: &str) -> Result<KeyMap, String> {
+        let Entries(entries) = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let remappings = entries.into_iter().map(|(name, key_chord)| {
+            let key_chord: KeyChord = key_chord.parse().map_err(|err| format!("{} for {}", err, name))?;
+            Ok((name, key_chord))
+        }).collect::<Result<Vec<(String, KeyChord)>, String>>()?;
+        Ok(KeyMap { remappings })
+    }
+}
+
+///The entries of a JSON object of strings in the order of the file, a map would sort them and only keep the last entry of a duplicate name
+struct Entries(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for Entries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Entries, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Entries;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a JSON object mapping action names to key chords")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Entries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_key_maps_in_the_order_of_the_file() {
+        let key_map = KeyMap::from_json(r#"{"save_image": "Ctrl+S", "move_up": "W", "reset": "Ctrl+K R", "move_up": "Up"}"#).unwrap();
+        let remappings: Vec<(&str, String)> = key_map.remappings.iter().map(|(name, key_chord)| (name.as_str(), key_chord.to_string())).collect();
+        assert_eq!(remappings, [("save_image", String::from("Ctrl+S")), ("move_up", String::from("W")), ("reset", String::from("Ctrl+K R")), ("move_up", String::from("Up"))]);
+    }
+
+    #[test]
+    fn rejects_invalid_key_maps() {
+        assert_eq!(KeyMap::from_json(r#"{"move_up": "Ctrl+Nope"}"#).err().unwrap(), "Unknown key: Nope for move_up");
+        assert!(KeyMap::from_json(r#"{"move_up": 1}"#).is_err());
+        assert!(KeyMap::from_json(r#"["W"]"#).err().unwrap().starts_with("invalid type: sequence, expected a JSON object mapping action names to key chords"));
+    }
+}
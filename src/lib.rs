@@ -26,20 +26,30 @@
     clippy::cast_sign_loss
 )]
 
-use std::error::Error;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use coloring::ColorChannelMapping;
+use coloring::{ColorChannelMapping, ColoringAlgorithm, ColoringFunction};
 pub use config::Config;
+pub use error::Error;
 use mandelbrot_set::MandelbrotSet;
-use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+use serde::{Deserialize, Serialize};
+use minifb::{Key, MouseButton, MouseMode, ScaleMode, Window, WindowOptions};
 
-use crate::coloring::TrueColor;
+use crate::animation::keyframes::{self, KEYFRAMES_FILE_NAME};
+use crate::command::{CommandContext, Script};
 use crate::complex_plane::{ComplexPlane, View};
-use crate::key_bindings::KeyBindings;
+use crate::help_screen::HelpScreen;
+use crate::history::{History, HISTORY_FILE_NAME};
+use crate::hud::Hud;
+use crate::key_bindings::{KeyBindings, KeyLookup};
+use crate::key_bindings::key_chord::{self, KeyChord, KeyStroke};
+use crate::key_bindings::key_map::KeyMap;
 use crate::pixel_buffer::PixelBuffer;
+use crate::session::{Session, SESSION_FILE_NAME};
+use crate::pixel_buffer::overlay::Overlay;
 use crate::pixel_buffer::pixel_plane::PixelPlane;
-use crate::user_input::{ask, pick_option};
+use crate::user_input::dialog::{Dialog, DialogState, TypedCharacters};
 
 pub mod complex_plane;
 pub mod complex;
@@ -50,10 +60,28 @@ pub mod key_bindings;
 pub mod coloring;
 pub mod user_input;
 pub mod config;
-
-//Coloring function
-type ColoringFunction = fn(iterations: u32, max_iterations: u32) -> TrueColor;
-static COLORING_FUNCTION : ColoringFunction = TrueColor::new_from_bernstein_polynomials;
+pub mod metadata;
+pub mod animation;
+pub mod hud;
+pub mod help_screen;
+pub mod history;
+pub mod session;
+pub mod command;
+pub mod tile_server;
+pub mod poster;
+pub mod pyramid;
+pub mod checkpoint;
+pub mod distributed;
+pub mod error;
+pub mod escape_data;
+pub mod float_image;
+pub mod image_format;
+pub mod output;
+#[cfg(test)]
+mod test_utils;
+
+//Coloring algorithm
+static COLORING_ALGORITHM: ColoringAlgorithm = ColoringAlgorithm::BernsteinPolynomials;
 
 //Color channel mapping
 static COLOR_CHANNEL_MAPPING: ColorChannelMapping = ColorChannelMapping::RGB;
@@ -70,20 +98,29 @@ static VIEW_8: View = View::new(-1.7862581627050718, 0.00005198056959995248, 0.0
 static VIEW_9: View = View::new( -0.4687339999999999, 0.5425518958333333, 0.000010000000000000003);
 static VIEW_0: View = View::new( -0.437520465811966, 0.5632133750000006, 0.000004000000000000004);
 
+//Title of the window
+static WINDOW_TITLE: &str = "Mandelbrot set viewer";
+
+//Color of the rubber band drawn while selecting a box to zoom into
+static RUBBER_BAND_COLOR: u32 = 0x00FF_FFFF;
+
 //Banner values
 static VERSION: &str = "1.4";
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InteractionVariables{
     ///Variable determining the amount of rows and columns are translated by pressing the 4 arrow keys
     pub translation_amount: u8,
     ///Variable denoting the user scaling speed; the lower this value, the more aggressive the zooming will become
     pub scale_denominator: f64,
     pub scale_numerator: f64,
+    ///Variable determining the amount of degrees the view is rotated by pressing the rotation keys
+    pub rotation_step: f64,
 }
 
 impl InteractionVariables{
-    pub fn new(translation_amount: u8, scale_numerator: f64, scale_denominator: f64) -> InteractionVariables {
-        InteractionVariables { translation_amount, scale_denominator, scale_numerator }
+    pub fn new(translation_amount: u8, scale_numerator: f64, scale_denominator: f64, rotation_step: f64) -> InteractionVariables {
+        InteractionVariables { translation_amount, scale_denominator, scale_numerator, rotation_step }
     }
 
     pub fn scaling_factor(&self) -> f64 {
@@ -119,71 +156,401 @@ impl InteractionVariables{
 
 impl Default for InteractionVariables{
     fn default() -> Self {
-        InteractionVariables { translation_amount:10, scale_numerator: 9.0, scale_denominator: 10.0 }
+        InteractionVariables { translation_amount:10, scale_numerator: 9.0, scale_denominator: 10.0, rotation_step: 15.0 }
+    }
+}
+
+///The state of the interactive Mandelbrot set viewer, given to the action of every `KeyAction`
+pub struct AppState {
+    pub window: Window,
+    ///Whether the window is fullscreen, see `create_window`
+    pub fullscreen: bool,
+    pub c: ComplexPlane,
+    pub p: PixelBuffer,
+    pub m: MandelbrotSet,
+    pub vars: InteractionVariables,
+    ///SSAA multiplier used to render the window
+    pub supersampling_amount: u8,
+    ///SSAA multiplier used to render saved images
+    pub image_supersampling_amount: u8,
+    pub coloring_algorithm: ColoringAlgorithm,
+    pub config: Config,
+    pub hud: Hud,
+    pub help_screen: HelpScreen,
+    ///Shared with the main loop, so that actions can list the key bindings
+    pub key_bindings: Rc<KeyBindings>,
+    ///The first `KeyStroke` of a chord that was pressed, waiting for the `KeyStroke` that completes it
+    pub pending_key_stroke: Option<KeyStroke>,
+    ///Navigation history of the visited views, used to undo and redo navigation
+    pub history: History,
+    ///In-window dialog asking the user for input, key bindings are not handled while it is open
+    pub dialog: Option<Dialog>,
+    ///Characters typed into the window, used as the input of the `dialog`
+    pub typed_characters: TypedCharacters,
+}
+
+impl AppState {
+    ///Renders the whole view into the `PixelBuffer`
+    /// # Errors
+    /// Returns an Error if a rendering thread panicked
+    pub fn render(&mut self) -> Result<(), Error> {
+        rendering::render_complex_plane_into_buffer(&mut self.p, &self.c, &self.m, self.supersampling_amount, self.coloring_algorithm.coloring_function())
+    }
+
+    ///Returns the `CommandContext` to run commands on the view, the `PixelBuffer` holds the rendered view
+    pub fn command_context(&mut self) -> CommandContext<'_> {
+        CommandContext {
+            c: &mut self.c,
+            p: &mut self.p,
+            m: &mut self.m,
+            supersampling_amount: &mut self.supersampling_amount,
+            image_supersampling_amount: &mut self.image_supersampling_amount,
+            coloring_algorithm: &mut self.coloring_algorithm,
+            image_width: self.config.image_width,
+            image_height: self.config.image_height,
+            rendered: true,
+            checkpoint: false,
+            workers: self.config.workers.clone(),
+            image_format: self.config.image_format,
+            output: self.config.output.clone(),
+        }
+    }
+
+    pub fn coloring_function(&self) -> ColoringFunction {
+        self.coloring_algorithm.coloring_function()
     }
 }
 
-// Handle any key events
-fn handle_key_events(window: &Window, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &mut MandelbrotSet, vars: &mut InteractionVariables, k: &KeyBindings, supersampling_amount: &mut u8, image_supersampling_amount: &mut u8,coloring_function: &mut ColoringFunction, config: &Config) {
-    if let Some(key) = window.get_keys_pressed(minifb::KeyRepeat::No).first() {
+// Handle any key events, by running the action of the pressed keys and rerendering the view if the `KeyAction` requires it.
+// Modifier keys are only read together with another key, the first key of a chord is remembered until the next key is pressed
+fn handle_key_events(state: &mut AppState) -> Result<(), Error> {
+    let characters = state.typed_characters.take();
+    if let Some(dialog) = state.dialog.take() {
+        return handle_dialog(state, dialog, &characters);
+    }
+    let pressed_key = state.window.get_keys_pressed(minifb::KeyRepeat::No).into_iter().find(|key| !key_chord::is_modifier(*key));
+    if let Some(key) = pressed_key {
+        //Any key dismisses the error line of the HUD
+        state.hud.dismiss_error();
+        let key_stroke = KeyStroke::from_window(key, &state.window);
+        let pending = state.pending_key_stroke.take();
+        let key_bindings = Rc::clone(&state.key_bindings);
         print!("\nKey pressed: ");
-        k.print_key(key);
-        match key {
-            Key::Up => rendering::translate_and_render_efficiently(c, p, m, vars.translation_amount.into(), 0, *supersampling_amount, *coloring_function),
-            Key::Down => rendering::translate_and_render_efficiently(c, p, m, -i16::from(vars.translation_amount), 0, *supersampling_amount, *coloring_function),
-            Key::Left => rendering::translate_and_render_efficiently(c, p, m, 0, -i16::from(vars.translation_amount), *supersampling_amount, *coloring_function),
-            Key::Right => rendering::translate_and_render_efficiently(c, p, m, 0, vars.translation_amount.into(), *supersampling_amount, *coloring_function),
-            Key::R => c.reset(),
-            Key::NumPadPlus => vars.increment_translation_amount(),
-            Key::NumPadMinus => vars.decrement_translation_amount(),
-            Key::NumPadAsterisk => vars.increment_scale_numerator(),
-            Key::NumPadSlash => vars.decrement_scale_numerator(),
-            Key::LeftBracket => c.scale(vars.scaling_factor()),
-            Key::RightBracket => c.scale(vars.inverse_scaling_factor()),
-            Key::V => println!("Center: {:?}, scale: {:?}", c.center(), c.get_scale()),
-            Key::Key1 => c.set_view(&VIEW_1),
-            Key::Key2 => c.set_view(&VIEW_2),
-            Key::Key3 => c.set_view(&VIEW_3),
-            Key::Key4 => c.set_view(&VIEW_4),
-            Key::Key5 => c.set_view(&VIEW_5),
-            Key::Key6 => c.set_view(&VIEW_6),
-            Key::Key7 => c.set_view(&VIEW_7),
-            Key::Key8 => c.set_view(&VIEW_8),
-            Key::Key9 => c.set_view(&VIEW_9),
-            Key::Key0 => c.set_view(&VIEW_0),
-            Key::K => k.print(),
-            Key::S => {
-                let time_stamp = chrono::Utc::now().to_string();
-                if config.window_scale == 1.0 {
-                    p.save_as_png(&time_stamp, &c.get_view(), m, *image_supersampling_amount);
-                } else {
-                    let mut image_p: PixelBuffer = PixelBuffer::new(PixelPlane::new(config.image_width, config.image_height));
-                    let mut image_c: ComplexPlane = ComplexPlane::new(config.image_width, config.image_height);
-                    image_p.color_channel_mapping = p.color_channel_mapping;
-                    image_c.set_view(&c.get_view());
-                    rendering::render_complex_plane_into_buffer(&mut image_p, &image_c, m, *image_supersampling_amount, *coloring_function);
-                    image_p.save_as_png(&time_stamp, &c.get_view(), m, *image_supersampling_amount);
+        match key_bindings.lookup(pending, key_stroke) {
+            KeyLookup::Action(key_action) => {
+                println!("{:?}", key_action);
+                key_action.action(state)?;
+                if key_action.rerender {
+                    state.render()?;
+                    state.c.print();
                 }
             }
-            Key::I => c.set_view(&View::new(ask("x"), ask("y"), ask("scale"))),
-            Key::A => *coloring_function = pick_option(&[("HSV", TrueColor::new_from_hsv_colors), ("Bernstein polynomials", TrueColor::new_from_bernstein_polynomials)]),
-            Key::M => m.max_iterations = ask("max_iterations"),
-            Key::O => p.color_channel_mapping = ask("color_channel_mapping"),
-            Key::Q => {*supersampling_amount = ask::<u8>("supersampling_amount").clamp(1, 64); *image_supersampling_amount = *supersampling_amount;},
-            Key::X => *image_supersampling_amount = ask::<u8>("image_supersampling_amount").clamp(1, 64),
-            Key::C => println!("{:?}", config),
-            _ => (),
+            KeyLookup::ChordPrefix => {
+                println!("{} (waiting for the next key of the chord)", key_stroke);
+                state.pending_key_stroke = Some(key_stroke);
+            }
+            KeyLookup::Unbound => key_bindings.print_key(&KeyChord::new(pending.unwrap_or(key_stroke), pending.map(|_| key_stroke))),
         }
-        match key {
-            Key::NumPadPlus | Key::NumPadMinus => println!("translation_amount: {}", vars.translation_amount),
-            Key::NumPadSlash | Key::NumPadAsterisk => println!("scale factor: {}/{}",vars.scale_numerator,vars.scale_denominator),
-            Key::Up | Key::Down | Key::Left | Key::Right => c.print(),
-            Key::R | Key::Key1 | Key::Key2 | Key::Key3 | Key::Key4 | Key::Key5 | Key::Key6 | Key::Key7 | Key::Key8 | Key::Key9 | Key::Key0 | Key::LeftBracket | Key::RightBracket | Key::I | Key::A | Key::M | Key::O | Key::Q => {
-                rendering::render_complex_plane_into_buffer(p, c, m, *supersampling_amount, *coloring_function);
-                c.print();
-            },
-            _ => (),
+    }
+    Ok(())
+}
+
+///Handles the input of the open `dialog`, instead of the key bindings. Renders the view if the submitted `dialog` requires it
+fn handle_dialog(state: &mut AppState, mut dialog: Dialog, characters: &[char]) -> Result<(), Error> {
+    let keys = state.window.get_keys_pressed(minifb::KeyRepeat::Yes);
+    match dialog.handle_input(state, characters, &keys) {
+        DialogState::Open => state.dialog = Some(dialog),
+        DialogState::Submitted => {
+            if dialog.rerender {
+                state.render()?;
+                state.c.print();
+            }
+        }
+        DialogState::Cancelled => println!("Cancelled the input"),
+    }
+    Ok(())
+}
+
+///Translates the view `rows_up` rows and `columns_right` columns, only rendering the exposed rows and columns
+fn translate(s: &mut AppState, rows_up: i16, columns_right: i16) -> Result<(), Error> {
+    let coloring_function = s.coloring_function();
+    rendering::translate_and_render_efficiently(&mut s.c, &mut s.p, &s.m, rows_up, columns_right, s.supersampling_amount, coloring_function)?;
+    s.c.print();
+    Ok(())
+}
+
+///Scales the view by `factor`, showing the resampled image as an instant preview while the rest is rendered
+fn scale(s: &mut AppState, factor: f64) -> Result<(), Error> {
+    let coloring_function = s.coloring_function();
+    let window = &mut s.window;
+    rendering::scale_and_render_efficiently(&mut s.c, &mut s.p, &s.m, factor, s.supersampling_amount, coloring_function, |preview| {
+        //The preview is only shown until the view is rendered, so it can be skipped
+        if let Err(err) = window.update_with_buffer(&preview.pixels, preview.pixel_plane.width, preview.pixel_plane.height) {
+            eprintln!("Warning: could not show the preview: {}", err);
+        }
+    })?;
+    s.c.print();
+    Ok(())
+}
+
+///Saves the current view as an image in the output directory named by the file name template, rendered at the image resolution with the image SSAA multiplier
+fn save_image(s: &mut AppState) -> Result<(), Error> {
+    s.command_context().save(None)
+}
+
+///Goes `back` or forward in the navigation history, restoring the cached pixels of the view if they are still valid and rendering the view otherwise
+fn step_through_history(s: &mut AppState, back: bool) -> Result<(), Error> {
+    let AppState { c, p, m, history, coloring_algorithm, supersampling_amount, .. } = s;
+    //The coloring settings may have changed since the current view was recorded
+    history.cache(p, *coloring_algorithm, *supersampling_amount);
+    let entry = if back {history.undo()} else {history.redo()};
+    let Some(entry) = entry else {
+        println!("Nothing to {}", if back {"undo"} else {"redo"});
+        return Ok(());
+    };
+    c.set_view(&entry.view);
+    m.clone_from(&entry.mandelbrot_set);
+    let cached = entry.cached_pixel_buffer(p, *coloring_algorithm, *supersampling_amount);
+    let restored = cached.is_some();
+    if let Some(cached) = cached {
+        p.clone_from(cached);
+    }
+    let (position, length) = history.position();
+    println!("History: view {} of {}", position, length);
+    if !restored {
+        s.render()?;
+    }
+    s.c.print();
+    Ok(())
+}
+
+///Returns the `KeyBindings` of the Mandelbrot set viewer, every `KeyAction` holds the behavior of its key
+fn create_key_bindings() -> KeyBindings {
+    let mut key_bindings: KeyBindings = KeyBindings::new(Vec::new());
+    key_bindings.add_fallible(Key::Up, "move_up", "Navigation", "Move up translation_amount pixels", |s| translate(s, s.vars.translation_amount.into(), 0));
+    key_bindings.add_fallible(Key::Down, "move_down", "Navigation", "Move down translation_amount pixels", |s| translate(s, -i16::from(s.vars.translation_amount), 0));
+    key_bindings.add_fallible(Key::Left, "move_left", "Navigation", "Move left translation_amount pixels", |s| translate(s, 0, -i16::from(s.vars.translation_amount)));
+    key_bindings.add_fallible(Key::Right, "move_right", "Navigation", "Move right translation_amount pixels", |s| translate(s, 0, s.vars.translation_amount.into()));
+    key_bindings.add_rendering(Key::R, "reset", "Navigation", "Reset the Mandelbrot set view to the starting view", |s| s.c.reset());
+    key_bindings.add(Key::NumPadPlus, "increment_translation_amount", "Interaction", "Increment translation_amount", |s| {
+        s.vars.increment_translation_amount();
+        println!("translation_amount: {}", s.vars.translation_amount);
+    });
+    key_bindings.add(Key::NumPadMinus, "decrement_translation_amount", "Interaction", "Decrement translation amount", |s| {
+        s.vars.decrement_translation_amount();
+        println!("translation_amount: {}", s.vars.translation_amount);
+    });
+    key_bindings.add(Key::NumPadAsterisk, "increment_scale_numerator", "Interaction", "Increment scale_numerator", |s| {
+        s.vars.increment_scale_numerator();
+        println!("scale factor: {}/{}", s.vars.scale_numerator, s.vars.scale_denominator);
+    });
+    key_bindings.add(Key::NumPadSlash, "decrement_scale_numerator", "Interaction", "Decrement scale_numerator", |s| {
+        s.vars.decrement_scale_numerator();
+        println!("scale factor: {}/{}", s.vars.scale_numerator, s.vars.scale_denominator);
+    });
+    key_bindings.add_fallible(Key::LeftBracket, "zoom_in", "Navigation", "Scale the view by scaling_factor, effectively zooming in", |s| scale(s, s.vars.scaling_factor()));
+    key_bindings.add_fallible(Key::RightBracket, "zoom_out", "Navigation", "Scale the view by inverse_scaling_factor, effectively zooming out", |s| scale(s, s.vars.inverse_scaling_factor()));
+    key_bindings.add_rendering(Key::Comma, "rotate_counterclockwise", "Navigation", "Rotate the view counterclockwise by rotation_step degrees", |s| s.c.rotate_view(s.vars.rotation_step));
+    key_bindings.add_rendering(Key::Period, "rotate_clockwise", "Navigation", "Rotate the view clockwise by rotation_step degrees", |s| s.c.rotate_view(-s.vars.rotation_step));
+    key_bindings.add(Key::Slash, "change_rotation_step", "Interaction", "Change the rotation_step in degrees", |s| {
+        s.dialog = Some(Dialog::ask("rotation_step", |s, rotation_step| {
+            s.vars.rotation_step = rotation_step;
+            println!("rotation_step: {}", s.vars.rotation_step);
+        }).with_text(&s.vars.rotation_step));
+    });
+    key_bindings.add(Key::V, "print_view", "Information and window", "Prints the current Mandelbrot set view; the center, scale and rotation", |s| println!("Center: {:?}, scale: {:?}, rotation: {:?}", s.c.center(), s.c.get_scale(), s.c.get_rotation()));
+    let views = [(Key::Key1, "view_1", "Renders VIEW_1", VIEW_1), (Key::Key2, "view_2", "Renders VIEW_2", VIEW_2), (Key::Key3, "view_3", "Renders VIEW_3", VIEW_3), (Key::Key4, "view_4", "Renders VIEW_4", VIEW_4), (Key::Key5, "view_5", "Renders VIEW_5", VIEW_5),
+        (Key::Key6, "view_6", "Renders VIEW_6", VIEW_6), (Key::Key7, "view_7", "Renders VIEW_7", VIEW_7), (Key::Key8, "view_8", "Renders VIEW_8", VIEW_8), (Key::Key9, "view_9", "Renders VIEW_9", VIEW_9), (Key::Key0, "view_0", "Renders VIEW_0", VIEW_0)];
+    for (key, name, description, view) in views {
+        key_bindings.add_rendering(key, name, "Navigation", description, move |s| s.c.set_view(&view));
+    }
+    key_bindings.add(Key::K, "print_key_bindings", "Information and window", "Prints the keybindings", |s| s.key_bindings.print());
+    key_bindings.add_fallible(Key::S, "save_image", "Saving", "Saves the current Mandelbrot set view as an image in the saved folder", save_image);
+    key_bindings.add(Key::I, "input_view", "Navigation", "Manually input a Mandelbrot set view", |s| {
+        //Ask for the scale after the y after the x
+        s.dialog = Some(Dialog::ask("x", |s, x: f64| {
+            s.dialog = Some(Dialog::ask("y", move |s, y: f64| {
+                s.dialog = Some(Dialog::ask("scale", move |s, scale| s.c.set_view(&View::new(x, y, scale))).rendering());
+            }));
+        }));
+    });
+    key_bindings.add(Key::A, "pick_coloring_algorithm", "Coloring", "Pick an algorithm to color the Mandelbrot set view", |s| {
+        let options = [(ColoringAlgorithm::HSV.name(), ColoringAlgorithm::HSV), (ColoringAlgorithm::BernsteinPolynomials.name(), ColoringAlgorithm::BernsteinPolynomials)];
+        s.dialog = Some(Dialog::pick_option("coloring algorithm", &options, |s, coloring_algorithm| s.coloring_algorithm = coloring_algorithm).rendering());
+    });
+    key_bindings.add(Key::M, "change_max_iterations", "Rendering", "Change the Mandelbrot set view max_iterations", |s| {
+        s.dialog = Some(Dialog::ask("max_iterations", |s, max_iterations| s.m.max_iterations = max_iterations).with_text(&s.m.max_iterations).rendering());
+    });
+    key_bindings.add(Key::O, "change_color_channel_mapping", "Coloring", "Change the Mandelbrot set view color channel mapping, xyz -> RGB, where x,y,z ∈ {{'R','G','B'}} (case-insensitive)", |s| {
+        s.dialog = Some(Dialog::ask("color_channel_mapping", |s, color_channel_mapping| s.p.color_channel_mapping = color_channel_mapping).with_text(&s.p.color_channel_mapping).rendering());
+    });
+    key_bindings.add(Key::Q, "change_supersampling_amount", "Rendering", "Change the window and image quality of the Mandelbrot set rendering by setting the SSAA multiplier, clamped from 1x to 64x", |s| {
+        s.dialog = Some(Dialog::ask("supersampling_amount", |s, supersampling_amount: u8| {
+            s.supersampling_amount = supersampling_amount.clamp(1, 64);
+            s.image_supersampling_amount = s.supersampling_amount;
+        }).with_text(&s.supersampling_amount).rendering());
+    });
+    key_bindings.add(Key::X, "change_image_supersampling_amount", "Rendering", "Change the image quality of the Mandelbrot set rendering by setting the SSAA multiplier, clamped from 1x to 64x", |s| {
+        s.dialog = Some(Dialog::ask("image_supersampling_amount", |s, image_supersampling_amount: u8| s.image_supersampling_amount = image_supersampling_amount.clamp(1, 64)).with_text(&s.image_supersampling_amount));
+    });
+    key_bindings.add(Key::C, "print_config", "Information and window", "Prints the configuration variables", |s| println!("{:?}", s.config));
+    key_bindings.add(Key::L, "load_view", "Navigation", "Load a Mandelbrot set view from the metadata of a saved png image", |s| {
+        s.dialog = Some(Dialog::ask("path", |s, path: String| load_metadata_from_png(&path, s)).rendering());
+    });
+    key_bindings.add(Key::H, "toggle_hud", "Information and window", "Show or hide the HUD with the view, rendering parameters and the point under the mouse cursor", |s| {
+        s.hud.toggle();
+        println!("HUD: {}", if s.hud.visible {"shown"} else {"hidden"});
+    });
+    key_bindings.add(Key::F1, "toggle_help_screen", "Information and window", "Show or hide this help screen, use PageUp and PageDown to change its page", |s| s.help_screen.toggle());
+    key_bindings.add(Key::PageDown, "next_help_page", "Information and window", "Show the next page of the help screen", |s| s.help_screen.next_page());
+    key_bindings.add(Key::PageUp, "previous_help_page", "Information and window", "Show the previous page of the help screen", |s| s.help_screen.previous_page());
+    key_bindings.add_fallible(Key::F11, "toggle_fullscreen", "Information and window", "Toggle fullscreen, using the screen size given by the --screen option", |s| {
+        //The window is resized to the new window size in the main loop
+        s.window = create_window(&s.config, !s.fullscreen)?;
+        s.fullscreen = !s.fullscreen;
+        s.window.set_input_callback(Box::new(s.typed_characters.clone()));
+        Ok(())
+    });
+    key_bindings.add(Key::F, "append_keyframe", "Saving", "Append the current Mandelbrot set view as a keyframe to keyframes.json in the output directory", append_keyframe);
+    key_bindings.add_fallible(KeyStroke::new(Key::Z, true, false, false), "undo", "Navigation", "Undo the last change of the Mandelbrot set view or max_iterations", |s| step_through_history(s, true));
+    key_bindings.add_fallible(KeyStroke::new(Key::Y, true, false, false), "redo", "Navigation", "Redo the last undone change of the Mandelbrot set view or max_iterations", |s| step_through_history(s, false));
+    key_bindings.add(Key::E, "export_history", "Saving", "Export the navigation history to history.json in the output directory, it can be loaded using --history", export_history);
+    key_bindings.add(Key::Backquote, "open_console", "Interaction", "Open the console to run commands, e.g. view -0.74 0.1 1e-5; iter 5000; ssaa 8; save name", |s| {
+        s.dialog = Some(Dialog::ask("command", |s, script: Script| run_script(&script, s)));
+    });
+    key_bindings
+}
+
+///Creates the resizable window of `config.window_width` x `config.window_height` pixels.
+///
+///In `fullscreen` mode, creates a borderless window of the screen size at the top left of the screen instead, as minifb cannot make a window fullscreen
+/// # Errors
+/// Returns an Error if minifb cannot open a Window
+fn create_window(config: &Config, fullscreen: bool) -> Result<Window, Error> {
+    let options = WindowOptions { borderless: fullscreen, resize: !fullscreen, topmost: fullscreen, scale_mode: ScaleMode::UpperLeft, ..WindowOptions::default() };
+    let (width, height) = if fullscreen {(config.screen_width, config.screen_height)} else {(config.window_width, config.window_height)};
+    let mut window = Window::new(WINDOW_TITLE, width, height, options).map_err(|err| Error::Render(format!("Could not open a {}x{} window: {}", width, height, err)))?;
+    if fullscreen {
+        window.set_position(0, 0);
+    }
+    Ok(window)
+}
+
+///Rebuilds the pixel plane, `PixelBuffer` and `ComplexPlane` when the size of the window changed, keeping the center and scale of the view
+fn handle_window_resized(s: &mut AppState) -> Result<(), Error> {
+    let (width, height) = s.window.get_size();
+    if width == 0 || height == 0 || (width, height) == (s.p.pixel_plane.width, s.p.pixel_plane.height) {
+        return Ok(()); //Minimized or unchanged
+    }
+    println!("\nWindow resized from {}x{} to {}x{}", s.p.pixel_plane.width, s.p.pixel_plane.height, width, height);
+    let coloring_function = s.coloring_function();
+    rendering::resize_and_render_efficiently(&mut s.c, &mut s.p, &s.m, width, height, s.supersampling_amount, coloring_function)?;
+    s.p.pixel_plane.print();
+    s.c.print();
+    Ok(())
+}
+
+///Restores the view, Mandelbrot set parameters, coloring settings and image SSAA multiplier from the `Metadata` of the png image at `path`.
+///Shows an error and leaves everything unchanged if the `Metadata` cannot be read
+fn load_metadata_from_png(path: &str, s: &mut AppState) {
+    if let Err(err) = s.command_context().load(path) {
+        show_error_message(s, &format!("could not load metadata: {}", err));
+    }
+}
+
+///Runs the commands of the script file at `path`, see `Script`. Shows an error if the script cannot be read or one of its commands fails
+fn run_script_file(path: &str, s: &mut AppState) {
+    match Script::read_from_file(path) {
+        Ok(script) => run_script(&script, s),
+        Err(err) => show_error_message(s, &format!("could not run the script: {}", err)),
+    }
+}
+
+///Runs the commands of `script`, and renders the view if the commands changed it without rendering it. Shows an error if one of its commands fails
+fn run_script(script: &Script, s: &mut AppState) {
+    let mut context = s.command_context();
+    let result = context.run_script(script);
+    let rendered = context.rendered;
+    show_error(s, result);
+    if !rendered {
+        run_action(s, AppState::render);
+        s.c.print();
+    }
+}
+
+///Shows the Error of a failed action in the HUD and the console, so the viewer keeps running instead of crashing, e.g. when the disk is full while saving
+fn show_error(s: &mut AppState, result: Result<(), Error>) {
+    if let Err(err) = result {
+        show_error_message(s, &err.to_string());
+    }
+}
+
+///Shows the error `message` in the error line of the HUD, and prints it to the console
+fn show_error_message(s: &mut AppState, message: &str) {
+    eprintln!("Error: {}", message);
+    s.hud.show_error(message);
+}
+
+///Runs `action` on `s`, showing its Error using `show_error` if it fails
+fn run_action(s: &mut AppState, action: impl FnOnce(&mut AppState) -> Result<(), Error>) {
+    let result = action(s);
+    show_error(s, result);
+}
+
+///Restores the resumed `session`, then the view stored in the metadata of the image given by --load, then the navigation history given by --history
+fn apply_startup_options(config: &Config, session: Option<Session>, s: &mut AppState) {
+    if let Some(session) = session {
+        session.restore(s);
+        s.history.set_cache_size(config.history_cache_size);
+        println!("Resumed the session saved to {}", config.output.file(SESSION_FILE_NAME));
+    }
+    if let Some(path) = &config.load {
+        load_metadata_from_png(path, s);
+    }
+    if let Some(path) = &config.history {
+        load_history(path, s);
+    }
+}
+
+///Continues the navigation `History` of the history file at `path` at its current view.
+///Shows an error and leaves everything unchanged if the history file cannot be read
+fn load_history(path: &str, s: &mut AppState) {
+    match History::read_from_file(path, s.config.history_cache_size) {
+        Ok(history) => {
+            if let Some(entry) = history.current() {
+                s.c.set_view(&entry.view);
+                s.m.clone_from(&entry.mandelbrot_set);
+            }
+            s.history = history;
         }
+        Err(err) => show_error_message(s, &format!("could not load the navigation history: {}", err)),
+    }
+}
+
+///Appends the current view of `s` as a keyframe to the keyframe file in the output directory
+fn append_keyframe(s: &mut AppState) {
+    let path = s.config.output.file(KEYFRAMES_FILE_NAME);
+    match keyframes::append_keyframe(&path, &s.c.get_view(), &s.m, s.p.palette_offset) {
+        Ok(frame) => println!("Appended the current view as keyframe at frame {} to {}", frame, path),
+        Err(err) => show_error_message(s, &format!("could not append keyframe: {}", err)),
+    }
+}
+
+///Exports the navigation `History` of `s` to the history file in the output directory
+fn export_history(s: &mut AppState) {
+    let path = s.config.output.file(HISTORY_FILE_NAME);
+    match s.history.save_to_file(&path) {
+        Ok(()) => println!("Exported the navigation history to {}", path),
+        Err(err) => show_error_message(s, &format!("could not export the navigation history: {}", err)),
+    }
+}
+
+///Saves the `Session` of `s` to the session file in the output directory, taking its navigation `History`
+fn save_session(s: &mut AppState) {
+    let path = s.config.output.file(SESSION_FILE_NAME);
+    match Session::take(s).save_to_file(&path) {
+        Ok(()) => println!("Saved the session to {}, resume it using --resume", path),
+        Err(err) => eprintln!("Error: could not save the session: {}", err),
     }
 }
 
@@ -200,15 +567,16 @@ fn handle_left_mouse_clicked(x: f32, y: f32, c: &ComplexPlane) {
     println!();
 }
 
-fn handle_right_mouse_clicked(x: f32, y: f32, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_function: ColoringFunction) {
+fn handle_right_mouse_clicked(x: f32, y: f32, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_function: ColoringFunction) -> Result<(), Error> {
     println!("\nMouseButton::Right -> Move to ({x}, {y})");
     let new_center = c.complex_from_pixel_plane(x.into(), y.into());
     println!("c.center: {:?}", c.center());
     println!("new_center: {:?}", new_center);
 
-    rendering::translate_to_center_and_render_efficiently(c, p, m, &new_center, supersampling_amount, coloring_function);
+    rendering::translate_to_center_and_render_efficiently(c, p, m, &new_center, supersampling_amount, coloring_function)?;
     c.print();
     println!();
+    Ok(())
 }
 
 /////Mouse click recorder with interior mutability to toggle mouse clicks; 
@@ -233,22 +601,135 @@ impl MouseClickRecorder {
     }
 }
 
-fn handle_mouse_events(window: &Window, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_function: ColoringFunction) {
+///A left mouse button drag: pans the view, or selects a box to zoom into when shift was held down at the start of the drag
+pub struct MouseDrag {
+    ///Position where the drag started
+    start: (f32, f32),
+    ///Pixel the view was last dragged to
+    previous: (i32, i32),
+    box_selection: bool,
+    moved: bool,
+}
+
+///Zooms in when scrolling up and out when scrolling down, keeping the point under the mouse cursor at (x,y) in place
+#[allow(clippy::too_many_arguments)]
+fn handle_mouse_scrolled(x: f32, y: f32, scroll: f32, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, vars: &InteractionVariables, supersampling_amount: u8, coloring_function: ColoringFunction) -> Result<(), Error> {
+    let factor = if scroll > 0.0 {vars.scaling_factor()} else {vars.inverse_scaling_factor()};
+    println!("\nMouse wheel -> Scale by {} around ({x}, {y})", factor);
+    let anchor = c.complex_from_pixel_plane(x.into(), y.into());
+    c.scale_around(factor, &anchor);
+    rendering::render_complex_plane_into_buffer(p, c, m, supersampling_amount, coloring_function)?;
+    c.print();
+    Ok(())
+}
+
+///Zooms into the box with corners `a` and `b`, the box becomes the new view while keeping the aspect ratio of the pixel plane
+fn handle_box_selected(a: (f32, f32), b: (f32, f32), c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, supersampling_amount: u8, coloring_function: ColoringFunction) -> Result<(), Error> {
+    let (box_width, box_height) = ((a.0 - b.0).abs(), (a.1 - b.1).abs());
+    if box_width < 2.0 || box_height < 2.0 {
+        return Ok(()); //Too small to be an intentional selection
+    }
+    println!("\nMouseButton::Left + Shift -> Zoom into box ({}, {}) -> ({}, {})", a.0, a.1, b.0, b.1);
+    let new_center = c.complex_from_pixel_plane(f64::from(a.0.midpoint(b.0)), f64::from(a.1.midpoint(b.1)));
+    let factor = (f64::from(box_width) / f64::from(p.pixel_plane.width as u32)).max(f64::from(box_height) / f64::from(p.pixel_plane.height as u32));
+    c.set_center(&new_center);
+    c.scale(factor);
+    rendering::render_complex_plane_into_buffer(p, c, m, supersampling_amount, coloring_function)?;
+    c.print();
+    Ok(())
+}
+
+///Handles the left mouse button drag in progress: pans the view by the mouse movement, or draws the rubber band of the box selection into the `overlay`.
+///
+///When the left mouse button is released, zooms into the selected box, or prints info about the clicked point if the mouse did not move
+#[allow(clippy::too_many_arguments)]
+fn handle_left_mouse_drag(window: &Window, c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, drag: &mut Option<MouseDrag>, overlay: &mut Overlay, supersampling_amount: u8, coloring_function: ColoringFunction) -> Result<(), Error> {
+    let Some(current) = drag.as_mut() else { return Ok(()) };
+    let (x, y) = window.get_mouse_pos(MouseMode::Clamp).unwrap_or(current.start);
+    let position = (x as i32, y as i32);
+    if window.get_mouse_down(MouseButton::Left) {
+        if position == current.previous {
+            return Ok(());
+        }
+        current.moved = true;
+        if current.box_selection {
+            overlay.clear();
+            overlay.add_rectangle((current.start.0 as usize, current.start.1 as usize), (x as usize, y as usize), RUBBER_BAND_COLOR);
+        } else {
+            //Move the image along with the mouse
+            let (rows_up, columns_right) = (position.1 - current.previous.1, current.previous.0 - position.0);
+            //The drag continues from the current position even if rendering failed
+            current.previous = position;
+            return rendering::pan_and_render_efficiently(c, p, m, rows_up, columns_right, supersampling_amount, coloring_function);
+        }
+        current.previous = position;
+    } else if let Some(finished) = drag.take() {
+        overlay.clear();
+        if !finished.moved {
+            handle_left_mouse_clicked(finished.start.0, finished.start.1, c);
+        } else if finished.box_selection {
+            handle_box_selected(finished.start, (x, y), c, p, m, supersampling_amount, coloring_function)?;
+        } else {
+            c.print();
+        }
+    }
+    Ok(())
+}
+
+fn handle_mouse_events(s: &mut AppState, drag: &mut Option<MouseDrag>, overlay: &mut Overlay) -> Result<(), Error> {
     static LEFT_MOUSE_RECORDER: MouseClickRecorder = MouseClickRecorder::new(MouseButton::Left); //Static variable with interior mutability to toggle mouse clicks; without such a variable, clicking the screen once would result in multiple actions
     static RIGHT_MOUSE_RECORDER: MouseClickRecorder = MouseClickRecorder::new(MouseButton::Right); 
+    let (supersampling_amount, coloring_function) = (s.supersampling_amount, s.coloring_function());
+    let AppState { window, c, p, m, vars, .. } = s;
 
     if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard) {
 
-        //Left mouse actions
-        if LEFT_MOUSE_RECORDER.was_clicked(window) {
-            handle_left_mouse_clicked(x, y, c);
+        //Left mouse actions, start a drag
+        if LEFT_MOUSE_RECORDER.was_clicked(window) && drag.is_none() {
+            let box_selection = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+            *drag = Some(MouseDrag { start: (x, y), previous: (x as i32, y as i32), box_selection, moved: false });
         }
 
         //Right mouse actions
         if RIGHT_MOUSE_RECORDER.was_clicked(window) {
-            handle_right_mouse_clicked(x, y, c, p, m, supersampling_amount, coloring_function);
+            handle_right_mouse_clicked(x, y, c, p, m, supersampling_amount, coloring_function)?;
         }
 
+        //Mouse wheel actions
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            if scroll.abs() > 0.0 {
+                handle_mouse_scrolled(x, y, scroll, c, p, m, vars, supersampling_amount, coloring_function)?;
+            }
+        }
+    }
+
+    handle_left_mouse_drag(window, c, p, m, drag, overlay, supersampling_amount, coloring_function)
+}
+
+///Updates the window with the rendered pixels, with the `overlay` and the HUD or help screen drawn over them
+/// # Errors
+/// Returns an Error if minifb cannot update the window
+fn update_window(s: &mut AppState, overlay: &Overlay) -> Result<(), Error> {
+    let (width, height) = (s.p.pixel_plane.width, s.p.pixel_plane.height);
+    s.hud.update(&s.window, &s.c, &s.p, &s.m, s.supersampling_amount, s.coloring_algorithm);
+    s.help_screen.update(&s.key_bindings, width, height);
+    if let Some(dialog) = &mut s.dialog {
+        dialog.update(width, height);
+    }
+    if overlay.is_empty() && !s.hud.is_shown() && !s.help_screen.visible && s.dialog.is_none() {
+        s.window.update_with_buffer(&s.p.pixels, width, height).map_err(|err| Error::Render(format!("Could not update the window: {}", err)))
+    } else {
+        let mut pixels = overlay.composite(&s.p);
+        //The help screen is drawn instead of the HUD, so they do not overlap
+        if s.help_screen.visible {
+            s.help_screen.draw(&mut pixels, width, height);
+        } else {
+            s.hud.draw(&mut pixels, width, height);
+        }
+        if let Some(dialog) = &s.dialog {
+            dialog.draw(&mut pixels, width, height);
+        }
+        s.window.update_with_buffer(&pixels, width, height).map_err(|err| Error::Render(format!("Could not update the window: {}", err)))
     }
 }
 
@@ -278,110 +759,173 @@ println!("{}{}v{}\n\n", application_banner, author_banner, version);
 ///Prints a command info tip for the users benefit
 fn print_command_info() {
     let tip = "Run Mandelbrot using:";
-    let command = "cargo run --release -- <width> <height> <max_iterations> <supersampling_amount> <window_scale>";
-    let command_info = "where <arg> means substitute with the value of arg\nuse '-' to use the default value of arg";
+    let command = "cargo run --release -- <width> <height> <max_iterations> <supersampling_amount> <window_scale> [--load <path>] [--animate <path>] [--keyframes <path>] [--screen <width>x<height>] [--keys <path>] [--history <path>] [--history-cache <n>] [--resume] [--script <path>] [--headless] [--serve <address>] [--tile-cache <path>] [--checkpoint] [--worker <address>] [--workers <address,...>] [--format <png|ppm|bmp|tiff|jpeg[:quality]>] [--output <directory>] [--name <template>] [--overwrite]";
+    let command_info = "where <arg> means substitute with the value of arg\nuse '-' to use the default value of arg\n--load <path> restores the view stored in the metadata of a saved png image\n--animate <path> renders the frames of a JSON zoom animation file into the saved folder, instead of opening the window\n--keyframes <path> renders the frames of a JSON keyframe file into the saved folder, instead of opening the window\n--screen <width>x<height> sets the window size in fullscreen mode, 1920x1080 by default\n--keys <path> remaps the key bindings using a JSON key map file\n--history <path> continues the navigation history exported to a JSON history file\n--history-cache <n> keeps the rendered images of the n most recent views in the navigation history, 16 by default, 0 disables caching\n--resume reopens the session saved to session.json in the output directory on exit: the window size, view, rendering and coloring settings, interaction variables and navigation history\n--script <path> runs the commands of a script file, e.g. view -0.74 0.1 1e-5; iter 5000; ssaa 8; save name\n--headless runs the --script, or the commands read from stdin, without opening the window\n--serve <address> serves a slippy map of the Mandelbrot set over HTTP, e.g. at 127.0.0.1:8080, instead of opening the window\n--tile-cache <path> caches the tiles served by --serve in a directory\n--checkpoint saves long renders of --headless, --animate and --keyframes in tiles, so an interrupted render continues where it stopped when it is run again\n--worker <address> renders the jobs of distributed renders sent to address, e.g. 0.0.0.0:9000, instead of opening the window\n--workers <address,...> renders saved images on the workers at the comma separated addresses\n--format <png|ppm|bmp|tiff|jpeg[:quality]> sets the format of saved images, png by default, e.g. jpeg:85 for JPEG images of quality 85\n--output <directory> sets the directory saved images, posters, pyramids, escape data, animations, keyframes, the navigation history and the session are written to, saved by default\n--name <template> sets the name of files saved without a name, {date} by default, using the placeholders {date}, {x}, {y}, {scale}, {iter}, {ssaa}, {coloring} and {seq}\n--overwrite overwrites existing files when saving, instead of adding a suffix to the name";
     println!("{}\n\t{}\n{}\n",tip, command, command_info);
 }
 
+///Runs the mode given by the `config` that does not open the window, e.g. rendering an animation or running commands headless.
+///
+///Returns whether such a mode was run
+/// # Errors
+/// Returns the Error of the mode that was run
+fn run_without_window(config: &Config) -> Result<bool, Error> {
+    //Render an animation instead of opening the window
+    if let Some(animation_path) = &config.animate {
+        print_banner();
+        animation::render_zoom_animation(animation_path, config, COLORING_ALGORITHM, COLOR_CHANNEL_MAPPING)?;
+        return Ok(true);
+    }
+    //Run commands instead of opening the window
+    if config.headless {
+        print_banner();
+        command::run_headless(config, COLORING_ALGORITHM, COLOR_CHANNEL_MAPPING)?;
+        return Ok(true);
+    }
+    //Render the jobs of distributed renders instead of opening the window
+    if let Some(address) = &config.worker {
+        print_banner();
+        distributed::work(address)?;
+        return Ok(true);
+    }
+    //Serve tiles over HTTP instead of opening the window
+    if let Some(address) = &config.serve {
+        print_banner();
+        tile_server::serve(address, config, COLORING_ALGORITHM, COLOR_CHANNEL_MAPPING)?;
+        return Ok(true);
+    }
+    if let Some(keyframes_path) = &config.keyframes {
+        print_banner();
+        keyframes::render_keyframe_animation(keyframes_path, config, COLORING_ALGORITHM, COLOR_CHANNEL_MAPPING)?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
 ///Holds all the logic currently in the main function that isn't involved with setting up configuration or handling errors, to make `main` concise and
 ///easy to verify by inspection
-/// # Panics
-/// Will panic if minifb cannot open a Window
 /// # Errors
-/// Currently does not return any Errors
-pub fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+/// Returns an Error if minifb cannot open a Window, or the mode given by the `config` that does not open the window failed.
+/// Errors of the interactive viewer are shown in the console instead, and it keeps running
+pub fn run(config: &Config) -> Result<(), Error> {
+    //Render or serve without opening the window
+    if run_without_window(config)? {
+        return Ok(());
+    }
+    //Reopen the window at the size of the resumed session
+    let session = if config.resume {
+        Session::read_from_file(&config.output.file(SESSION_FILE_NAME)).map_err(|err| eprintln!("Error: could not resume the session: {}", err)).ok()
+    } else {
+        None
+    };
+    let mut config = config.clone();
+    if let Some(session) = &session {
+        config.window_width = session.window_width;
+        config.window_height = session.window_height;
+    }
+    let config = &config;
     // Complex plane dimensions and increments
-    let mut c = ComplexPlane::new(config.window_width, config.window_height);
+    let c = ComplexPlane::new(config.window_width, config.window_height);
     // Pixel plane and buffer
     let mut p = PixelBuffer::new(PixelPlane::new(config.window_width, config.window_height));
-    // User interaction variables
-    let mut vars = InteractionVariables::default();
-    // Multithreading variables
-    let amount_of_threads = num_cpus::get(); //Amount of CPU threads to use, TODO: use this value in rendering functions
-    // Mandelbrot set iterator
-    let mut m: MandelbrotSet = MandelbrotSet::new(config.max_iterations, config.orbit_radius);
-    //Coloring function
-    let mut coloring_function = COLORING_FUNCTION;
     //Color channel mapping
     p.color_channel_mapping = COLOR_CHANNEL_MAPPING;
-    //SSAA multiplier
-    let mut supersampling_amount = config.supersampling_amount;
-    //Image SSAA multiplier
-    let mut image_supersampling_amount = supersampling_amount;
-    // Create a new window
-    let mut window = Window::new(
-        "Mandelbrot set viewer",
-        config.window_width,
-        config.window_height,
-        WindowOptions::default(),
-    )
-    .unwrap_or_else(|e| {
-        panic!("{}", e);
-    });
+    // Multithreading variables
+    let amount_of_threads = num_cpus::get(); //Amount of CPU threads to use, TODO: use this value in rendering functions
+    // Create a new window, listening for typed characters
+    let mut window = create_window(config, false)?;
+    let typed_characters = TypedCharacters::new();
+    window.set_input_callback(Box::new(typed_characters.clone()));
     //Print the banner
     print_banner();
     //Print command info
     print_command_info();
-    //Initialize keybindings TODO: I want to have a vector of structs containing functions with different signatures, this is not easily possible. All functionality should be placed here, in the future, when 
-    //I've figured out how to have closures with different signatures in the same struct field
-    //For now, use empty_closure, to have a closure that does nothing as action
-    let mut key_bindings: KeyBindings = KeyBindings::new(Vec::new());
-    let empty_closure = || ();
-    key_bindings.add(Key::Up, "Move up translation_amount pixels", empty_closure);
-    key_bindings.add(Key::Down, "Move down translation_amount pixels", empty_closure);
-    key_bindings.add(Key::Left, "Move left translation_amount pixels", empty_closure);
-    key_bindings.add(Key::Right, "Move right translation_amount pixels", empty_closure);
-    key_bindings.add(Key::R, "Reset the Mandelbrot set view to the starting view", empty_closure);
-    key_bindings.add(Key::NumPadPlus, "Increment translation_amount", empty_closure);
-    key_bindings.add(Key::NumPadMinus, "Decrement translation amount", empty_closure);
-    key_bindings.add(Key::NumPadAsterisk, "Increment scale_numerator", empty_closure);
-    key_bindings.add(Key::NumPadSlash, "Decrement scale_numerator", empty_closure);
-    key_bindings.add(Key::LeftBracket, "Scale the view by scaling_factor, effectively zooming in",empty_closure);
-    key_bindings.add(Key::RightBracket, "Scale the view by inverse_scaling_factor, effectively zooming out", empty_closure);
-    key_bindings.add(Key::V, "Prints the current Mandelbrot set view; the center and scale", empty_closure);
-    key_bindings.add(Key::Key1, "Renders VIEW_1", empty_closure);
-    key_bindings.add(Key::Key2, "Renders VIEW_2", empty_closure);
-    key_bindings.add(Key::Key3, "Renders VIEW_3", empty_closure);
-    key_bindings.add(Key::Key4, "Renders VIEW_4", empty_closure);
-    key_bindings.add(Key::Key5, "Renders VIEW_5", empty_closure);
-    key_bindings.add(Key::Key6, "Renders VIEW_6", empty_closure);
-    key_bindings.add(Key::Key7, "Renders VIEW_7", empty_closure);
-    key_bindings.add(Key::Key8, "Renders VIEW_8", empty_closure);
-    key_bindings.add(Key::Key9, "Renders VIEW_9", empty_closure);
-    key_bindings.add(Key::Key0, "Renders VIEW_0", empty_closure);
-    key_bindings.add(Key::K, "Prints the keybindings", empty_closure);
-    key_bindings.add(Key::S, "Saves the current Mandelbrot set view as an image in the saved folder", empty_closure);
-    key_bindings.add(Key::I, "Manually input a Mandelbrot set view", empty_closure);
-    key_bindings.add(Key::A, "Pick an algorithm to color the Mandelbrot set view", empty_closure);
-    key_bindings.add(Key::M, "Change the Mandelbrot set view max_iterations", empty_closure);
-    key_bindings.add(Key::O, "Change the Mandelbrot set view color channel mapping, xyz -> RGB, where x,y,z ∈ {{'R','G','B'}} (case-insensitive)", empty_closure);
-    key_bindings.add(Key::Q, "Change the window and image quality of the Mandelbrot set rendering by setting the SSAA multiplier, clamped from 1x to 64x", empty_closure);
-    key_bindings.add(Key::X, "Change the image quality of the Mandelbrot set rendering by setting the SSAA multiplier, clamped from 1x to 64x", empty_closure);
-    key_bindings.add(Key::C, "Prints the configuration variables", empty_closure);
+    //Initialize keybindings, every KeyAction holds the behavior of its key
+    let mut key_bindings = create_key_bindings();
+    //Remap the key bindings using a key map file
+    if let Some(path) = &config.keys {
+        match KeyMap::read_from_file(path) {
+            Ok(key_map) => {
+                for problem in key_bindings.apply_key_map(&key_map) {
+                    eprintln!("Warning: {}: {}", problem, path);
+                }
+            }
+            Err(err) => eprintln!("Error: could not load the key map, using the default key bindings: {}", err),
+        }
+    }
+    let key_bindings = Rc::new(key_bindings);
     key_bindings.print();
 
-    p.pixel_plane.print();
-    c.print();
-    println!("Mandelbrot set parameters: max. iterations is {} and orbit radius is {}", config.max_iterations, config.orbit_radius);
+    let mut state = AppState {
+        window,
+        fullscreen: false,
+        c,
+        p,
+        // Mandelbrot set iterator
+        m: MandelbrotSet::new(config.max_iterations, config.orbit_radius),
+        // User interaction variables
+        vars: InteractionVariables::default(),
+        supersampling_amount: config.supersampling_amount,
+        image_supersampling_amount: config.supersampling_amount,
+        coloring_algorithm: COLORING_ALGORITHM,
+        config: config.clone(),
+        //Heads-up display
+        hud: Hud::new(false),
+        //In-window help screen
+        help_screen: HelpScreen::new(),
+        key_bindings,
+        pending_key_stroke: None,
+        history: History::new(config.history_cache_size),
+        dialog: None,
+        typed_characters,
+    };
+
+    //Restore the session, view and navigation history given by the options
+    apply_startup_options(config, session, &mut state);
+
+    state.p.pixel_plane.print();
+    state.c.print();
+    println!("Mandelbrot set parameters: max. iterations is {} and orbit radius is {}", state.m.max_iterations, state.m.orbit_radius);
     println!("Amount of CPU threads that will be used for rendering: {}", amount_of_threads);
-    println!("Supersampling amount used for rendering: {}x", supersampling_amount);
+    println!("Supersampling amount used for rendering: {}x", state.supersampling_amount);
     println!();
 
+    //Mouse drag in progress and the overlay drawn over the rendered pixels
+    let mut mouse_drag: Option<MouseDrag> = None;
+    let mut overlay = Overlay::new();
+
     println!("Rendering Mandelbrot set default view");
-    rendering::render_complex_plane_into_buffer(&mut p, &c, &m, supersampling_amount, coloring_function);
+    run_action(&mut state, AppState::render);
+
+    //Run the commands of a script file
+    if let Some(path) = &config.script {
+        run_script_file(path, &mut state);
+    }
 
     // Main loop
-    while window.is_open() && !window.is_key_down(Key::Escape) {
+    //Escape exits, unless it cancels a dialog. Failures are shown in the HUD and the console and the loop keeps running
+    while state.window.is_open() && !(state.dialog.is_none() && state.window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No)) {
         
-        // Update the window with the new buffer
-        window.update_with_buffer(&p.pixels, config.window_width, config.window_height).unwrap();
+        // Update the window with the new buffer, with the overlay and HUD drawn over it
+        run_action(&mut state, |s| update_window(s, &overlay));
+
+        // Handle window resizing
+        run_action(&mut state, handle_window_resized);
 
         // Handle any window events
-        handle_key_events(&window, &mut c, &mut p, &mut m, &mut vars, &key_bindings, &mut supersampling_amount, &mut image_supersampling_amount, &mut coloring_function, config);
+        run_action(&mut state, handle_key_events);
 
         //Handle any mouse events
-        handle_mouse_events(&window, &mut c, &mut p, &m, supersampling_amount, coloring_function);
+        run_action(&mut state, |s| handle_mouse_events(s, &mut mouse_drag, &mut overlay));
+
+        //Record the view in the navigation history, once dragging it has finished
+        if mouse_drag.is_none() {
+            state.history.record(state.c.get_view(), &state.m, &state.p, state.coloring_algorithm, state.supersampling_amount);
+        }
     }
 
+    //Save the session, so that it can be resumed using --resume
+    save_session(&mut state);
+
     Ok(())
 }
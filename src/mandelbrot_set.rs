@@ -1,21 +1,40 @@
+use serde::{Deserialize, Serialize};
+
 use crate::complex::Complex;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MandelbrotSet {
     pub max_iterations: u32,
-    ///If z remains within the orbit_radius in max_iterations, we assume c does not tend to infinity
-    pub orbit_radius: f64,    
+    ///If z remains within the `orbit_radius` in `max_iterations`, we assume c does not tend to infinity
+    pub orbit_radius: f64,
+    ///If Some(c), the Julia set of c is iterated instead of the Mandelbrot set: Z0 is the iterated point and c stays fixed
+    #[serde(default)]
+    pub julia_c: Option<Complex>,
+}
+
+///The raw escape data of a point, see `MandelbrotSet::escape`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Escape {
+    ///The amount of iterations before Zn escaped, equal to `MandelbrotSet::iterate`
+    pub iterations: u32,
+    ///The continuous iteration count n + 1 - log2(ln|Zn|), or `max_iterations` if Zn did not escape
+    pub smooth_iterations: f64,
+    ///|Zn| after the last iteration
+    pub final_abs: f64,
+    ///The estimated distance to the set in the complex plane, 0 if Zn did not escape
+    pub distance_estimate: f64,
 }
 
 impl MandelbrotSet {
     pub fn new(max_iterations: u32, orbit_radius: f64) -> MandelbrotSet {
-        MandelbrotSet { max_iterations, orbit_radius }
+        MandelbrotSet { max_iterations, orbit_radius, julia_c: None }
     }
 
     /// Run the Mandelbrot set algorithm for a single Complex number
     /// Returns the amount of iterations needed before Zn escapes to infinity
     pub fn iterate(&self, c: &Complex) -> u32 {
-        let mut z = Complex::new(0.0, 0.0);
+        //For the Julia set of julia_c, start at the iterated point and add julia_c every iteration
+        let (mut z, c) = self.julia_c.as_ref().map_or((Complex::new(0.0, 0.0), c), |julia_c| (*c, julia_c));
         let mut iterations: u32 = 0;
         let orbit_radius_squared = self.orbit_radius*self.orbit_radius;
         for _ in 0..self.max_iterations {
@@ -28,10 +47,61 @@ impl MandelbrotSet {
         }
         iterations
     }
+
+    ///Runs the Mandelbrot set algorithm for a single Complex number like `iterate`, returning its raw `Escape` data instead of only the amount of iterations.
+    ///
+    ///Also iterates the derivative dZn/dc (dZn/dZ0 for a Julia set) for the exterior distance estimate |Zn| ln|Zn| / (2 |dZn|)
+    pub fn escape(&self, c: &Complex) -> Escape {
+        let (mut z, c, mut derivative, derivative_c) = self.julia_c.as_ref()
+            .map_or((Complex::new(0.0, 0.0), c, Complex::new(0.0, 0.0), 1.0), |julia_c| (*c, julia_c, Complex::new(1.0, 0.0), 0.0));
+        let mut iterations: u32 = 0;
+        let orbit_radius_squared = self.orbit_radius*self.orbit_radius;
+        for _ in 0..self.max_iterations {
+            derivative = z.multiply(&derivative).multiply_real(2.0).add(&Complex::new(derivative_c, 0.0));
+            z = z.squared().add(c);
+            if (z.x * z.x + z.y * z.y) > orbit_radius_squared {
+                let final_abs = z.abs();
+                let smooth_iterations = f64::from(iterations) + 1.0 - final_abs.ln().log2();
+                let distance_estimate = final_abs * final_abs.ln() / (2.0 * derivative.abs());
+                return Escape { iterations, smooth_iterations, final_abs, distance_estimate };
+            }
+            iterations += 1;
+        }
+        Escape { iterations, smooth_iterations: f64::from(iterations), final_abs: z.abs(), distance_estimate: 0.0 }
+    }
 }
 
 impl std::fmt::Debug for MandelbrotSet {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "max_iterations = {}, orbit_radius = {}", self.max_iterations, self.orbit_radius)
+        write!(f, "max_iterations = {}, orbit_radius = {}", self.max_iterations, self.orbit_radius)?;
+        if let Some(julia_c) = &self.julia_c {
+            write!(f, ", julia_c = {:?}", julia_c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn escapes_after_as_many_iterations_as_iterate() {
+        let m = MandelbrotSet::new(100, 2.0);
+        for c in [Complex::new(0.5, 0.5), Complex::new(-0.75, 0.1), Complex::new(2.0, 2.0), Complex::new(-1.9, 0.0), Complex::new(0.0, 0.0), Complex::new(-1.0, 0.0)] {
+            assert_eq!(m.escape(&c).iterations, m.iterate(&c));
+        }
+        //Points inside the set never escape and have no distance to the set
+        for c in [Complex::new(0.0, 0.0), Complex::new(-1.0, 0.0), Complex::new(-0.1, 0.1)] {
+            let escape = m.escape(&c);
+            assert_eq!(escape.iterations, 100);
+            assert_eq!(escape.distance_estimate, 0.0);
+        }
+        //Points outside the set escape with a positive distance to the set
+        let escape = m.escape(&Complex::new(0.5, 0.5));
+        assert!(escape.iterations < 100);
+        assert!(escape.distance_estimate > 0.0);
+        assert!(escape.final_abs > 2.0);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,55 @@
+use std::{fs::File, io::BufReader};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{complex_plane::View, mandelbrot_set::MandelbrotSet, coloring::{ColoringAlgorithm, ColorChannelMapping}};
+
+///The keyword of the iTXt chunk in which the `Metadata` of a saved png image is stored as JSON
+pub static METADATA_KEYWORD: &str = "mandelbrot";
+
+///Everything needed to render a saved Mandelbrot set image again.
+///
+///Contains the view, the Mandelbrot set parameters, the coloring settings and the SSAA multiplier. Stored as JSON in the metadata of png images saved by `PixelBuffer::write_png`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    pub view: View,
+    pub mandelbrot_set: MandelbrotSet,
+    pub coloring_algorithm: ColoringAlgorithm,
+    pub color_channel_mapping: ColorChannelMapping,
+    #[serde(default)]
+    pub palette_offset: u32,
+    pub supersampling_amount: u8,
+}
+
+impl Metadata {
+    pub fn new(view: View, mandelbrot_set: MandelbrotSet, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping, palette_offset: u32, supersampling_amount: u8) -> Metadata {
+        Metadata { view, mandelbrot_set, coloring_algorithm, color_channel_mapping, palette_offset, supersampling_amount }
+    }
+
+    ///Serializes the `Metadata` to a JSON string
+    /// # Panics
+    /// If the `Metadata` cannot be serialized, which cannot happen for the types it contains
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    ///Deserializes `Metadata` from a JSON string
+    /// # Errors
+    /// Returns an Error if `json` does not contain valid `Metadata`
+    pub fn from_json(json: &str) -> Result<Metadata, String> {
+        serde_json::from_str(json).map_err(|err| format!("Invalid metadata: {}", err))
+    }
+
+    ///Reads the `Metadata` stored in the `METADATA_KEYWORD` iTXt chunk of the png image at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be opened, is not a png image, or does not contain valid `Metadata`
+    pub fn read_from_png(path: &str) -> Result<Metadata, String> {
+        let file = File::open(path).map_err(|err| format!("{}: {}", err, path))?;
+        let decoder = png::Decoder::new(BufReader::new(file));
+        let reader = decoder.read_info().map_err(|err| format!("{}: {}", err, path))?;
+        let chunk = reader.info().utf8_text.iter().find(|chunk| chunk.keyword == METADATA_KEYWORD)
+            .ok_or_else(|| format!("The image does not contain {} metadata: {}", METADATA_KEYWORD, path))?;
+        let json = chunk.get_text().map_err(|err| format!("{}: {}", err, path))?;
+        Metadata::from_json(&json)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use crate::{error::Error, metadata::Metadata};
+
+///Directory saved images, posters, pyramids, escape data and animations are written to if no `--output` is given
+pub static DEFAULT_OUTPUT_DIRECTORY: &str = "saved";
+///Template of the names of saved files if no `--name` is given
+pub static DEFAULT_FILE_NAME_TEMPLATE: &str = "{date}";
+
+///Where saved files are written: the output `directory`, the `template` of the names of files that are saved without a name,
+///and whether existing files are overwritten
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub directory: PathBuf,
+    pub template: String,
+    pub overwrite: bool,
+}
+
+impl Output {
+    pub fn new(directory: &str, template: &str, overwrite: bool) -> Output {
+        Output { directory: PathBuf::from(directory), template: String::from(template), overwrite }
+    }
+
+    ///Replaces the placeholders in `template` by the values of the view in `metadata`: `{date}` by the current UTC date and time, `{x}` and `{y}` by the center,
+    ///`{scale}` by the scale, `{iter}` by the max. iterations, `{ssaa}` by the SSAA multiplier, `{coloring}` by the coloring algorithm and `{seq}` by `sequence_number`
+    #[allow(clippy::literal_string_with_formatting_args)]
+    pub fn expand(template: &str, metadata: &Metadata, date: &str, sequence_number: u32) -> String {
+        template.replace("{date}", date)
+            .replace("{x}", &metadata.view.x.to_string())
+            .replace("{y}", &metadata.view.y.to_string())
+            .replace("{scale}", &format!("{:e}", metadata.view.scale))
+            .replace("{iter}", &metadata.mandelbrot_set.max_iterations.to_string())
+            .replace("{ssaa}", &metadata.supersampling_amount.to_string())
+            .replace("{coloring}", &format!("{:?}", metadata.coloring_algorithm))
+            .replace("{seq}", &format!("{:04}", sequence_number))
+    }
+
+    ///Returns the path in the output `directory` of a new file with `extension`, named `name`, or the `template` if no name is given, after expanding its placeholders.
+    ///
+    ///Unless `overwrite` is set, an existing file is never returned: `{seq}` is the lowest sequence number from 1 of a file that does not exist yet,
+    ///and a name without `{seq}` of an existing file gets the suffix `_1`, `_2`, ... instead. `{seq}` always counts up, even if `overwrite` is set.
+    ///An empty `extension` returns the path of a directory. The output directory, and the directories in the name, are created if they do not exist
+    /// # Errors
+    /// Returns an Error if the name is empty, or the directory of the file cannot be created
+    pub fn path(&self, name: Option<&str>, metadata: &Metadata, extension: &str) -> Result<PathBuf, Error> {
+        Ok(self.paths(name, metadata, &[extension])?.remove(0))
+    }
+
+    ///Returns the paths of new files with the same name and each of the `extensions`, like `Output::path`, e.g. a file and its sidecar file.
+    ///
+    ///Unless `overwrite` is set, the name is chosen such that none of the files exist yet
+    /// # Errors
+    /// Returns an Error if the name is empty, or the directory of the files cannot be created
+    pub fn paths(&self, name: Option<&str>, metadata: &Metadata, extensions: &[&str]) -> Result<Vec<PathBuf>, Error> {
+        let template = name.unwrap_or(&self.template);
+        let date = chrono::Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+        let has_sequence_number = template.contains("{seq}");
+        let mut paths = Vec::new();
+        for attempt in 1.. {
+            //Replace ':' with '-' for Windows file system. Replace ' ' with '_' because spaces are annoying in filenames
+            let mut file_name = Output::expand(template, metadata, &date, attempt).replace(':', "-").replace(' ', "_");
+            if file_name.is_empty() {
+                return Err(Error::Other(String::from("The file name should not be empty")));
+            }
+            if attempt > 1 && !has_sequence_number {
+                file_name = format!("{}_{}", file_name, attempt - 1);
+            }
+            //The extension is appended instead of set using `Path::with_extension`, as the name may contain dots, e.g. `{scale}`
+            paths = extensions.iter().map(|extension| {
+                self.directory.join(if extension.is_empty() { file_name.clone() } else { format!("{}.{}", file_name, extension) })
+            }).collect();
+            if !paths.iter().any(|path| path.exists()) || (self.overwrite && !has_sequence_number) {
+                break;
+            }
+        }
+        for path in &paths {
+            let directory = path.parent().unwrap_or(&self.directory);
+            fs::create_dir_all(directory).map_err(|err| Error::io(directory, err))?;
+        }
+        Ok(paths)
+    }
+
+    ///Returns the path of the file `file_name` in the output `directory`, e.g. the session file
+    pub fn file(&self, file_name: &str) -> String {
+        self.directory.join(file_name).to_string_lossy().into_owned()
+    }
+
+    ///Returns `path` without `extension`, which was appended by `Output::path`
+    pub fn strip_extension(path: &Path, extension: &str) -> PathBuf {
+        let path = path.to_string_lossy();
+        PathBuf::from(path.strip_suffix(&format!(".{}", extension)).unwrap_or(&path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, mandelbrot_set::MandelbrotSet, test_utils::temporary_directory};
+
+    fn metadata() -> Metadata {
+        Metadata::new(View::new(-0.75, 0.0, 1.0), MandelbrotSet::new(100, 2.0), ColoringAlgorithm::HSV, ColorChannelMapping::RGB, 0, 1)
+    }
+
+    fn output(directory: &Path, template: &str, overwrite: bool) -> Output {
+        Output::new(directory.to_str().unwrap(), template, overwrite)
+    }
+
+    ///Returns the file name of the new path, after creating the file
+    fn create(output: &Output, name: Option<&str>) -> String {
+        let path = output.path(name, &metadata(), "png").unwrap();
+        fs::write(&path, "").unwrap();
+        path.file_name().unwrap().to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn suffixes_the_names_of_existing_files() {
+        let directory = temporary_directory("output_suffixes");
+        let output = output(&directory, "{date}", false);
+        assert_eq!([create(&output, Some("name")), create(&output, Some("name")), create(&output, Some("name"))], ["name.png", "name_1.png", "name_2.png"]);
+        //Existing files are overwritten if overwrite is set
+        assert_eq!(create(&Output { overwrite: true, ..output }, Some("name")), "name.png");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn counts_up_sequence_numbers_from_the_lowest_free_number() {
+        let directory = temporary_directory("output_sequence_numbers");
+        let output = output(&directory, "frame_{seq}", false);
+        assert_eq!([create(&output, None), create(&output, None)], ["frame_0001.png", "frame_0002.png"]);
+        fs::remove_file(directory.join("frame_0001.png")).unwrap();
+        assert_eq!([create(&output, None), create(&output, None)], ["frame_0001.png", "frame_0003.png"]);
+        //`{seq}` counts up even if overwrite is set
+        assert_eq!(create(&Output { overwrite: true, ..output }, None), "frame_0004.png");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn reserves_the_paths_of_all_extensions() {
+        let directory = temporary_directory("output_paths");
+        let output = output(&directory, "{date}", false);
+        fs::write(directory.join("data.json"), "").unwrap();
+        let paths = output.paths(Some("data"), &metadata(), &["npy", "json"]).unwrap();
+        assert_eq!(paths, [directory.join("data_1.npy"), directory.join("data_1.json")]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn replaces_colons_and_spaces_in_names() {
+        let directory = temporary_directory("output_substitution");
+        let output = output(&directory, "{date}", false);
+        assert_eq!(create(&output, Some("a:b c")), "a-b_c.png");
+        assert_eq!(create(&output, Some("x={x} i={iter}")), "x=-0.75_i=100.png");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn creates_the_directories_in_names() {
+        let directory = temporary_directory("output_directories");
+        let output = output(&directory.join("out"), "{date}", false);
+        let path = output.path(Some("a/b/name"), &metadata(), "png").unwrap();
+        assert_eq!(path, directory.join("out").join("a/b/name.png"));
+        assert!(directory.join("out/a/b").is_dir());
+        assert!(!path.exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn rejects_empty_names() {
+        let directory = temporary_directory("output_empty_names");
+        assert!(matches!(output(&directory, "{date}", false).path(Some(""), &metadata(), "png"), Err(Error::Other(_))));
+        assert!(output(&directory, "", false).path(None, &metadata(), "png").is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
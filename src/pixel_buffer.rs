@@ -1,10 +1,11 @@
-use std::{path::Path, fs::File, io::BufWriter};
+use std::{path::Path, fs::File, io::{BufWriter, Write}, time::Duration};
 
-use crate::{coloring::{TrueColor, ColorChannelMapping}, complex_plane::View, mandelbrot_set::MandelbrotSet};
+use crate::{coloring::{TrueColor, ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, error::Error, image_format::ImageFormat, mandelbrot_set::MandelbrotSet, metadata::{Metadata, METADATA_KEYWORD}};
 
 use self::pixel_plane::PixelPlane;
 
 pub mod pixel_plane;
+pub mod overlay;
 
 #[derive(Clone)]
 pub struct PixelBuffer {
@@ -12,6 +13,10 @@ pub struct PixelBuffer {
     pub colors: Vec<TrueColor>,
     pub pixels: Vec<u32>,
     pub color_channel_mapping: ColorChannelMapping,
+    ///Shifts the palette of the coloring function by this amount of iterations when rendering, see `coloring::apply_palette_offset`
+    pub palette_offset: u32,
+    ///Time it took to render the pixels that were rendered last, None if nothing has been rendered yet
+    pub render_time: Option<Duration>,
 }
 
 impl PixelBuffer {
@@ -21,7 +26,7 @@ impl PixelBuffer {
         let colors: Vec<TrueColor> = vec![black; pixel_plane.width * pixel_plane.height];
         let color_channel_mapping = ColorChannelMapping::RGB;
         let pixels: Vec<u32> = PixelBuffer::colors_to_pixels(&colors, &color_channel_mapping);
-        PixelBuffer { pixel_plane, colors, pixels, color_channel_mapping}
+        PixelBuffer { pixel_plane, colors, pixels, color_channel_mapping, palette_offset: 0, render_time: None}
     }
 
     /// Converts a buffer index to a screen coordinate
@@ -44,7 +49,7 @@ impl PixelBuffer {
         self.pixels = PixelBuffer::colors_to_pixels(&self.colors, &self.color_channel_mapping);
     }
 
-    /// Returns the amount of Mandelbrot iterations at a given point inside the pixel plane //TODO: move this somewhere else
+    // Returns the amount of Mandelbrot iterations at a given point inside the pixel plane //TODO: move this somewhere else
     /*pub fn iterations_at_point(&self, x: usize, y: usize, max_iterations: u32) -> u32 {
         let index = self.point_to_index(x, y);
         let pixel = self.buffer[index];
@@ -76,43 +81,197 @@ impl PixelBuffer {
         }
     }
 
-    ///Saves the `PixelBuffer` as an RGB png image to `saved/{file_name_without_extension}.png` </br>
-    ///Stores the current `ComplexPlane` View in the png's metadata under the view keyword </br>
-    ///Stores the `supersampling_amount` in the metadata </br>
-    ///Also stores author and application metadata
-    /// # Panics
-    /// If the file `saved/{file_name_without_extension}.png` cannot be created
-    pub fn save_as_png(&self, file_name_without_extension: &str, view: &View, m: &MandelbrotSet, supersampling_amount: u8) {
-        let file_name_without_extension = file_name_without_extension.replace(':', "-").replace(' ', "_"); //Replace ':' with '-' for Windows file system. Replace ' ' with '_' because spaces are annoying in filenames.
-        let file_name = format!("saved{}{}.png", std::path::MAIN_SEPARATOR_STR, file_name_without_extension);
-        match std::fs::create_dir_all("saved") { //Create the saved folder if it does not exist 
-            Ok(()) => (), //Currently not doing anything with the Result of trying to create the saved folder
-            Err(err) => eprintln!("{}", err),
+    /// Resample the image in the `buffer` as if the complex plane was scaled by `factor` around the point `anchor` in the pixel plane, which stays in place.
+    /// This operation is significantly less expensive than rendering, and can be shown as a preview while the scaled complex plane is rendered:
+    /// every pixel takes the color of the old pixel under its center, pixels that fall outside of the old image become black.
+    /// Returns for every pixel whether it was kept: its complex coordinate coincides exactly with that of an old pixel (e.g. when scaling by 2 or 0.5), so its color is still valid.
+    /// Note: The pixels that were not kept should be rerendered by the `render_remaining_complex_plane_into_buffer` function.
+    pub fn resample(&mut self, factor: f64, anchor: (f64, f64)) -> Vec<bool> {
+        let old = self.clone();
+        self.resample_from(&old, factor, anchor, anchor)
+    }
+
+    /// Resize the `buffer` to `pixel_plane`, keeping the image scale: the old image is resampled so that its center stays at the center of the `buffer`.
+    /// Pixels that fall outside of the old image become black. The old pixel (`old_width / 2`, `old_height / 2`) becomes pixel (`width / 2`, `height / 2`),
+    /// so if the width is unchanged every pixel that is still inside the old image is kept, see `resample`.
+    /// Note: The pixels that were not kept should be rerendered by the `render_remaining_complex_plane_into_buffer` function.
+    pub fn resize(&mut self, pixel_plane: PixelPlane) -> Vec<bool> {
+        let old_anchor = (f64::from((self.pixel_plane.width / 2) as u32), f64::from((self.pixel_plane.height / 2) as u32));
+        let new_anchor = (f64::from((pixel_plane.width / 2) as u32), f64::from((pixel_plane.height / 2) as u32));
+        //The complex plane keeps its scale, so a pixel is old_width / width old pixels wide
+        let factor = f64::from(self.pixel_plane.width as u32) / f64::from(pixel_plane.width as u32);
+        let mut resized = PixelBuffer::new(pixel_plane);
+        resized.color_channel_mapping = self.color_channel_mapping;
+        resized.palette_offset = self.palette_offset;
+        let old = std::mem::replace(self, resized);
+        self.resample_from(&old, factor, old_anchor, new_anchor)
+    }
+
+    /// Resample the image of `old` into the `buffer`: the point `new_anchor` in the pixel plane takes the place of `old_anchor` in `old`,
+    /// and every pixel is `factor` old pixels wide. See `resample`
+    fn resample_from(&mut self, old: &PixelBuffer, factor: f64, old_anchor: (f64, f64), new_anchor: (f64, f64)) -> Vec<bool> {
+        let (width, height) = (self.pixel_plane.width, self.pixel_plane.height);
+        let (old_width, old_height) = (old.pixel_plane.width, old.pixel_plane.height);
+        let mut kept = vec![false; width * height];
+        //Maps a pixel coordinate to the pixel coordinate in old of the same complex number
+        let to_old = |coordinate: f64, anchor: f64, old_anchor: f64| (coordinate - anchor).mul_add(factor, old_anchor);
+        let black = TrueColor::new(0, 0, 0);
+        for y in 0..height {
+            let float_y = f64::from(y as u32);
+            let old_corner_y = to_old(float_y, new_anchor.1, old_anchor.1);
+            for x in 0..width {
+                let float_x = f64::from(x as u32);
+                let old_corner_x = to_old(float_x, new_anchor.0, old_anchor.0);
+                //The complex coordinate of a pixel is that of its corner, see ComplexPlane::complex_from_pixel_plane
+                let coincides = (old_corner_x - old_corner_x.round()).abs() < f64::EPSILON && (old_corner_y - old_corner_y.round()).abs() < f64::EPSILON;
+                //Take the old pixel with the same complex coordinate, or else the old pixel under the center of this pixel
+                let (old_x, old_y) = if coincides {(old_corner_x.round(), old_corner_y.round())} else {(to_old(float_x + 0.5, new_anchor.0, old_anchor.0).floor(), to_old(float_y + 0.5, new_anchor.1, old_anchor.1).floor())};
+                let index = self.point_to_index(x, y);
+                let inside = old_x >= 0.0 && old_y >= 0.0 && old_x < f64::from(old_width as u32) && old_y < f64::from(old_height as u32);
+                if inside {
+                    self.colors[index] = old.colors[old.point_to_index(old_x as usize, old_y as usize)];
+                    kept[index] = coincides;
+                } else {
+                    self.colors[index] = black;
+                }
+            }
         }
-        let path = Path::new(&file_name);
-        let file = File::create(path).unwrap();
-        let w = &mut BufWriter::new(file);
-        let mut encoder = png::Encoder::new(w, self.pixel_plane.width as u32, self.pixel_plane.height as u32);
-        encoder.set_color(png::ColorType::Rgb);
-        encoder.set_depth(png::BitDepth::Eight);
-        let view_text = format!("{:?}", view);
-        encoder.add_text_chunk(String::from("view"), view_text).unwrap();
-        let mandelbrot_set_text = format!("{:?}", m);
-        encoder.add_text_chunk(String::from("mandelbrot_set"), mandelbrot_set_text).unwrap();
-        let supersampling_amount_text = format!("{}x", supersampling_amount);
-        encoder.add_text_chunk(String::from("supersampling_amount"), supersampling_amount_text).unwrap();
-        encoder.add_text_chunk(String::from("application"), String::from("Mandelbrot by Jort (https://github.com/jortrr/mandelbrot)")).unwrap();
-        encoder.add_text_chunk(String::from("author"), String::from("jortrr (https://github.com/jortrr/)")).unwrap();
-        let color_channel_mapping_text = format!("{:?}", self.color_channel_mapping);
-        encoder.add_text_chunk(String::from("color_channel_mapping"), color_channel_mapping_text).unwrap();
-        let mut data: Vec<u8> = Vec::new();
+        self.update_pixels();
+        kept
+    }
+
+    ///Saves the `PixelBuffer` as an RGB png image to `path`, with the same metadata as `write_png`
+    /// # Errors
+    /// Returns an Error if the file at `path` cannot be created, or the png image cannot be encoded or written
+    pub fn save_as_png_at(&self, path: &Path, view: &View, m: &MandelbrotSet, supersampling_amount: u8, coloring_algorithm: ColoringAlgorithm) -> Result<(), Error> {
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        self.write_png(BufWriter::new(file), view, m, supersampling_amount, coloring_algorithm).map_err(|err| Error::Image(format!("{}: {}", err, path.display())))
+    }
+
+    ///Encodes the `PixelBuffer` as an RGB png image into `w`. </br>
+    ///Stores the current `ComplexPlane` View, the `MandelbrotSet`, the coloring settings and the `supersampling_amount` as JSON `Metadata`
+    ///in the png's metadata under the `METADATA_KEYWORD` keyword, so the view can be restored using `Metadata::read_from_png` </br>
+    ///Also stores author and application metadata
+    /// # Errors
+    /// Returns an Error if the png image cannot be encoded or written
+    pub fn write_png<W: Write>(&self, w: W, view: &View, m: &MandelbrotSet, supersampling_amount: u8, coloring_algorithm: ColoringAlgorithm) -> Result<(), String> {
+        let metadata = Metadata::new(*view, m.clone(), coloring_algorithm, self.color_channel_mapping, self.palette_offset, supersampling_amount);
+        ImageFormat::Png.write(w, self.pixel_plane.width, self.pixel_plane.height, &self.rgb(), &metadata)
+    }
+
+    ///Saves the `PixelBuffer` as an image of `format` to `path`, storing `metadata` if the format supports it, see `ImageFormat`
+    /// # Errors
+    /// Returns an Error if the file at `path` cannot be created, or the image cannot be encoded or written
+    pub fn save_at(&self, path: &Path, format: ImageFormat, metadata: &Metadata) -> Result<(), Error> {
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        format.write(BufWriter::new(file), self.pixel_plane.width, self.pixel_plane.height, &self.rgb(), metadata).map_err(|err| Error::Image(format!("{}: {}", err, path.display())))
+    }
+
+    ///Returns the RGB bytes of the colors, ordered by the `color_channel_mapping`
+    pub fn rgb(&self) -> Vec<u8> {
         let (r_map, g_map, b_map) = self.color_channel_mapping.get_r_g_b_mapping();
-        for color in &self.colors {
-            data.push(color.get_color(r_map));
-            data.push(color.get_color(g_map));
-            data.push(color.get_color(b_map));
+        self.colors.iter().flat_map(|color| [color.get_color(r_map), color.get_color(g_map), color.get_color(b_map)]).collect()
+    }
+
+    ///Returns an encoder of an RGB png image of `width` x `height` pixels with `bit_depth` bits per channel into `w`, storing `metadata` and the author and application metadata, see `write_png`
+    /// # Errors
+    /// Returns an Error if the metadata cannot be encoded
+    pub fn png_encoder<W: Write>(w: W, width: usize, height: usize, bit_depth: png::BitDepth, metadata: &Metadata) -> Result<png::Encoder<'static, W>, String> {
+        let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(bit_depth);
+        encoder.add_itxt_chunk(String::from(METADATA_KEYWORD), metadata.to_json()).map_err(|err| err.to_string())?;
+        encoder.add_text_chunk(String::from("application"), String::from("Mandelbrot by Jort (https://github.com/jortrr/mandelbrot)")).map_err(|err| err.to_string())?;
+        encoder.add_text_chunk(String::from("author"), String::from("jortrr (https://github.com/jortrr/)")).map_err(|err| err.to_string())?;
+        Ok(encoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    ///Returns a `width` x `height` `PixelBuffer` in which the pixel (x, y) has the color (x + 1, y + 1, 1), so it is never black
+    fn numbered_buffer(width: usize, height: usize) -> PixelBuffer {
+        let mut p = PixelBuffer::new(PixelPlane::new(width, height));
+        for index in 0..width * height {
+            let (x, y) = p.index_to_point(index);
+            p.colors[index] = TrueColor::new(x as u8 + 1, y as u8 + 1, 1);
+        }
+        p.update_pixels();
+        p
+    }
+
+    ///Returns the old pixel (x, y) whose color the pixel (`x`, `y`) of `p` has, or None if it is black
+    fn source(p: &PixelBuffer, x: usize, y: usize) -> Option<(usize, usize)> {
+        let color = p.colors[p.point_to_index(x, y)];
+        (color.blue != 0).then(|| (usize::from(color.red) - 1, usize::from(color.green) - 1))
+    }
+
+    #[test]
+    fn resample_by_one_keeps_every_pixel() {
+        let mut p = numbered_buffer(5, 4);
+        let kept = p.resample(1.0, (2.0, 2.0));
+        assert!(kept.iter().all(|&k| k));
+        for index in 0..5 * 4 {
+            let (x, y) = p.index_to_point(index);
+            assert_eq!(source(&p, x, y), Some((x, y)));
+        }
+    }
+
+    #[test]
+    fn resample_up_keeps_only_coinciding_pixels() {
+        //Zoom in 2x around the top left corner: pixel (x, y) starts at old pixel (x / 2, y / 2)
+        let mut p = numbered_buffer(4, 4);
+        let kept = p.resample(0.5, (0.0, 0.0));
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(source(&p, x, y), Some((x / 2, y / 2)), "pixel ({}, {})", x, y);
+                assert_eq!(kept[p.point_to_index(x, y)], x % 2 == 0 && y % 2 == 0, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn resample_up_around_the_center_fills_the_edges() {
+        let mut p = numbered_buffer(4, 4);
+        let kept = p.resample(0.5, (2.0, 2.0));
+        //The first and last rows and columns show the old pixels next to the center, none of them coincide with an old pixel
+        assert_eq!(source(&p, 0, 0), Some((1, 1)));
+        assert_eq!(source(&p, 3, 3), Some((2, 2)));
+        assert_eq!(source(&p, 0, 3), Some((1, 2)));
+        assert!(!kept[p.point_to_index(3, 3)]);
+        assert!(!kept[p.point_to_index(0, 3)]);
+        assert!(kept[p.point_to_index(0, 0)]);
+        assert!(kept[p.point_to_index(2, 2)]);
+    }
+
+    #[test]
+    fn resample_down_blackens_pixels_outside_the_old_image() {
+        //Zoom out 2x around the center: pixel x shows old pixel 2x - 2
+        let mut p = numbered_buffer(4, 4);
+        let kept = p.resample(2.0, (2.0, 2.0));
+        for y in 0..4 {
+            for x in 0..4 {
+                let inside = (1..=2).contains(&x) && (1..=2).contains(&y);
+                let expected = inside.then(|| (2 * x - 2, 2 * y - 2));
+                assert_eq!(source(&p, x, y), expected, "pixel ({}, {})", x, y);
+                assert_eq!(kept[p.point_to_index(x, y)], inside, "pixel ({}, {})", x, y);
+            }
         }
-        let mut writer = encoder.write_header().unwrap();
-        writer.write_image_data(&data).unwrap();
+    }
+
+    #[test]
+    fn resample_down_around_the_corner_keeps_the_first_rows_and_columns() {
+        let mut p = numbered_buffer(5, 3);
+        let kept = p.resample(2.0, (0.0, 0.0));
+        assert_eq!(source(&p, 0, 0), Some((0, 0)));
+        assert_eq!(source(&p, 2, 1), Some((4, 2)));
+        assert!(kept[p.point_to_index(2, 1)]);
+        //The last columns and row are outside of the old image
+        assert_eq!(source(&p, 3, 0), None);
+        assert_eq!(source(&p, 4, 2), None);
+        assert_eq!(source(&p, 0, 2), None);
+        assert!(!kept[p.point_to_index(4, 2)]);
     }
 }
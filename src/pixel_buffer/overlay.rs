@@ -0,0 +1,123 @@
+pub mod font;
+
+use super::PixelBuffer;
+
+use self::font::{GLYPH_ADVANCE, GLYPH_HEIGHT};
+
+///A rectangle outline drawn by an `Overlay`, in pixel plane coordinates, including both corners
+#[derive(Clone, Copy)]
+pub struct Rectangle {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+    ///32-bit `0RGB` color
+    pub color: u32,
+}
+
+///A single line of text drawn by an `Overlay` using the embedded bitmap `font`, on a darkened background so it is readable on any image
+#[derive(Clone)]
+pub struct Text {
+    ///Top left corner of the text, in pixel plane coordinates
+    pub x: usize,
+    pub y: usize,
+    pub text: String,
+    ///32-bit `0RGB` color
+    pub color: u32,
+    ///Every pixel of a glyph is drawn as a `scale` x `scale` square
+    pub scale: usize,
+}
+
+///A layer of shapes drawn over the pixels of a `PixelBuffer` when it is shown in the window, without changing the rendered pixels
+#[derive(Default)]
+pub struct Overlay {
+    rectangles: Vec<Rectangle>,
+    texts: Vec<Text>,
+}
+
+impl Overlay {
+    pub fn new() -> Overlay {
+        Overlay::default()
+    }
+
+    ///Removes all shapes from the `Overlay`
+    pub fn clear(&mut self) {
+        self.rectangles.clear();
+        self.texts.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rectangles.is_empty() && self.texts.is_empty()
+    }
+
+    ///Adds the outline of the rectangle with corners `a` and `b` to the `Overlay`, in any order
+    pub fn add_rectangle(&mut self, a: (usize, usize), b: (usize, usize), color: u32) {
+        self.rectangles.push(Rectangle { min_x: a.0.min(b.0), min_y: a.1.min(b.1), max_x: a.0.max(b.0), max_y: a.1.max(b.1), color });
+    }
+
+    ///Adds a line of `text` with its top left corner at `position` to the `Overlay`, see `Text`
+    pub fn add_text(&mut self, position: (usize, usize), text: &str, color: u32, scale: usize) {
+        self.texts.push(Text { x: position.0, y: position.1, text: String::from(text), color, scale: scale.max(1) });
+    }
+
+    ///Returns the pixels of `p` with the `Overlay` drawn over them, shapes outside of the pixel plane are clipped
+    pub fn composite(&self, p: &PixelBuffer) -> Vec<u32> {
+        let mut pixels = p.pixels.clone();
+        self.draw(&mut pixels, p.pixel_plane.width, p.pixel_plane.height);
+        pixels
+    }
+
+    ///Draws the `Overlay` over `pixels`, an image of `width` x `height` pixels, shapes outside of the image are clipped
+    pub fn draw(&self, pixels: &mut [u32], width: usize, height: usize) {
+        for rectangle in &self.rectangles {
+            let max_x = rectangle.max_x.min(width.saturating_sub(1));
+            let max_y = rectangle.max_y.min(height.saturating_sub(1));
+            for x in rectangle.min_x..=max_x {
+                for y in [rectangle.min_y, rectangle.max_y] {
+                    if y < height {
+                        pixels[y * width + x] = rectangle.color;
+                    }
+                }
+            }
+            for y in rectangle.min_y..=max_y {
+                for x in [rectangle.min_x, rectangle.max_x] {
+                    if x < width {
+                        pixels[y * width + x] = rectangle.color;
+                    }
+                }
+            }
+        }
+        for text in &self.texts {
+            Overlay::draw_text(text, pixels, width, height);
+        }
+    }
+
+    ///Draws `text` over `pixels`, see `draw`
+    fn draw_text(text: &Text, pixels: &mut [u32], width: usize, height: usize) {
+        let (text_width, text_height) = font::text_size(&text.text, text.scale);
+        //Darken the background, including a margin of one glyph pixel
+        let margin = text.scale;
+        if text.x.saturating_sub(margin) >= width {
+            return;
+        }
+        let (min_x, min_y) = (text.x.saturating_sub(margin), text.y.saturating_sub(margin));
+        let max_x = (text.x + text_width + margin).min(width);
+        let max_y = (text.y + text_height + margin).min(height);
+        for y in min_y..max_y {
+            for pixel in &mut pixels[y * width + min_x..y * width + max_x.max(min_x)] {
+                *pixel = (*pixel >> 1) & 0x007F_7F7F;
+            }
+        }
+        for (i, character) in text.text.chars().enumerate() {
+            let glyph_x = text.x + i * GLYPH_ADVANCE * text.scale;
+            for y in 0..GLYPH_HEIGHT * text.scale {
+                for x in 0..GLYPH_ADVANCE * text.scale {
+                    let (pixel_x, pixel_y) = (glyph_x + x, text.y + y);
+                    if pixel_x < width && pixel_y < height && font::is_set(character, x / text.scale, y / text.scale) {
+                        pixels[pixel_y * width + pixel_x] = text.color;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path, time::Instant};
+
+use crate::{complex_plane::ComplexPlane, error::Error, metadata::Metadata, pixel_buffer::PixelBuffer, rendering::{self, RenderBox}};
+
+///The maximum amount of pixels held in memory while rendering a `Poster`, 3 bytes each
+pub static BAND_PIXELS: usize = 1 << 22;
+///The maximum height of a band in rows, so the progress is reported often enough
+pub static MAX_BAND_HEIGHT: usize = 64;
+///The maximum width and height of a png image in pixels
+pub static MAX_POSTER_SIZE: usize = i32::MAX as usize;
+
+///An image that is too large to hold in memory, e.g. a 50000x50000 poster.
+///
+///Rendered in bands of rows, which are streamed into a png encoder one at a time, so at most `BAND_PIXELS` pixels are held in memory
+pub struct Poster {
+    pub width: usize,
+    pub height: usize,
+    ///The view, Mandelbrot set parameters and coloring settings to render, stored in the metadata of the png image
+    pub metadata: Metadata,
+}
+
+impl Poster {
+    pub fn new(width: usize, height: usize, metadata: Metadata) -> Poster {
+        Poster { width, height, metadata }
+    }
+
+    ///Returns the height in rows of the bands the `Poster` is rendered in
+    pub fn band_height(&self) -> usize {
+        (BAND_PIXELS / self.width.max(1)).clamp(1, MAX_BAND_HEIGHT)
+    }
+
+    ///Returns an Error if the size of the `Poster` is not a valid png image size
+    fn check_size(&self) -> Result<(), String> {
+        if !(1..=MAX_POSTER_SIZE).contains(&self.width) || !(1..=MAX_POSTER_SIZE).contains(&self.height) {
+            return Err(format!("The poster size should be between 1x1 and {}x{} pixels: {}x{}", MAX_POSTER_SIZE, MAX_POSTER_SIZE, self.width, self.height));
+        }
+        Ok(())
+    }
+
+    ///Renders the `Poster` and saves it as a png image to `path`, printing the progress and the estimated remaining time
+    /// # Errors
+    /// Returns an Error if the file at `path` cannot be created, or the png image cannot be encoded or written, see `write_png`
+    pub fn save_as_png_at(&self, path: &Path) -> Result<(), Error> {
+        self.check_size().map_err(Error::Image)?;
+        let file = File::create(path).map_err(|err| Error::io(path, err))?;
+        println!("Rendering a {}x{} poster in bands of {} rows into {}", self.width, self.height, self.band_height(), path.display());
+        self.write_png(BufWriter::new(file)).map_err(|err| Error::Image(format!("{}: {}", err, path.display())))?;
+        println!("Saved the poster: {}", path.display());
+        Ok(())
+    }
+
+    ///Renders the `Poster` band by band, encoding it as an RGB png image into `w` with the same metadata as `PixelBuffer::write_png`
+    /// # Errors
+    /// Returns an Error if the size of the `Poster` is not a valid png image size, or the png image cannot be encoded or written
+    pub fn write_png<W: Write>(&self, w: W) -> Result<(), String> {
+        self.check_size()?;
+        let mut c = ComplexPlane::new(self.width, self.height);
+        c.set_view(&self.metadata.view);
+        let coloring_function = self.metadata.coloring_algorithm.coloring_function();
+        let encoder = PixelBuffer::png_encoder(w, self.width, self.height, png::BitDepth::Eight, &self.metadata)?;
+        let mut writer = encoder.write_header().map_err(|err| err.to_string())?;
+        let mut stream_writer = writer.stream_writer().map_err(|err| err.to_string())?;
+        let band_height = self.band_height();
+        let mut band = vec![0; band_height * self.width * 3];
+        let time = Instant::now();
+        for min_y in (0..self.height).step_by(band_height) {
+            let max_y = (min_y + band_height).min(self.height);
+            let band = &mut band[..(max_y - min_y) * self.width * 3];
+            rendering::render_box_into_rgb(band, &c, &self.metadata.mandelbrot_set, RenderBox::new(0, self.width, min_y, max_y), self.metadata.supersampling_amount, self.metadata.palette_offset, self.metadata.color_channel_mapping, coloring_function);
+            stream_writer.write_all(band).map_err(|err| err.to_string())?;
+            rendering::print_progress_with_eta(max_y, self.height, "rows", time.elapsed());
+        }
+        println!();
+        stream_writer.finish().and_then(|()| writer.finish()).map_err(|err| err.to_string())
+    }
+}
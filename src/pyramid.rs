@@ -0,0 +1,240 @@
+use std::{fs::{self, File}, io::BufWriter, path::{Path, PathBuf}, str::FromStr, time::Instant};
+
+use serde::Serialize;
+
+use crate::{complex_plane::ComplexPlane, error::Error, metadata::Metadata, poster::MAX_POSTER_SIZE, rendering::{self, RenderBox}};
+
+///The layout of a `Pyramid` on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PyramidFormat {
+    ///Deep Zoom Image: a `{name}.dzi` XML descriptor, and the tiles of every level at `{name}_files/{level}/{column}_{row}.png`.
+    ///Level 0 is 1x1 pixels, every next level doubles the size
+    Dzi,
+    ///IIIF Image API 3.0 level 0: a `{name}/info.json` descriptor, and the tiles at `{name}/{region}/{size}/0/default.png` for every scale factor
+    Iiif,
+}
+
+impl PyramidFormat {
+    ///Returns the extension of the descriptor next to the tiles, or an empty extension if the descriptor is inside the directory of the tiles
+    pub fn descriptor_extension(self) -> &'static str {
+        match self {
+            PyramidFormat::Dzi => "dzi",
+            PyramidFormat::Iiif => "",
+        }
+    }
+}
+
+impl FromStr for PyramidFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PyramidFormat, String> {
+        match s.to_lowercase().as_str() {
+            "dzi" => Ok(PyramidFormat::Dzi),
+            "iiif" => Ok(PyramidFormat::Iiif),
+            _ => Err(format!("Unknown pyramid format: {}, should be dzi or iiif", s)),
+        }
+    }
+}
+
+///A tile of a `Pyramid`: the pixels inside `render_box` of a level of `level_width` x `level_height` pixels, saved as a png image to `path`
+struct Tile {
+    level_width: usize,
+    level_height: usize,
+    render_box: RenderBox,
+    width: usize,
+    height: usize,
+    path: PathBuf,
+}
+
+impl Tile {
+    fn new(level_size: (usize, usize), (x, y): (usize, usize), (width, height): (usize, usize), path: PathBuf) -> Tile {
+        Tile { level_width: level_size.0, level_height: level_size.1, render_box: RenderBox::new(x, x + width, y, y + height), width, height, path }
+    }
+
+    ///Renders the `Tile` of the view of `metadata`, on the `ComplexPlane` of its level, and saves it as an RGB png image
+    fn render(&self, metadata: &Metadata) -> Result<(), String> {
+        let mut c = ComplexPlane::new(self.level_width, self.level_height);
+        c.set_view(&metadata.view);
+        let mut rgb = vec![0; self.width * self.height * 3];
+        rendering::render_box_into_rgb(&mut rgb, &c, &metadata.mandelbrot_set, self.render_box, metadata.supersampling_amount, metadata.palette_offset, metadata.color_channel_mapping, metadata.coloring_algorithm.coloring_function());
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory).map_err(|err| format!("{}: {}", err, directory.display()))?;
+        }
+        let file = File::create(&self.path).map_err(|err| format!("{}: {}", err, self.path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header().and_then(|mut writer| writer.write_image_data(&rgb)).map_err(|err| format!("{}: {}", err, self.path.display()))
+    }
+}
+
+///The tile layout of an IIIF pyramid in its `info.json` descriptor
+#[derive(Serialize)]
+struct IiifTiles {
+    width: usize,
+    height: usize,
+    #[serde(rename = "scaleFactors")]
+    scale_factors: Vec<usize>,
+}
+
+///The `info.json` descriptor of an IIIF pyramid
+#[derive(Serialize)]
+struct IiifInfo {
+    #[serde(rename = "@context")]
+    context: &'static str,
+    ///The URL the pyramid is served at, the name of its directory so viewers resolve the tiles relative to the descriptor
+    id: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    protocol: &'static str,
+    profile: &'static str,
+    width: usize,
+    height: usize,
+    tiles: Vec<IiifTiles>,
+}
+
+///A multi-resolution tile pyramid of a view, e.g. for a Deep Zoom viewer.
+///
+///Every level is rendered directly at its own resolution, on a `ComplexPlane` of the size of that level, instead of being downsampled from the full image
+pub struct Pyramid {
+    pub format: PyramidFormat,
+    ///Size of the full resolution image in pixels
+    pub width: usize,
+    pub height: usize,
+    ///Width and height of a tile in pixels, without the overlap
+    pub tile_size: usize,
+    ///Amount of pixels a tile overlaps with each of its neighbours, only supported by `PyramidFormat::Dzi`
+    pub overlap: usize,
+    ///The view, Mandelbrot set parameters and coloring settings to render
+    pub metadata: Metadata,
+}
+
+impl Pyramid {
+    pub fn new(format: PyramidFormat, width: usize, height: usize, tile_size: usize, overlap: usize, metadata: Metadata) -> Pyramid {
+        Pyramid { format, width, height, tile_size, overlap, metadata }
+    }
+
+    ///Returns an Error if the size, tile size or overlap of the `Pyramid` is not valid for its format
+    fn check(&self) -> Result<(), String> {
+        if !(1..=MAX_POSTER_SIZE).contains(&self.width) || !(1..=MAX_POSTER_SIZE).contains(&self.height) {
+            return Err(format!("The pyramid size should be between 1x1 and {}x{} pixels: {}x{}", MAX_POSTER_SIZE, MAX_POSTER_SIZE, self.width, self.height));
+        }
+        if self.tile_size == 0 {
+            return Err(String::from("The tile size should be at least 1 pixel"));
+        }
+        if self.overlap > 0 && self.format == PyramidFormat::Iiif {
+            return Err(format!("IIIF tiles cannot overlap: {} pixels", self.overlap));
+        }
+        Ok(())
+    }
+
+    ///Returns the highest DZI level, at which the `Pyramid` has its full size
+    pub fn max_level(&self) -> usize {
+        let size = self.width.max(self.height);
+        (usize::BITS - (size - 1).leading_zeros()) as usize
+    }
+
+    ///Returns the IIIF scale factors: powers of two, up to the first at which the whole image fits in a single tile
+    pub fn scale_factors(&self) -> Vec<usize> {
+        let mut scale_factors = vec![1];
+        while self.width.div_ceil(scale_factors[scale_factors.len() - 1]) > self.tile_size || self.height.div_ceil(scale_factors[scale_factors.len() - 1]) > self.tile_size {
+            scale_factors.push(2 * scale_factors[scale_factors.len() - 1]);
+        }
+        scale_factors
+    }
+
+    ///Returns the `start` pixel of the tile at `index` and its size, for a level of `size` pixels, including the overlap with its neighbours
+    fn tile_span(&self, index: usize, size: usize) -> (usize, usize) {
+        let start = (index * self.tile_size).saturating_sub(self.overlap);
+        let end = ((index + 1) * self.tile_size + self.overlap).min(size);
+        (start, end - start)
+    }
+
+    ///Returns the tiles of every DZI level, in the directory `{base}_files`
+    fn dzi_tiles(&self, base: &Path) -> Vec<Tile> {
+        let directory = PathBuf::from(format!("{}_files", base.display()));
+        let max_level = self.max_level();
+        let mut tiles = Vec::new();
+        for level in 0..=max_level {
+            let divisor = 1 << (max_level - level);
+            let level_size = (self.width.div_ceil(divisor), self.height.div_ceil(divisor));
+            for row in 0..level_size.1.div_ceil(self.tile_size) {
+                for column in 0..level_size.0.div_ceil(self.tile_size) {
+                    let (x, width) = self.tile_span(column, level_size.0);
+                    let (y, height) = self.tile_span(row, level_size.1);
+                    let path = directory.join(level.to_string()).join(format!("{}_{}.png", column, row));
+                    tiles.push(Tile::new(level_size, (x, y), (width, height), path));
+                }
+            }
+        }
+        tiles
+    }
+
+    ///Returns the tiles of every IIIF scale factor, in the directory `base`.
+    ///
+    ///Uses the canonical IIIF region and size: `full` for the whole image, `max` for the full size
+    fn iiif_tiles(&self, base: &Path) -> Vec<Tile> {
+        let mut tiles = Vec::new();
+        for scale_factor in self.scale_factors().into_iter().rev() {
+            let level_size = (self.width.div_ceil(scale_factor), self.height.div_ceil(scale_factor));
+            let region_size = self.tile_size * scale_factor;
+            for region_y in (0..self.height).step_by(region_size) {
+                for region_x in (0..self.width).step_by(region_size) {
+                    let (region_width, region_height) = (region_size.min(self.width - region_x), region_size.min(self.height - region_y));
+                    let (width, height) = (region_width.div_ceil(scale_factor), region_height.div_ceil(scale_factor));
+                    let region = if region_width == self.width && region_height == self.height {String::from("full")} else {format!("{},{},{},{}", region_x, region_y, region_width, region_height)};
+                    let size = if width == self.width && height == self.height {String::from("max")} else {format!("{},{}", width, height)};
+                    let path = base.join(region).join(size).join("0").join("default.png");
+                    tiles.push(Tile::new(level_size, (region_x / scale_factor, region_y / scale_factor), (width, height), path));
+                }
+            }
+        }
+        tiles
+    }
+
+    ///Returns the path and contents of the descriptor of the `Pyramid`
+    fn descriptor(&self, base: &Path) -> (PathBuf, String) {
+        match self.format {
+            PyramidFormat::Dzi => {
+                let xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"png\" Overlap=\"{}\" TileSize=\"{}\">\n  <Size Width=\"{}\" Height=\"{}\"/>\n</Image>\n",
+                    self.overlap, self.tile_size, self.width, self.height);
+                (PathBuf::from(format!("{}.{}", base.display(), self.format.descriptor_extension())), xml)
+            }
+            PyramidFormat::Iiif => {
+                let info = IiifInfo {
+                    context: "http://iiif.io/api/image/3/context.json",
+                    id: base.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+                    kind: "ImageService3",
+                    protocol: "http://iiif.io/api/image",
+                    profile: "level0",
+                    width: self.width,
+                    height: self.height,
+                    tiles: vec![IiifTiles { width: self.tile_size, height: self.tile_size, scale_factors: self.scale_factors() }],
+                };
+                (base.join("info.json"), serde_json::to_string_pretty(&info).unwrap_or_default())
+            }
+        }
+    }
+
+    ///Renders every tile of the `Pyramid` and saves it next to its descriptor, `{base}.dzi` or `{base}/info.json`, printing the progress and the estimated remaining time
+    /// # Errors
+    /// Returns an Error if the `Pyramid` is not valid for its format, or a tile or the descriptor cannot be written
+    pub fn save(&self, base: &Path) -> Result<(), Error> {
+        self.check().map_err(Error::Image)?;
+        let tiles = match self.format {
+            PyramidFormat::Dzi => self.dzi_tiles(base),
+            PyramidFormat::Iiif => self.iiif_tiles(base),
+        };
+        println!("Rendering a {}x{} {:?} pyramid of {} tiles of {} pixels into {}", self.width, self.height, self.format, tiles.len(), self.tile_size, base.display());
+        let time = Instant::now();
+        for (i, tile) in tiles.iter().enumerate() {
+            tile.render(&self.metadata)?;
+            rendering::print_progress_with_eta(i + 1, tiles.len(), "tiles", time.elapsed());
+        }
+        println!();
+        let (path, descriptor) = self.descriptor(base);
+        fs::write(&path, descriptor).map_err(|err| Error::io(&path, err))?;
+        println!("Saved the pyramid: {}", path.display());
+        Ok(())
+    }
+}
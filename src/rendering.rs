@@ -1,17 +1,18 @@
 //Temporary file to group together all rendering functionality
-use std::{time::Instant, thread, sync::{Arc, Mutex, atomic::{AtomicU8, Ordering}}, io::{self, Write}};
+use std::{time::Instant, thread, sync::{Arc, Mutex, atomic::{AtomicU8, Ordering}}, io::{self, Write}, time::Duration};
 
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{pixel_buffer::PixelBuffer, complex_plane::ComplexPlane, mandelbrot_set::MandelbrotSet, complex::Complex, coloring::TrueColor};
+use crate::{error::Error, pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane}, complex_plane::ComplexPlane, mandelbrot_set::MandelbrotSet, complex::Complex, coloring::{TrueColor, FloatColor, FloatColoringFunction, ColorChannelMapping, apply_palette_offset}};
 
 ///A box representing the area to render by rendering functions
-#[derive(Clone,Copy)]
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Serialize,Deserialize)]
 pub struct RenderBox {
-    min_x: usize,
-    max_x: usize,
-    min_y: usize,
-    max_y: usize
+    pub min_x: usize,
+    pub max_x: usize,
+    pub min_y: usize,
+    pub max_y: usize
 }
 
 impl RenderBox {
@@ -27,6 +28,18 @@ impl RenderBox {
         (self.max_x-self.min_x)*(self.max_y-self.min_y)
     }
 
+    ///Splits a pixel plane of `width` x `height` pixels into tiles of `tile_size` x `tile_size` pixels, row by row, the tiles at the right and bottom edges can be smaller
+    pub fn split(width: usize, height: usize, tile_size: usize) -> Vec<RenderBox> {
+        let tile_size = tile_size.max(1);
+        let mut tiles = Vec::new();
+        for min_y in (0..height).step_by(tile_size) {
+            for min_x in (0..width).step_by(tile_size) {
+                tiles.push(RenderBox::new(min_x, (min_x + tile_size).min(width), min_y, (min_y + tile_size).min(height)));
+            }
+        }
+        tiles
+    }
+
     ///Returns whether the point (x,y) is inside the `RenderBox`
     pub fn contains(&self, point: (usize, usize)) -> bool {
         !(point.0 < self.min_x || point.0 > self.max_x || point.1 < self.min_y || point.1 > self.max_y)
@@ -35,16 +48,20 @@ impl RenderBox {
 
 
 /// Render the Complex plane c into the 32-bit pixel buffer by applying the Mandelbrot formula iteratively to every Complex point mapped to a pixel in the buffer. 
+///
 /// The buffer should have a size of width*height.
 /// `orbit_radius` determines when Zn is considered to have gone to infinity.
 /// `max_iterations` concerns the maximum amount of times the Mandelbrot formula will be applied to each Complex number.
 /// Note: This function is computationally intensive, and should not be used for translations
-pub fn render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
     let render_box = RenderBox::new(0, p.pixel_plane.width, 0, p.pixel_plane.height);
-    render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount,coloring_function);
+    render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount,coloring_function)
 }
 
 /// Render the Complex plane c into the 32-bit pixel buffer by applying the Mandelbrot formula iteratively to every Complex point mapped to a pixel in the buffer. 
+///
 /// The buffer should have a size of width*height.
 /// Only renders Pixels inside the render box denoted by `render_min_x`, `render_max_x`, `render_min_y`, `render_max_y`
 /// `orbit_radius` determines when Zn is considered to have gone to infinity.
@@ -54,10 +71,47 @@ pub fn render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m
 /// * `coloring_function` - e.g. `TrueColor::new_from_hsv`
 /// # Panics 
 /// If `lock().unwrap()` panics
-pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
+    render_box.print();
+    render_into_buffer(p, c, m, render_box, None, supersampling_amount, coloring_function)
+}
+
+/// Render the Complex plane c into the 32-bit pixel buffer, skipping every pixel that was `kept`, e.g. by `PixelBuffer::resample`.
+///
+/// `kept` should have a size of width*height.
+/// Note: This function is multithreaded
+/// # Panics 
+/// If `lock().unwrap()` panics
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn render_remaining_complex_plane_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, kept: &[bool], supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
+    let render_box = RenderBox::new(0, p.pixel_plane.width, 0, p.pixel_plane.height);
+    let kept_count = kept.iter().filter(|&&is_kept| is_kept).count();
+    println!("Rendering {} pixels, reusing {} pixels", kept.len() - kept_count, kept_count);
+    render_into_buffer(p, c, m, render_box, Some(kept), supersampling_amount, coloring_function)
+}
+
+///Renders the pixels inside the `render_box` into the pixel buffer on the current thread.
+///
+///Unlike `render_box_render_complex_plane_into_buffer`, it does not spawn threads, e.g. when many small images are rendered in parallel
+pub fn render_box_into_buffer_on_current_thread(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+    let supersampling_amount = supersampling_amount.clamp(1, 64);
+    for y in render_box.min_y..render_box.max_y {
+        for x in render_box.min_x..render_box.max_x {
+            let index = p.point_to_index(x, y);
+            p.colors[index] = supersampled_color(c, m, (x, y), supersampling_amount, p.palette_offset, coloring_function);
+        }
+    }
+    p.update_pixels();
+}
+
+/// Renders the pixels inside the `render_box` that were not `kept` into the pixel buffer, see `render_box_render_complex_plane_into_buffer`.
+/// The rows rendered by the other threads are kept if a thread panicked
+fn render_into_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, kept: Option<&[bool]>, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
     let time = benchmark_start();
     let supersampling_amount = supersampling_amount.clamp(1, 64); //Supersampling_amount should be at least 1 and atmost 64
-    render_box.print();
     println!("SSAA: {}x", supersampling_amount);
     let chunk_size = p.pixel_plane.width;
     let chunks: Vec<Vec<TrueColor>> = p.colors.chunks(chunk_size).map(ToOwned::to_owned).collect();
@@ -69,6 +123,7 @@ pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &Comp
     let max_progress: u8 = 30;
     let chunks_len_over_max_progress = chunks_len / max_progress as usize;
     let current_progress_atomic: Arc<Mutex<AtomicU8>>= Arc::new(Mutex::new(AtomicU8::new(0)));
+    let kept: Option<Arc<Vec<bool>>> = kept.map(|kept| Arc::new(kept.to_vec()));
 
     for _thread_id in 0..amount_of_threads {
         let plane = (*c).clone();
@@ -77,6 +132,7 @@ pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &Comp
         let pixel_buffer = (*p).clone();
         let ms = (*m).clone();
         let atm = Arc::clone(&current_progress_atomic);
+        let thread_kept = kept.clone();
 
         let handle = thread::spawn(move || {
             let mut thread_chunks = Vec::new();
@@ -90,7 +146,7 @@ pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &Comp
                     return thread_chunks;
                 }
                 //println!("Thread[{}] takes chunk[{}]", thread_id, current_chunk);
-                if current_chunk % chunks_len_over_max_progress == 0 {
+                if current_chunk.is_multiple_of(chunks_len_over_max_progress) {
                     let current_progress = atm.lock().unwrap().load(Ordering::Relaxed);
                     print_progress_bar(current_progress, max_progress);
                     if current_progress < u8::MAX
@@ -104,24 +160,11 @@ pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &Comp
                 
                 for (i, pixel) in chunk.iter_mut().enumerate() {
                     let point = pixel_buffer.index_to_point(i + chunk_start);
-                    if !render_box.contains(point)
+                    if !render_box.contains(point) || thread_kept.as_ref().is_some_and(|kept| kept[i + chunk_start])
                     {
-                        continue; //Do not render Pixel points outside of the render box
+                        continue; //Do not render Pixel points outside of the render box, or Pixel points that are still valid
                     }
-                    let original_x: f64 = f64::from(point.0 as u32);
-                    let original_y: f64 = f64::from(point.1 as u32);
-                    //Supersampling, see: https://darkeclipz.github.io/fractals/paper/Fractals%20&%20Rendering%20Techniques.html
-                    let mut colors: Vec<TrueColor> = Vec::new();
-                    for _ in 0..supersampling_amount {
-                        let (random_x, random_y): (f64, f64) = rand::thread_rng().gen::<(f64,f64)>();
-                        let (x, y) : (f64, f64) = (original_x+random_x, original_y+random_y);
-                        let complex = plane.complex_from_pixel_plane(x, y);
-                        let iterations = ms.iterate(&complex);
-                        let color = coloring_function(iterations, ms.max_iterations);
-                        colors.push(color);    
-                    }
-                    let supersampled_color = TrueColor::average(&colors);
-                    *pixel = supersampled_color;
+                    *pixel = supersampled_color(&plane, &ms, point, supersampling_amount, pixel_buffer.palette_offset, coloring_function);
                 }
                 thread_chunks.push((current_chunk, chunk.clone()));
             }
@@ -129,65 +172,213 @@ pub fn render_box_render_complex_plane_into_buffer(p: &mut PixelBuffer, c: &Comp
         handles.push(handle);
     }
 
+    let mut panicked_threads = 0;
     for handle in handles {
-        let thread_chunks = handle.join().unwrap();
+        let Ok(thread_chunks) = handle.join() else {
+            panicked_threads += 1;
+            continue;
+        };
         for (i, chunk) in thread_chunks{
-            let mut index = i*p.pixel_plane.width;
-            for color in chunk {
-                p.colors[index] = color;
-                index+=1;
-            }
+            let start = i*p.pixel_plane.width;
+            p.colors[start..start+chunk.len()].copy_from_slice(&chunk);
         }
     }
     p.update_pixels();
+    p.render_time = Some(time.elapsed());
     println!();
-    benchmark("render_box_render_complex_plane_into_buffer()", time);
+    benchmark("render_into_buffer()", time);
+    if panicked_threads > 0 {
+        return Err(Error::Render(format!("{} of {} rendering threads panicked, the view is only partially rendered", panicked_threads, amount_of_threads)));
+    }
+    Ok(())
 }
 
-pub fn translate_and_render_complex_plane_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, rows: i128, columns: i128, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+///Returns the color of the pixel at `point` in the pixel plane of `c`, the average of `supersampling_amount` randomly placed samples inside the pixel
+fn supersampled_color(c: &ComplexPlane, m: &MandelbrotSet, point: (usize, usize), supersampling_amount: u8, palette_offset: u32, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> TrueColor {
+    supersample(c, m, point, supersampling_amount, palette_offset, coloring_function, TrueColor::average)
+}
+
+///Returns the average of `supersampling_amount` colors of randomly placed samples inside the pixel at `point` in the pixel plane of `c`, colored by `coloring_function` and averaged by `average`
+fn supersample<C>(c: &ComplexPlane, m: &MandelbrotSet, point: (usize, usize), supersampling_amount: u8, palette_offset: u32, coloring_function: fn(iterations: u32, max_iterations: u32) -> C, average: fn(&[C]) -> C) -> C {
+    let original_x: f64 = f64::from(point.0 as u32);
+    let original_y: f64 = f64::from(point.1 as u32);
+    //Supersampling, see: https://darkeclipz.github.io/fractals/paper/Fractals%20&%20Rendering%20Techniques.html
+    let mut colors: Vec<C> = Vec::new();
+    for _ in 0..supersampling_amount {
+        let (random_x, random_y): (f64, f64) = rand::thread_rng().gen::<(f64,f64)>();
+        let (x, y) : (f64, f64) = (original_x+random_x, original_y+random_y);
+        let complex = c.complex_from_pixel_plane(x, y);
+        let iterations = m.iterate(&complex);
+        let iterations = apply_palette_offset(iterations, m.max_iterations, palette_offset);
+        let color = coloring_function(iterations, m.max_iterations);
+        colors.push(color);
+    }
+    average(&colors)
+}
+
+///Renders the pixels inside the `render_box` into `samples`, the `N` samples per pixel returned by `render_pixel` for the point of the pixel, e.g. its RGB bytes.
+///
+///`samples` should have a size of `render_box.compute_pixel_count()` * `N`. The rows are divided over all CPU threads
+/// # Panics
+/// If `lock().unwrap()` panics
+pub fn render_box_into_samples<T: Send, const N: usize>(samples: &mut [T], render_box: RenderBox, render_pixel: impl Fn((usize, usize)) -> [T; N] + Sync) {
+    let row_size = (render_box.max_x - render_box.min_x) * N;
+    //Every thread takes the next row that has not been rendered yet
+    let rows = Mutex::new(samples.chunks_mut(row_size.max(1)).zip(render_box.min_y..render_box.max_y));
+    thread::scope(|scope| {
+        for _thread_id in 0..num_cpus::get() {
+            scope.spawn(|| loop {
+                let next_row = rows.lock().unwrap().next();
+                let Some((row, y)) = next_row else {
+                    return;
+                };
+                for (i, pixel) in row.chunks_mut(N).enumerate() {
+                    for (sample, value) in pixel.iter_mut().zip(render_pixel((render_box.min_x + i, y))) {
+                        *sample = value;
+                    }
+                }
+            });
+        }
+    });
+}
+
+///Renders the pixels of the pixel plane of `c` inside the `render_box` into `rgb`, as RGB bytes ordered by `color_channel_mapping`, without holding the rest of the image in memory.
+///
+///`rgb` should have a size of `render_box.compute_pixel_count()` * 3. The rows are divided over all CPU threads
+/// # Panics
+/// If `lock().unwrap()` panics
+#[allow(clippy::too_many_arguments)]
+pub fn render_box_into_rgb(rgb: &mut [u8], c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, supersampling_amount: u8, palette_offset: u32, color_channel_mapping: ColorChannelMapping, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+    let supersampling_amount = supersampling_amount.clamp(1, 64);
+    let (r_map, g_map, b_map) = color_channel_mapping.get_r_g_b_mapping();
+    render_box_into_samples(rgb, render_box, |point| {
+        let color = supersampled_color(c, m, point, supersampling_amount, palette_offset, coloring_function);
+        [color.get_color(r_map), color.get_color(g_map), color.get_color(b_map)]
+    });
+}
+
+///Renders the pixels of the pixel plane of `c` inside the `render_box` into `rgb` at full precision, as RGB floats ∈ [0.0, 1.0] ordered by `color_channel_mapping`.
+///
+///The colors of the SSAA samples are averaged as `FloatColor`s, so the result is not rounded to 8 bits, see `render_box_into_rgb`
+/// # Panics
+/// If `lock().unwrap()` panics
+#[allow(clippy::too_many_arguments)]
+pub fn render_box_into_float_rgb(rgb: &mut [f32], c: &ComplexPlane, m: &MandelbrotSet, render_box: RenderBox, supersampling_amount: u8, palette_offset: u32, color_channel_mapping: ColorChannelMapping, float_coloring_function: FloatColoringFunction) {
+    let supersampling_amount = supersampling_amount.clamp(1, 64);
+    let (r_map, g_map, b_map) = color_channel_mapping.get_r_g_b_mapping();
+    render_box_into_samples(rgb, render_box, |point| {
+        let color = supersample(c, m, point, supersampling_amount, palette_offset, float_coloring_function, FloatColor::average);
+        [color.get_color(r_map), color.get_color(g_map), color.get_color(b_map)]
+    });
+}
+
+///Translates the `PixelBuffer` `rows` rows and `columns` columns, only rendering the exposed rows or columns
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn translate_and_render_complex_plane_buffer(p: &mut PixelBuffer, c: &ComplexPlane, m: &MandelbrotSet, rows: i128, columns: i128, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
     println!("rows: {}, columns: {}",rows, columns);
     let max_x: usize = if columns > 0 {columns as usize} else {p.pixel_plane.width-1};
     let max_y: usize = if rows > 0 {rows as usize} else {p.pixel_plane.height-1};
     p.translate_buffer(rows, columns);
     if rows == 0 {
         let render_box = RenderBox::new((max_x as i128-columns.abs()) as usize, max_x, 0, p.pixel_plane.height);
-        render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount, coloring_function);
+        render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount, coloring_function)?;
     }
     else if columns == 0 {
         let render_box = RenderBox::new(0, p.pixel_plane.width, (max_y as i128 -rows.abs()) as usize, max_y);
-        render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount, coloring_function);
+        render_box_render_complex_plane_into_buffer(p, c, m, render_box, supersampling_amount, coloring_function)?;
     } else {
         println!("ERROR: translate_and_render_complex_plane_buffer() requires that rows == 0 || columns == 0");
     }
+    Ok(())
+}
+
+///Scales the view by `factor` around its center, by resampling the `PixelBuffer` and only rendering the pixels that could not be kept.
+///
+///`show_preview` is called with the resampled `PixelBuffer` before rendering, e.g. to show it in the window
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn scale_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, factor: f64, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor, show_preview: impl FnOnce(&PixelBuffer)) -> Result<(), Error> {
+    let center = (f64::from(p.pixel_plane.width as u32) / 2.0, f64::from(p.pixel_plane.height as u32) / 2.0);
+    let kept = p.resample(factor, center);
+    show_preview(p);
+    c.scale(factor);
+    render_remaining_complex_plane_into_buffer(p, c, m, &kept, supersampling_amount, coloring_function)
+}
+
+///Resizes the pixel plane to `width` x `height` pixels, keeping the center and scale of the view, by resizing the `PixelBuffer` and only rendering
+///the pixels that could not be kept.
+///
+///If only the height changes, only the exposed rows are rendered. If the width changes, the scale of a pixel changes, so most pixels are rendered
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn resize_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, width: usize, height: usize, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
+    let kept = p.resize(PixelPlane::new(width, height));
+    c.resize(width, height);
+    if kept.iter().all(|&is_kept| is_kept) {
+        return Ok(()); //Every pixel is still valid
+    }
+    render_remaining_complex_plane_into_buffer(p, c, m, &kept, supersampling_amount, coloring_function)
 }
 
 ///# Panics
 /// If `rows_up` != 0 && `columns_right` != 0
-pub fn translate_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, rows_up: i16, columns_right: i16, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn translate_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, rows_up: i16, columns_right: i16, supersampling_amount: u8,coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
     assert!(rows_up == 0 || columns_right == 0, "translate_and_render_efficiently: rows_up should be 0 or columns_right should be 0!");
 
     let row_sign: f64 = if rows_up > 0 {-1.0} else {1.0};
     let column_sign: f64 = if columns_right > 0 {1.0} else {-1.0};
-    c.translate(column_sign*c.pixels_to_real(columns_right.unsigned_abs() as u8), row_sign*c.pixels_to_imaginary(rows_up.unsigned_abs() as u8)); 
-    translate_and_render_complex_plane_buffer(p, c, m, rows_up.into(), (-columns_right).into(), supersampling_amount, coloring_function);
+    c.translate_along_view(column_sign*c.pixels_to_real(columns_right.unsigned_abs() as u8), row_sign*c.pixels_to_imaginary(rows_up.unsigned_abs() as u8)); 
+    translate_and_render_complex_plane_buffer(p, c, m, rows_up.into(), (-columns_right).into(), supersampling_amount, coloring_function)
 }
 
-pub fn translate_to_center_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, new_center: &Complex, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) {
-    let mut translation: Complex = new_center.subtract(&c.center());
+///Translates the view `rows_up` rows and `columns_right` columns, by translating the `PixelBuffer` and only rendering the exposed rows and columns.
+///
+///Translations of at least the size of the pixel plane are rendered completely
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn pan_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, rows_up: i32, columns_right: i32, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
+    if rows_up.unsigned_abs() as usize >= p.pixel_plane.height || columns_right.unsigned_abs() as usize >= p.pixel_plane.width {
+        c.translate_along_view(f64::from(columns_right) * c.increment_x, -f64::from(rows_up) * c.increment_y);
+        return render_complex_plane_into_buffer(p, c, m, supersampling_amount, coloring_function);
+    }
+    //translate_and_render_efficiently translates at most u8::MAX pixels at a time
+    let max_step = i32::from(u8::MAX);
+    let mut columns_right = columns_right;
+    while columns_right != 0 {
+        let step = columns_right.clamp(-max_step, max_step);
+        translate_and_render_efficiently(c, p, m, 0, step as i16, supersampling_amount, coloring_function)?;
+        columns_right -= step;
+    }
+    let mut rows_up = rows_up;
+    while rows_up != 0 {
+        let step = rows_up.clamp(-max_step, max_step);
+        translate_and_render_efficiently(c, p, m, step as i16, 0, supersampling_amount, coloring_function)?;
+        rows_up -= step;
+    }
+    Ok(())
+}
+
+///Translates the view to `new_center`, only rendering the exposed rows and columns
+/// # Errors
+/// Returns an Error if a rendering thread panicked
+pub fn translate_to_center_and_render_efficiently(c: &mut ComplexPlane, p: &mut PixelBuffer, m: &MandelbrotSet, new_center: &Complex, supersampling_amount: u8, coloring_function: fn(iterations: u32, max_iterations: u32) -> TrueColor) -> Result<(), Error> {
+    //Express the translation along the axes of the (rotated) view
+    let mut translation: Complex = c.unrotate(&new_center.subtract(&c.center()));
     //Mirror the y translation because the screen y is mirrored compared to the complex plane y axis
     translation.y = -translation.y;
 
     //Translate x, to the right
-    c.translate(translation.x, 0.0);
+    c.translate_along_view(translation.x, 0.0);
     let columns_right = -c.real_to_pixels(translation.x);
-    dbg!(columns_right);
-    translate_and_render_complex_plane_buffer(p, c, m, 0, columns_right.into(), supersampling_amount, coloring_function);
+    translate_and_render_complex_plane_buffer(p, c, m, 0, columns_right.into(), supersampling_amount, coloring_function)?;
 
     //Translate y, up
-    c.translate(0.0, translation.y);
+    c.translate_along_view(0.0, translation.y);
     let rows_up = -c.imaginary_to_pixels(translation.y);
-    dbg!(rows_up);
-    translate_and_render_complex_plane_buffer(p, c, m, rows_up.into(), 0, supersampling_amount, coloring_function);
+    translate_and_render_complex_plane_buffer(p, c, m, rows_up.into(), 0, supersampling_amount, coloring_function)
 }
 
 fn benchmark_start() -> Instant {
@@ -207,4 +398,29 @@ fn print_progress_bar(current_progress: u8, max_progress: u8) {
     }
     print!("]");
     io::stdout().flush().unwrap();
-}
\ No newline at end of file
+}
+
+///Copies `rgb`, the RGB bytes of the pixels inside `render_box` rendered using `ColorChannelMapping::RGB` by `render_box_into_rgb`, into the colors of `p`.
+///
+///The pixels of `p` should be updated afterwards, see `PixelBuffer::update_pixels`
+pub fn copy_rgb_into_buffer(p: &mut PixelBuffer, render_box: RenderBox, rgb: &[u8]) {
+    let width = (render_box.max_x - render_box.min_x).max(1);
+    for (i, pixel) in rgb.chunks_exact(3).enumerate() {
+        let index = p.point_to_index(render_box.min_x + i % width, render_box.min_y + i / width);
+        p.colors[index] = TrueColor::new(pixel[0], pixel[1], pixel[2]);
+    }
+}
+
+///Prints the progress over the current line: the percentage of the `total` amount of `unit`s that is `done`, the elapsed time and the estimated remaining time
+pub fn print_progress_with_eta(done: usize, total: usize, unit: &str, elapsed: Duration) {
+    let fraction = f64::from(done as u32) / f64::from(total.max(1) as u32);
+    let remaining = elapsed.mul_f64(1.0 / fraction - 1.0);
+    print!("\rProgress: {:5.1}% ({}/{} {}), elapsed: {}, ETA: {}  ", 100.0 * fraction, done, total, unit, format_duration(elapsed), format_duration(remaining));
+    io::stdout().flush().unwrap_or_default();
+}
+
+///Formats `duration` as hours, minutes and seconds, e.g. 1:02:03
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
@@ -0,0 +1,90 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, InteractionVariables, error::Error, coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::View, history::History, mandelbrot_set::MandelbrotSet};
+
+///The file in the output directory the viewer saves its session to on exit, and restores it from using --resume
+pub static SESSION_FILE_NAME: &str = "session.json";
+
+///Everything needed to reopen the viewer exactly where it was closed.
+///
+///Contains the window size, the view, the Mandelbrot set parameters, the rendering and coloring settings, the `InteractionVariables` and the navigation `History`
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub window_width: usize,
+    pub window_height: usize,
+    pub view: View,
+    pub mandelbrot_set: MandelbrotSet,
+    pub supersampling_amount: u8,
+    pub image_supersampling_amount: u8,
+    pub coloring_algorithm: ColoringAlgorithm,
+    pub color_channel_mapping: ColorChannelMapping,
+    pub palette_offset: u32,
+    pub vars: InteractionVariables,
+    pub hud_visible: bool,
+    #[serde(default)]
+    pub history: History,
+}
+
+impl Session {
+    ///Takes the `Session` of the application state `s`, leaving an empty navigation `History` behind
+    pub fn take(s: &mut AppState) -> Session {
+        //In fullscreen mode, the window size of the Config is the size of the window when it is not fullscreen
+        let (window_width, window_height) = if s.fullscreen {(s.config.window_width, s.config.window_height)} else {(s.p.pixel_plane.width, s.p.pixel_plane.height)};
+        Session {
+            window_width,
+            window_height,
+            view: s.c.get_view(),
+            mandelbrot_set: s.m.clone(),
+            supersampling_amount: s.supersampling_amount,
+            image_supersampling_amount: s.image_supersampling_amount,
+            coloring_algorithm: s.coloring_algorithm,
+            color_channel_mapping: s.p.color_channel_mapping,
+            palette_offset: s.p.palette_offset,
+            vars: s.vars.clone(),
+            hud_visible: s.hud.visible,
+            history: std::mem::take(&mut s.history),
+        }
+    }
+
+    ///Restores the `Session` into the application state `s`, except for the window size, which is used to create the window
+    pub fn restore(self, s: &mut AppState) {
+        s.c.set_view(&self.view);
+        s.m = self.mandelbrot_set;
+        s.supersampling_amount = self.supersampling_amount.clamp(1, 64);
+        s.image_supersampling_amount = self.image_supersampling_amount.clamp(1, 64);
+        s.coloring_algorithm = self.coloring_algorithm;
+        s.p.color_channel_mapping = self.color_channel_mapping;
+        s.p.palette_offset = self.palette_offset;
+        s.vars = self.vars;
+        s.hud.visible = self.hud_visible;
+        s.history = self.history;
+    }
+
+    ///Writes the `Session` to the JSON session file at `path`, creating its directory if it does not exist
+    /// # Errors
+    /// Returns an Error if the directory or the file cannot be written
+    pub fn save_to_file(&self, path: &str) -> Result<(), String> {
+        if let Some(directory) = std::path::Path::new(path).parent() {
+            fs::create_dir_all(directory).map_err(|err| format!("{}: {}", err, directory.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|err| format!("{}: {}", err, path))?;
+        fs::write(path, json).map_err(|err| format!("{}: {}", err, path))
+    }
+
+    ///Reads a `Session` from the JSON session file at `path`
+    /// # Errors
+    /// Returns an Error if the file cannot be read or is not a valid session file
+    pub fn read_from_file(path: &str) -> Result<Session, Error> {
+        let json = fs::read_to_string(path).map_err(|err| Error::io(Path::new(path), err))?;
+        let session: Session = serde_json::from_str(&json).map_err(|err| Error::Config(format!("Invalid session file: {}: {}", err, path)))?;
+        if session.window_width == 0 || session.window_height == 0 {
+            return Err(Error::Config(format!("The window of a session should be at least 1x1 pixels: {}", path)));
+        }
+        if !session.history.is_empty() && session.history.current().is_none() {
+            return Err(Error::Config(format!("The current view of a session's history should be one of its views: {}", path)));
+        }
+        Ok(session)
+    }
+}
@@ -0,0 +1,11 @@
+//!Helpers shared by the tests of several modules
+
+use std::{fs, path::PathBuf};
+
+///Returns an empty directory in the temporary directory, unique to the test `name`
+pub fn temporary_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("mandelbrot_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
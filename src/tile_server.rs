@@ -0,0 +1,300 @@
+use std::{fs, io::{BufRead, BufReader, Write}, net::{TcpListener, TcpStream}, path::{Path, PathBuf}, sync::{Arc, Mutex, atomic::{AtomicU64, Ordering}, mpsc}, thread, time::Duration};
+
+use crate::{coloring::{ColorChannelMapping, ColoringAlgorithm}, complex_plane::ComplexPlane, config::Config, mandelbrot_set::MandelbrotSet,
+    pixel_buffer::{PixelBuffer, pixel_plane::PixelPlane}, rendering::{self, RenderBox}};
+
+use self::tile_cache::{MAX_ZOOM, TileCache, TileKey, WORLD_CENTER, WORLD_SIZE};
+
+pub mod tile_cache;
+
+///Width and height of a tile in pixels
+pub static TILE_SIZE: usize = 256;
+///The amount of png tiles kept in memory
+pub static TILE_CACHE_CAPACITY: usize = 1024;
+///The HTML page with a slippy map of the tiles, served at /
+static INDEX_HTML: &str = include_str!("tile_server/index.html");
+///How long a connection may take to send its request before it is closed, so slow clients cannot keep every worker busy
+static READ_TIMEOUT: Duration = Duration::from_secs(10);
+///Numbers the temporary files of tiles written to the on-disk cache, so tiles that are written at the same time never share a temporary file
+static PARTIAL_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+///An HTTP response
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Response {
+        Response { status, content_type, body }
+    }
+
+    fn text(status: u16, text: &str) -> Response {
+        Response::new(status, "text/plain; charset=utf-8", text.as_bytes().to_vec())
+    }
+
+    ///Returns the reason phrase of the status code
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+///Renders the tiles of the quadtree of the complex plane as png images, see `TileKey`.
+///
+///Rendered tiles are kept in an in-memory LRU `TileCache`, and optionally in an on-disk cache directory
+pub struct TileServer {
+    m: MandelbrotSet,
+    supersampling_amount: u8,
+    coloring_algorithm: ColoringAlgorithm,
+    color_channel_mapping: ColorChannelMapping,
+    memory_cache: Mutex<TileCache>,
+    ///Directory of the on-disk cache, specific to the rendering settings
+    disk_cache: Option<PathBuf>,
+}
+
+impl TileServer {
+    ///Creates a `TileServer` rendering tiles with the given settings, caching them on disk in a subdirectory of `disk_cache` if it is Some
+    pub fn new(m: MandelbrotSet, supersampling_amount: u8, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping, disk_cache: Option<&str>) -> TileServer {
+        //Tiles rendered with other settings are cached in other directories
+        let settings = format!("{}_iterations_{}x_{:?}_{}", m.max_iterations, supersampling_amount, coloring_algorithm, color_channel_mapping);
+        let disk_cache = disk_cache.map(|directory| PathBuf::from(directory).join(settings));
+        TileServer { m, supersampling_amount, coloring_algorithm, color_channel_mapping, memory_cache: Mutex::new(TileCache::new(TILE_CACHE_CAPACITY)), disk_cache }
+    }
+
+    ///Returns the path of the tile `key` in the on-disk cache, if there is one
+    fn disk_cache_path(&self, key: &TileKey) -> Option<PathBuf> {
+        self.disk_cache.as_ref().map(|directory| directory.join(key.z.to_string()).join(format!("q{}.png", key.quadkey())))
+    }
+
+    ///Returns the png image of the tile `key`, from the in-memory cache, the on-disk cache, or else rendered
+    /// # Errors
+    /// Returns an Error if the tile cannot be encoded or written to the on-disk cache
+    /// # Panics
+    /// If the in-memory cache mutex is poisoned
+    pub fn tile(&self, key: &TileKey) -> Result<Vec<u8>, String> {
+        let cached = self.memory_cache.lock().unwrap().get(key);
+        if let Some(png) = cached {
+            return Ok(png);
+        }
+        let path = self.disk_cache_path(key);
+        let png = match path.as_ref().and_then(|path| fs::read(path).ok()) {
+            Some(png) => png,
+            None => {
+                let png = self.render_tile(key)?;
+                if let Some(path) = &path {
+                    TileServer::write_to_disk_cache(path, &png)?;
+                }
+                png
+            }
+        };
+        self.memory_cache.lock().unwrap().insert(*key, png.clone());
+        Ok(png)
+    }
+
+    ///Writes the png tile to `path` in the on-disk cache.
+    ///
+    ///The tile is first written to a temporary file and then renamed, so a crash never leaves a partial tile behind, and a tile that is being written is never read
+    fn write_to_disk_cache(path: &Path, png: &[u8]) -> Result<(), String> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory).map_err(|err| format!("{}: {}", err, directory.display()))?;
+        }
+        let partial_path = path.with_extension(format!("png.{}.part", PARTIAL_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)));
+        fs::write(&partial_path, png).map_err(|err| format!("{}: {}", err, partial_path.display()))?;
+        fs::rename(&partial_path, path).map_err(|err| {
+            let _ = fs::remove_file(&partial_path);
+            format!("{}: {}", err, path.display())
+        })
+    }
+
+    ///Renders the tile `key` on the current thread into a `PixelBuffer` of `TILE_SIZE` x `TILE_SIZE` pixels and encodes it as a png image.
+    ///
+    ///Tiles are rendered in parallel by the workers of `serve_connections`, so every tile is rendered on a single thread
+    fn render_tile(&self, key: &TileKey) -> Result<Vec<u8>, String> {
+        let mut c = ComplexPlane::new(TILE_SIZE, TILE_SIZE);
+        let mut p = PixelBuffer::new(PixelPlane::new(TILE_SIZE, TILE_SIZE));
+        p.color_channel_mapping = self.color_channel_mapping;
+        let view = key.view();
+        c.set_view(&view);
+        println!("Rendering tile {}/{}/{} (quadkey {:?})", key.z, key.x, key.y, key.quadkey());
+        rendering::render_box_into_buffer_on_current_thread(&mut p, &c, &self.m, RenderBox::new(0, TILE_SIZE, 0, TILE_SIZE), self.supersampling_amount, self.coloring_algorithm.coloring_function());
+        let mut png = Vec::new();
+        p.write_png(&mut png, &view, &self.m, self.supersampling_amount, self.coloring_algorithm)?;
+        Ok(png)
+    }
+
+    ///Returns the response to a GET request of `path`: the HTML page at /, or the tile at /{z}/{x}/{y}.png
+    pub fn respond(&self, path: &str) -> Response {
+        let path = path.split('?').next().unwrap_or_default();
+        if path == "/" || path == "/index.html" {
+            let html = INDEX_HTML.replace("%WORLD_CENTER_X%", &WORLD_CENTER.0.to_string()).replace("%WORLD_CENTER_Y%", &WORLD_CENTER.1.to_string())
+                .replace("%WORLD_SIZE%", &WORLD_SIZE.to_string()).replace("%MAX_ZOOM%", &MAX_ZOOM.to_string());
+            return Response::new(200, "text/html; charset=utf-8", html.into_bytes());
+        }
+        let Some(key) = TileKey::from_path(path) else {
+            return Response::text(404, &format!("Not found: {}", path));
+        };
+        match self.tile(&key) {
+            Ok(png) => Response::new(200, "image/png", png),
+            Err(err) => Response::text(500, &err),
+        }
+    }
+
+    ///Reads an HTTP request from `stream` and writes the response, closing the connection afterwards
+    fn handle_connection(&self, stream: &mut TcpStream) -> Result<(), String> {
+        stream.set_read_timeout(Some(READ_TIMEOUT)).map_err(|err| err.to_string())?;
+        let mut reader = BufReader::new(&*stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).map_err(|err| err.to_string())?;
+        //Skip the headers
+        let mut header = String::new();
+        while reader.read_line(&mut header).map_err(|err| err.to_string())? > 0 && !header.trim().is_empty() {
+            header.clear();
+        }
+        let mut parts = request_line.split_whitespace();
+        let response = match (parts.next(), parts.next()) {
+            (Some("GET"), Some(path)) => self.respond(path),
+            _ => Response::text(405, "Only GET requests are supported"),
+        };
+        let header = format!("HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", response.status, response.reason(), response.content_type, response.body.len());
+        stream.write_all(header.as_bytes()).and_then(|()| stream.write_all(&response.body)).map_err(|err| err.to_string())
+    }
+
+    ///Handles the connections to `listener` on `amount_of_workers` worker threads, every worker handles one connection at a time.
+    ///
+    ///Connections wait until a worker is available, so the amount of threads rendering tiles never exceeds `amount_of_workers`
+    /// # Panics
+    /// If the mutex of the connection queue is poisoned
+    pub fn serve_connections(self: Arc<Self>, listener: &TcpListener, amount_of_workers: usize) {
+        let (sender, receiver) = mpsc::channel::<TcpStream>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _worker_id in 0..amount_of_workers.max(1) {
+            let server = Arc::clone(&self);
+            let receiver = Arc::clone(&receiver);
+            thread::spawn(move || loop {
+                let next_stream = receiver.lock().unwrap().recv();
+                let Ok(mut stream) = next_stream else {
+                    return;
+                };
+                if let Err(err) = server.handle_connection(&mut stream) {
+                    eprintln!("Error: could not handle the connection: {}", err);
+                }
+            });
+        }
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if sender.send(stream).is_err() {
+                        eprintln!("Error: every worker stopped, no more connections can be handled");
+                        return;
+                    }
+                }
+                Err(err) => eprintln!("Error: could not accept the connection: {}", err),
+            }
+        }
+    }
+}
+
+///Serves the HTML page and the tiles rendered with the settings of `config` over HTTP at `address`, e.g. 127.0.0.1:8080, until the process is stopped
+/// # Errors
+/// Returns an Error if the server cannot listen at `address`
+pub fn serve(address: &str, config: &Config, coloring_algorithm: ColoringAlgorithm, color_channel_mapping: ColorChannelMapping) -> Result<(), String> {
+    let m = MandelbrotSet::new(config.max_iterations, config.orbit_radius);
+    let server = Arc::new(TileServer::new(m, config.supersampling_amount, coloring_algorithm, color_channel_mapping, config.tile_cache.as_deref()));
+    let listener = TcpListener::bind(address).map_err(|err| format!("{}: {}", err, address))?;
+    let local_address = listener.local_addr().map_err(|err| err.to_string())?;
+    println!("Serving the Mandelbrot set at http://{}/, tiles at http://{}/{{z}}/{{x}}/{{y}}.png", local_address, local_address);
+    server.serve_connections(&listener, num_cpus::get());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::test_utils::temporary_directory;
+
+    use super::*;
+
+    static PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    fn test_server(disk_cache: Option<&Path>) -> TileServer {
+        TileServer::new(MandelbrotSet::new(50, 2.0), 1, ColoringAlgorithm::HSV, ColorChannelMapping::RGB, disk_cache.and_then(Path::to_str))
+    }
+
+    #[test]
+    fn responds_with_the_status_of_the_request() {
+        let server = test_server(None);
+        let index = server.respond("/");
+        assert_eq!((index.status, index.content_type), (200, "text/html; charset=utf-8"));
+        assert!(!String::from_utf8(index.body).unwrap().contains("%MAX_ZOOM%"));
+        assert_eq!(server.respond("/index.html?v=2").status, 200);
+        let tile = server.respond("/1/1/0.png?v=2");
+        assert_eq!((tile.status, tile.content_type), (200, "image/png"));
+        assert!(tile.body.starts_with(&PNG_SIGNATURE));
+        for path in ["/favicon.ico", "/1/2/0.png", "/46/0/0.png", "/0/0/0"] {
+            let response = server.respond(path);
+            assert_eq!((response.status, response.reason()), (404, "Not Found"), "{}", path);
+        }
+    }
+
+    #[test]
+    fn responds_with_an_internal_server_error_if_the_tile_cannot_be_cached() {
+        let directory = temporary_directory("tile_cache_error");
+        let file = directory.join("file");
+        fs::write(&file, "not a directory").unwrap();
+        let response = test_server(Some(&file)).respond("/0/0/0.png");
+        assert_eq!((response.status, response.reason()), (500, "Internal Server Error"));
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn caches_tiles_on_disk_without_partial_files() {
+        let directory = temporary_directory("tile_cache");
+        let png = test_server(Some(&directory)).tile(&TileKey::new(2, 1, 3).unwrap()).unwrap();
+        let files: Vec<PathBuf> = fs::read_dir(directory.join("50_iterations_1x_HSV_RGB").join("2")).unwrap().map(|entry| entry.unwrap().path()).collect();
+        assert_eq!(files, [directory.join("50_iterations_1x_HSV_RGB").join("2").join("q23.png")]);
+        assert_eq!(fs::read(&files[0]).unwrap(), png);
+        //A new server reads the tile from the disk cache instead of rendering it again
+        fs::write(&files[0], b"cached").unwrap();
+        assert_eq!(test_server(Some(&directory)).respond("/2/1/3.png").body, b"cached");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    ///Sends `request` to the server at `address` and returns the status line and the body of the response
+    fn send(address: std::net::SocketAddr, request: &str) -> (String, Vec<u8>) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let header_end = response.windows(4).position(|window| window == b"\r\n\r\n").unwrap();
+        let header = String::from_utf8(response[..header_end].to_vec()).unwrap();
+        let content_length: usize = header.lines().find_map(|line| line.strip_prefix("Content-Length: ")).unwrap().parse().unwrap();
+        let body = response[header_end + 4..].to_vec();
+        assert_eq!(body.len(), content_length);
+        (header.lines().next().unwrap().to_string(), body)
+    }
+
+    #[test]
+    fn serves_tiles_over_http_on_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = Arc::new(test_server(None));
+        thread::spawn(move || server.serve_connections(&listener, 2));
+
+        //More concurrent requests than workers, they wait for a worker
+        let requests: Vec<_> = (0..4).map(|x| thread::spawn(move || send(address, &format!("GET /2/{}/1.png HTTP/1.1\r\nHost: localhost\r\n\r\n", x)))).collect();
+        for request in requests {
+            let (status, body) = request.join().unwrap();
+            assert_eq!(status, "HTTP/1.1 200 OK");
+            assert!(body.starts_with(&PNG_SIGNATURE));
+        }
+        assert_eq!(send(address, "GET /7/200/0.png HTTP/1.1\r\n\r\n").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(send(address, "POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\n").0, "HTTP/1.1 405 Method Not Allowed");
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::complex_plane::View;
+
+///Center of the square of the complex plane covered by the single tile at zoom level 0
+pub static WORLD_CENTER: (f64, f64) = (-0.75, 0.0);
+///Width and height of the square of the complex plane covered by the single tile at zoom level 0
+pub static WORLD_SIZE: f64 = 4.0;
+///The deepest zoom level, tiles are about 1e-14 wide, close to the precision of f64
+pub static MAX_ZOOM: u32 = 45;
+
+///A tile of the quadtree of the complex plane: at zoom level `z`, the world is split into 2^z x 2^z tiles, tile (`x`, `y`) counting from the top left.
+///
+///Every tile is split into four tiles at the next zoom level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TileKey {
+    pub z: u32,
+    pub x: u64,
+    pub y: u64,
+}
+
+impl TileKey {
+    ///Returns the `TileKey` of tile (`x`, `y`) at zoom level `z`
+    /// # Errors
+    /// Returns an Error if `z` is deeper than `MAX_ZOOM`, or the tile is outside of the world
+    pub fn new(z: u32, x: u64, y: u64) -> Result<TileKey, String> {
+        if z > MAX_ZOOM {
+            return Err(format!("The zoom level should be at most {}: {}", MAX_ZOOM, z));
+        }
+        if x >> z != 0 || y >> z != 0 {
+            return Err(format!("Tile ({}, {}) is outside of the world at zoom level {}", x, y, z));
+        }
+        Ok(TileKey { z, x, y })
+    }
+
+    ///Parses the `TileKey` of a tile path in the format `/{z}/{x}/{y}.png`, returns None if `path` is not a valid tile path
+    pub fn from_path(path: &str) -> Option<TileKey> {
+        let parts: Vec<&str> = path.strip_prefix('/')?.strip_suffix(".png")?.split('/').collect();
+        match parts.as_slice() {
+            [z, x, y] => TileKey::new(z.parse().ok()?, x.parse().ok()?, y.parse().ok()?).ok(),
+            _ => None,
+        }
+    }
+
+    ///Returns the quadtree key of the tile: a digit from 0 to 3 for every zoom level, picking the top left, top right, bottom left or bottom right quarter of the tile above it.
+    ///The tile at zoom level 0 has an empty quadkey
+    pub fn quadkey(&self) -> String {
+        (1..=self.z).rev().map(|level| {
+            let digit = ((self.x >> (level - 1)) & 1) + 2 * ((self.y >> (level - 1)) & 1);
+            char::from(b'0' + digit as u8)
+        }).collect()
+    }
+
+    ///Returns the `View` of the square of the complex plane covered by the tile
+    #[allow(clippy::cast_precision_loss)] //x and y are smaller than 2^MAX_ZOOM, which f64 represents exactly
+    pub fn view(&self) -> View {
+        let size = WORLD_SIZE * 0.5_f64.powi(i32::try_from(self.z).unwrap_or(i32::MAX));
+        let x = (self.x as f64 + 0.5).mul_add(size, WORLD_CENTER.0 - WORLD_SIZE / 2.0);
+        let y = (self.y as f64 + 0.5).mul_add(-size, WORLD_CENTER.1 + WORLD_SIZE / 2.0);
+        //A view with scale 1.0 is 2.5 wide, see ComplexPlane::new
+        View::new(x, y, size / 2.5)
+    }
+}
+
+///In-memory cache of encoded png tiles, evicting the least recently used tile when it holds more than `capacity` tiles
+pub struct TileCache {
+    capacity: usize,
+    ///Every tile and the time it was last used
+    tiles: HashMap<TileKey, (Vec<u8>, u64)>,
+    time: u64,
+}
+
+impl TileCache {
+    pub fn new(capacity: usize) -> TileCache {
+        TileCache { capacity, tiles: HashMap::new(), time: 0 }
+    }
+
+    ///Returns the cached png tile of `key`, marking it as used
+    pub fn get(&mut self, key: &TileKey) -> Option<Vec<u8>> {
+        self.time += 1;
+        let time = self.time;
+        self.tiles.get_mut(key).map(|(png, last_used)| {
+            *last_used = time;
+            png.clone()
+        })
+    }
+
+    ///Caches the png tile of `key`, evicting the least recently used tile if the cache is full
+    pub fn insert(&mut self, key: TileKey, png: Vec<u8>) {
+        self.time += 1;
+        self.tiles.insert(key, (png, self.time));
+        if self.tiles.len() > self.capacity {
+            if let Some(least_recently_used) = self.tiles.iter().min_by_key(|(_, (_, last_used))| *last_used).map(|(key, _)| *key) {
+                self.tiles.remove(&least_recently_used);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tile_paths() {
+        assert_eq!(TileKey::from_path("/0/0/0.png"), Some(TileKey { z: 0, x: 0, y: 0 }));
+        assert_eq!(TileKey::from_path("/3/5/2.png"), Some(TileKey { z: 3, x: 5, y: 2 }));
+        assert_eq!(TileKey::from_path("/45/0/35184372088831.png"), Some(TileKey { z: 45, x: 0, y: (1 << 45) - 1 }));
+    }
+
+    #[test]
+    fn rejects_invalid_tile_paths() {
+        for path in ["", "/", "/0/0/0", "0/0/0.png", "/0/0.png", "/0/0/0/0.png", "/a/0/0.png", "/1/-1/0.png", "/0/0/0.jpg", "/1/2/0.png", "/1/0/2.png", "/46/0/0.png"] {
+            assert_eq!(TileKey::from_path(path), None, "{}", path);
+        }
+        assert_eq!(TileKey::new(46, 0, 0).unwrap_err(), "The zoom level should be at most 45: 46");
+        assert_eq!(TileKey::new(2, 4, 0).unwrap_err(), "Tile (4, 0) is outside of the world at zoom level 2");
+    }
+
+    #[test]
+    fn quadkeys_pick_a_quarter_at_every_zoom_level() {
+        assert_eq!(TileKey::new(0, 0, 0).unwrap().quadkey(), "");
+        assert_eq!(TileKey::new(1, 1, 0).unwrap().quadkey(), "1");
+        assert_eq!(TileKey::new(1, 0, 1).unwrap().quadkey(), "2");
+        assert_eq!(TileKey::new(3, 5, 2).unwrap().quadkey(), "121");
+        assert_eq!(TileKey::new(3, 7, 7).unwrap().quadkey(), "333");
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn tiles_split_the_world_into_quarters() {
+        let world = TileKey::new(0, 0, 0).unwrap().view();
+        assert_eq!((world.x, world.y, world.scale), (WORLD_CENTER.0, WORLD_CENTER.1, WORLD_SIZE / 2.5));
+        let top_left = TileKey::new(1, 0, 0).unwrap().view();
+        assert_eq!((top_left.x, top_left.y, top_left.scale), (WORLD_CENTER.0 - WORLD_SIZE / 4.0, WORLD_CENTER.1 + WORLD_SIZE / 4.0, world.scale / 2.0));
+        let bottom_right = TileKey::new(1, 1, 1).unwrap().view();
+        assert_eq!((bottom_right.x, bottom_right.y), (WORLD_CENTER.0 + WORLD_SIZE / 4.0, WORLD_CENTER.1 - WORLD_SIZE / 4.0));
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_tile() {
+        let keys: Vec<TileKey> = (0..4).map(|x| TileKey::new(2, x, 0).unwrap()).collect();
+        let mut cache = TileCache::new(2);
+        assert!(cache.is_empty());
+        cache.insert(keys[0], vec![0]);
+        cache.insert(keys[1], vec![1]);
+        //Using the first tile makes the second tile the least recently used
+        assert_eq!(cache.get(&keys[0]), Some(vec![0]));
+        cache.insert(keys[2], vec![2]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&keys[1]), None);
+        assert_eq!(cache.get(&keys[0]), Some(vec![0]));
+        assert_eq!(cache.get(&keys[2]), Some(vec![2]));
+        //Replacing a cached tile does not evict another tile
+        cache.insert(keys[2], vec![3]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&keys[2]), Some(vec![3]));
+        cache.insert(keys[3], vec![4]);
+        assert_eq!(cache.get(&keys[0]), None);
+    }
+}
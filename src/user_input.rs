@@ -1,5 +1,7 @@
 use std::{str::FromStr, fmt::Display, io::{self, Write}};
 
+pub mod dialog;
+
 /// Ask the user for `result` named `name` from stdin. If `result` can be parsed to a `T`, return `result`. In any other case,
 /// call `ask` again.
 /// # Panics
@@ -22,6 +24,8 @@ pub fn ask<T: FromStr + Display>(name: &str) -> T
 }
 
 ///Reads a line from stdin, does not trim the input
+/// # Panics
+/// If the user's input cannot be read from stdin
 pub fn get_user_input_untrimmed() -> String {
     let mut user_input = String::new();
     // Read the user's input from the standard input stdin
@@ -0,0 +1,188 @@
+use std::{cell::RefCell, fmt::Display, rc::Rc, str::FromStr};
+
+use minifb::{InputCallback, Key};
+
+use crate::{AppState, pixel_buffer::overlay::{Overlay, font::{self, LINE_HEIGHT}}};
+
+//Colors of the dialog title, input, selected option and error message
+static TITLE_COLOR: u32 = 0x00FF_D700;
+static INPUT_COLOR: u32 = 0x00FF_FFFF;
+static SELECTED_COLOR: u32 = 0x0000_FFFF;
+static ERROR_COLOR: u32 = 0x00FF_5050;
+//Distance of the dialog to the left edge of the window in pixels
+static DIALOG_MARGIN: usize = 8;
+
+///The characters typed into the window, collected by the minifb input callback until they are taken
+#[derive(Clone, Default)]
+pub struct TypedCharacters(Rc<RefCell<Vec<char>>>);
+
+impl TypedCharacters {
+    pub fn new() -> TypedCharacters {
+        TypedCharacters::default()
+    }
+
+    ///Returns the characters typed since they were last taken
+    pub fn take(&self) -> Vec<char> {
+        self.0.take()
+    }
+}
+
+impl InputCallback for TypedCharacters {
+    fn add_char(&mut self, uni_char: u32) {
+        if let Some(character) = char::from_u32(uni_char).filter(|character| !character.is_control()) {
+            self.0.borrow_mut().push(character);
+        }
+    }
+}
+
+///Submits the text of a `Dialog`, returns an Error if the text is not valid
+type SubmitText = Box<dyn Fn(&mut AppState, &str) -> Result<(), String>>;
+///Submits the index of the option picked in a `Dialog`
+type SubmitOption = Box<dyn Fn(&mut AppState, usize)>;
+
+///What a `Dialog` asks the user for
+enum DialogInput {
+    ///A line of text, submitted to `submit`, which returns an Error if the text is not valid
+    Text { text: String, submit: SubmitText },
+    ///One of the `options`, the index of the picked option is submitted to `submit`
+    Options { options: Vec<String>, selected: usize, submit: SubmitOption },
+}
+
+///The state of a `Dialog` after handling the user's input, see `Dialog::handle_input`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialogState {
+    Open,
+    ///The input was valid and has been submitted
+    Submitted,
+    ///The `Dialog` was cancelled by pressing Escape
+    Cancelled,
+}
+
+///Non-blocking text input or option list drawn over the window, replacing the stdin prompts of `ask` and `pick_option`.
+///
+///Enter submits the input, Escape cancels the `Dialog`. Invalid input is shown as an error and can be corrected
+pub struct Dialog {
+    title: String,
+    input: DialogInput,
+    ///Error message shown when the submitted input was not valid
+    error: Option<String>,
+    ///Whether the view should be rendered after the input has been submitted
+    pub rerender: bool,
+    overlay: Overlay,
+}
+
+impl Dialog {
+    ///Asks the user for a value named `name` that can be parsed to a `T`, and runs `on_value` with it, see `ask`
+    pub fn ask<T: FromStr, F: Fn(&mut AppState, T) + 'static>(name: &str, on_value: F) -> Dialog
+        where <T as FromStr>::Err: Display {
+        let submit = move |s: &mut AppState, text: &str| {
+            let value = text.trim().parse::<T>().map_err(|err| format!("Error: {}: {}", err, text))?;
+            on_value(s, value);
+            Ok(())
+        };
+        let input = DialogInput::Text { text: String::new(), submit: Box::new(submit) };
+        Dialog { title: format!("Enter the {}:", name), input, error: None, rerender: false, overlay: Overlay::new() }
+    }
+
+    ///Lets the user pick one of the `options`, and runs `on_pick` with the value of the picked option, see `pick_option`
+    pub fn pick_option<T: Copy + 'static, F: Fn(&mut AppState, T) + 'static>(name: &str, options: &[(&str, T)], on_pick: F) -> Dialog {
+        let values: Vec<T> = options.iter().map(|(_, value)| *value).collect();
+        let input = DialogInput::Options {
+            options: options.iter().map(|(option, _)| String::from(*option)).collect(),
+            selected: 0,
+            submit: Box::new(move |s, index| on_pick(s, values[index])),
+        };
+        Dialog { title: format!("Pick the {}:", name), input, error: None, rerender: false, overlay: Overlay::new() }
+    }
+
+    ///Fills in `text` as the initial input of a text input `Dialog`, e.g. the current value
+    pub fn with_text(mut self, text: &impl Display) -> Dialog {
+        if let DialogInput::Text { text: input, .. } = &mut self.input {
+            *input = text.to_string();
+        }
+        self
+    }
+
+    ///Makes the `Dialog` render the view after its input has been submitted
+    pub fn rendering(mut self) -> Dialog {
+        self.rerender = true;
+        self
+    }
+
+    ///Handles the `characters` typed and the `keys` pressed in the window, submitting the input to the application state `s` when Enter is pressed.
+    ///
+    ///A text input accepts the typed characters and Backspace. An option list is navigated using Up and Down, typing the number of an option picks it
+    pub fn handle_input(&mut self, s: &mut AppState, characters: &[char], keys: &[Key]) -> DialogState {
+        if keys.contains(&Key::Escape) {
+            return DialogState::Cancelled;
+        }
+        let enter = keys.contains(&Key::Enter) || keys.contains(&Key::NumPadEnter);
+        match &mut self.input {
+            DialogInput::Text { text, submit } => {
+                text.extend(characters);
+                if keys.contains(&Key::Backspace) {
+                    text.pop();
+                }
+                if enter {
+                    match submit(s, text) {
+                        Ok(()) => return DialogState::Submitted,
+                        Err(err) => self.error = Some(err),
+                    }
+                }
+            }
+            DialogInput::Options { options, selected, submit } => {
+                if keys.contains(&Key::Up) {
+                    *selected = selected.saturating_sub(1);
+                }
+                if keys.contains(&Key::Down) {
+                    *selected = (*selected + 1).min(options.len().saturating_sub(1));
+                }
+                let typed = characters.iter().filter_map(|character| character.to_digit(10)).map(|digit| digit as usize).find(|index| *index < options.len());
+                if let Some(index) = typed {
+                    *selected = index;
+                }
+                if (enter || typed.is_some()) && *selected < options.len() {
+                    submit(s, *selected);
+                    return DialogState::Submitted;
+                }
+            }
+        }
+        DialogState::Open
+    }
+
+    ///Rebuilds the `Dialog` for a window of `width` x `height` pixels, it is drawn at a third of the window height
+    pub fn update(&mut self, width: usize, height: usize) {
+        self.overlay.clear();
+        let scale = font::scale_for_width(width);
+        for (i, (line, color)) in self.lines().iter().enumerate() {
+            self.overlay.add_text((DIALOG_MARGIN, height / 3 + i * LINE_HEIGHT * scale), line, *color, scale);
+        }
+    }
+
+    ///Draws the `Dialog` over `pixels`, an image of `width` x `height` pixels
+    pub fn draw(&self, pixels: &mut [u32], width: usize, height: usize) {
+        self.overlay.draw(pixels, width, height);
+    }
+
+    ///Returns the lines of the `Dialog` and their colors: the title with instructions, the input or options, and the error message if any
+    pub fn lines(&self) -> Vec<(String, u32)> {
+        let mut lines = Vec::new();
+        match &self.input {
+            DialogInput::Text { text, .. } => {
+                lines.push((format!("{} Enter confirms, Escape cancels", self.title), TITLE_COLOR));
+                lines.push((format!("> {}_", text), INPUT_COLOR));
+            }
+            DialogInput::Options { options, selected, .. } => {
+                lines.push((format!("{} Up and Down select, Enter confirms, Escape cancels", self.title), TITLE_COLOR));
+                for (i, option) in options.iter().enumerate() {
+                    let (marker, color) = if i == *selected {(">", SELECTED_COLOR)} else {(" ", INPUT_COLOR)};
+                    lines.push((format!("{} [{}]: {}", marker, i, option), color));
+                }
+            }
+        }
+        if let Some(error) = &self.error {
+            lines.push((error.clone(), ERROR_COLOR));
+        }
+        lines
+    }
+}